@@ -0,0 +1,12 @@
+//! Quantitative measures of the patterns a simulation produces. Everything
+//! here works on a [`crate::Field`], so it runs the same on a grid read back
+//! from the GPU or produced headless by the CPU backend.
+
+pub mod segmentation;
+pub mod spectrum;
+
+pub use segmentation::{
+    AreaStatistics, Component, ComponentShape, MitosisTracker, PatternClass, Segmentation,
+    SegmentationOptions, SpotCountChange,
+};
+pub use spectrum::{Anisotropy, PowerSpectrum, RadialProfile, SpectralAnalysis, SpectrumWindow};
@@ -0,0 +1,395 @@
+use crate::field::Field;
+
+/// V concentration above which a cell counts as part of the pattern.
+pub const DEFAULT_THRESHOLD: f32 = 0.2;
+
+/// Components at least this many times longer than they are wide count as
+/// stripes.
+const STRIPE_ELONGATION: f32 = 3.0;
+
+/// Components with a compactness up to this count as spots. A digital disc
+/// comes out around 1.6, as its perimeter is measured along cell edges.
+const SPOT_COMPACTNESS: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentationOptions {
+    pub threshold: f32,
+    /// Whether components continue across the edges of the grid, as they do in
+    /// the simulation. Turn this off for a region cut out of a larger grid.
+    pub periodic: bool,
+}
+
+impl Default for SegmentationOptions {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+            periodic: true,
+        }
+    }
+}
+
+/// One connected region of cells above the threshold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    /// The label its cells carry in [`Segmentation::labels`], counting from 1.
+    pub label: u32,
+    /// Number of cells.
+    pub area: usize,
+    /// Centre of mass in cells, wrapped into the grid.
+    pub centroid: (f32, f32),
+    /// Number of cell edges between the component and the background.
+    pub perimeter: usize,
+    /// Ratio of the longest to the shortest axis of the best-fitting ellipse.
+    /// Infinite for a component that wraps all the way around the grid.
+    pub elongation: f32,
+    /// Whether the component joins up with itself across the periodic boundary,
+    /// like a stripe that runs the whole way around.
+    pub wraps: bool,
+    pub shape: ComponentShape,
+}
+
+impl Component {
+    /// The squared perimeter over `4π` times the area: 1 for a perfect circle
+    /// and growing as the outline winds and branches.
+    pub fn compactness(&self) -> f32 {
+        (self.perimeter * self.perimeter) as f32 / (4.0 * std::f32::consts::PI * self.area as f32)
+    }
+}
+
+/// The rough shape of a single component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentShape {
+    /// A compact blob.
+    Spot,
+    /// A band, either elongated or wrapping around the grid in one direction.
+    Stripe,
+    /// A winding or branching region, or one that wraps around the grid in
+    /// more than one direction.
+    Network,
+}
+
+/// What kind of pattern a segmentation shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternClass {
+    /// Nothing is above the threshold; the pattern has died out.
+    Empty,
+    /// Everything is above the threshold.
+    Uniform,
+    /// Separate, compact blobs.
+    Spots,
+    /// Separate, elongated bands.
+    Stripes,
+    /// Winding, branching networks.
+    Labyrinth,
+}
+
+impl PatternClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatternClass::Empty => "Empty",
+            PatternClass::Uniform => "Uniform",
+            PatternClass::Spots => "Spots",
+            PatternClass::Stripes => "Stripes",
+            PatternClass::Labyrinth => "Labyrinth",
+        }
+    }
+}
+
+/// Summary of the component areas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaStatistics {
+    pub min: usize,
+    pub max: usize,
+    pub mean: f32,
+    pub median: f32,
+}
+
+/// The V field thresholded and split into 4-connected components.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segmentation {
+    pub width: usize,
+    pub height: usize,
+    pub step_count: u64,
+    /// The component label of every cell, 0 for background.
+    pub labels: Vec<u32>,
+    pub components: Vec<Component>,
+}
+
+impl Segmentation {
+    pub fn of_v(field: &Field, options: SegmentationOptions) -> Self {
+        let (width, height) = (field.width, field.height);
+        let foreground: Vec<bool> = field.v_values().map(|v| v > options.threshold).collect();
+        let mut labels = vec![0u32; width * height];
+        // Where each labelled cell was reached from its component's first cell,
+        // without wrapping, so a component can be measured across the edges
+        let mut unwrapped = vec![(0i64, 0i64); width * height];
+        let mut components = Vec::new();
+        let mut stack = Vec::new();
+
+        for start in 0..width * height {
+            if !foreground[start] || labels[start] != 0 {
+                continue;
+            }
+
+            let label = components.len() as u32 + 1;
+            let origin = ((start % width) as i64, (start / width) as i64);
+            labels[start] = label;
+            unwrapped[start] = origin;
+            stack.push(start);
+
+            let mut moments = Moments::default();
+            let mut perimeter = 0;
+            // The first way found around the grid, and whether there is
+            // another independent of it
+            let mut winding: Option<(i64, i64)> = None;
+            let mut winds_twice = false;
+            while let Some(index) = stack.pop() {
+                let (ux, uy) = unwrapped[index];
+                moments.add(ux as f64, uy as f64);
+
+                for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let (nx, ny) = (ux + dx, uy + dy);
+                    let (wx, wy) = (nx.rem_euclid(width as i64), ny.rem_euclid(height as i64));
+                    if !options.periodic && (wx != nx || wy != ny) {
+                        perimeter += 1;
+                        continue;
+                    }
+                    let neighbour = wy as usize * width + wx as usize;
+                    if !foreground[neighbour] {
+                        perimeter += 1;
+                        continue;
+                    }
+                    if labels[neighbour] == 0 {
+                        labels[neighbour] = label;
+                        unwrapped[neighbour] = (nx, ny);
+                        stack.push(neighbour);
+                    } else if unwrapped[neighbour] != (nx, ny) {
+                        // Reached again by going around the grid
+                        let (px, py) = unwrapped[neighbour];
+                        let offset = (nx - px, ny - py);
+                        match winding {
+                            None => winding = Some(offset),
+                            Some((x, y)) => winds_twice |= x * offset.1 != y * offset.0,
+                        }
+                    }
+                }
+            }
+
+            let wraps = winding.is_some();
+            let elongation = if wraps {
+                f32::INFINITY
+            } else {
+                moments.elongation()
+            };
+            let (mean_x, mean_y) = moments.mean();
+            let mut component = Component {
+                label,
+                area: moments.count as usize,
+                centroid: if wraps {
+                    circular_centroid(&labels, label, width, height)
+                } else {
+                    (
+                        mean_x.rem_euclid(width as f64) as f32,
+                        mean_y.rem_euclid(height as f64) as f32,
+                    )
+                },
+                perimeter,
+                elongation,
+                wraps,
+                shape: ComponentShape::Spot,
+            };
+            component.shape = if winds_twice {
+                ComponentShape::Network
+            } else if elongation >= STRIPE_ELONGATION {
+                ComponentShape::Stripe
+            } else if component.compactness() <= SPOT_COMPACTNESS {
+                ComponentShape::Spot
+            } else {
+                ComponentShape::Network
+            };
+            components.push(component);
+        }
+
+        Self {
+            width,
+            height,
+            step_count: field.step_count,
+            labels,
+            components,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Number of cells above the threshold.
+    pub fn foreground_area(&self) -> usize {
+        self.components.iter().map(|c| c.area).sum()
+    }
+
+    pub fn area_statistics(&self) -> Option<AreaStatistics> {
+        let mut areas: Vec<usize> = self.components.iter().map(|c| c.area).collect();
+        areas.sort_unstable();
+        let count = areas.len();
+        if count == 0 {
+            return None;
+        }
+        let median = if count % 2 == 1 {
+            areas[count / 2] as f32
+        } else {
+            (areas[count / 2 - 1] + areas[count / 2]) as f32 / 2.0
+        };
+        Some(AreaStatistics {
+            min: areas[0],
+            max: areas[count - 1],
+            mean: areas.iter().sum::<usize>() as f32 / count as f32,
+            median,
+        })
+    }
+
+    /// Classifies the pattern by the shape covering the most area, so a
+    /// scattering of specks doesn't outvote the stripes around it.
+    pub fn classify(&self) -> PatternClass {
+        let foreground = self.foreground_area();
+        if foreground == 0 {
+            return PatternClass::Empty;
+        }
+        if foreground == self.width * self.height {
+            return PatternClass::Uniform;
+        }
+
+        let area_of = |shape: ComponentShape| -> usize {
+            self.components
+                .iter()
+                .filter(|c| c.shape == shape)
+                .map(|c| c.area)
+                .sum()
+        };
+        let spots = area_of(ComponentShape::Spot);
+        let stripes = area_of(ComponentShape::Stripe);
+        let networks = area_of(ComponentShape::Network);
+        if spots >= stripes && spots >= networks {
+            PatternClass::Spots
+        } else if stripes >= networks {
+            PatternClass::Stripes
+        } else {
+            PatternClass::Labyrinth
+        }
+    }
+}
+
+#[derive(Default)]
+struct Moments {
+    count: u64,
+    sum_x: f64,
+    sum_y: f64,
+    sum_xx: f64,
+    sum_xy: f64,
+    sum_yy: f64,
+}
+
+impl Moments {
+    fn add(&mut self, x: f64, y: f64) {
+        self.count += 1;
+        self.sum_x += x;
+        self.sum_y += y;
+        self.sum_xx += x * x;
+        self.sum_xy += x * y;
+        self.sum_yy += y * y;
+    }
+
+    fn mean(&self) -> (f64, f64) {
+        let n = self.count.max(1) as f64;
+        (self.sum_x / n, self.sum_y / n)
+    }
+
+    fn elongation(&self) -> f32 {
+        let n = self.count.max(1) as f64;
+        let (mean_x, mean_y) = self.mean();
+        // A cell is a unit square, not a point, which keeps single-cell-wide
+        // lines from having zero width
+        let xx = self.sum_xx / n - mean_x * mean_x + 1.0 / 12.0;
+        let xy = self.sum_xy / n - mean_x * mean_y;
+        let yy = self.sum_yy / n - mean_y * mean_y + 1.0 / 12.0;
+        let spread = (((xx - yy) / 2.0).powi(2) + xy * xy).sqrt();
+        let major = (xx + yy) / 2.0 + spread;
+        let minor = ((xx + yy) / 2.0 - spread).max(f64::EPSILON);
+        (major / minor).sqrt() as f32
+    }
+}
+
+/// The centroid of a component that wraps around the grid, as the circular
+/// mean of its cells along each axis.
+fn circular_centroid(labels: &[u32], label: u32, width: usize, height: usize) -> (f32, f32) {
+    use std::f64::consts::TAU;
+    let (mut cx, mut sx, mut cy, mut sy) = (0.0, 0.0, 0.0, 0.0);
+    for (i, _) in labels.iter().enumerate().filter(|(_, l)| **l == label) {
+        let ax = TAU * (i % width) as f64 / width as f64;
+        let ay = TAU * (i / width) as f64 / height as f64;
+        cx += ax.cos();
+        sx += ax.sin();
+        cy += ay.cos();
+        sy += ay.sin();
+    }
+    let wrap = |angle: f64, size: usize| (angle.rem_euclid(TAU) / TAU * size as f64) as f32;
+    (wrap(sx.atan2(cx), width), wrap(sy.atan2(cy), height))
+}
+
+/// A change in the number of spots between two segmentations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpotCountChange {
+    /// Spots split, as in mitosis.
+    Divided {
+        step_count: u64,
+        from: usize,
+        to: usize,
+    },
+    /// Spots merged or died.
+    Vanished {
+        step_count: u64,
+        from: usize,
+        to: usize,
+    },
+}
+
+/// Follows the spot count over time to pick out divisions.
+#[derive(Debug, Clone, Default)]
+pub struct MitosisTracker {
+    /// Step count and spot count of every segmentation recorded, oldest first.
+    pub history: Vec<(u64, usize)>,
+    /// Total spots gained through divisions since tracking started.
+    pub divisions: usize,
+}
+
+impl MitosisTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a segmentation, reporting how the count changed since the last one.
+    pub fn record(&mut self, segmentation: &Segmentation) -> Option<SpotCountChange> {
+        let step_count = segmentation.step_count;
+        let count = segmentation.count();
+        let previous = self.history.last().map(|&(_, count)| count);
+        self.history.push((step_count, count));
+
+        let from = previous?;
+        match count.cmp(&from) {
+            std::cmp::Ordering::Greater => {
+                self.divisions += count - from;
+                Some(SpotCountChange::Divided {
+                    step_count,
+                    from,
+                    to: count,
+                })
+            }
+            std::cmp::Ordering::Less => Some(SpotCountChange::Vanished {
+                step_count,
+                from,
+                to: count,
+            }),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
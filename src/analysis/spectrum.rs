@@ -0,0 +1,274 @@
+use crate::field::Field;
+use rustfft::FftPlanner;
+use rustfft::num_complex::Complex;
+use std::f32::consts::PI;
+
+/// A taper applied to the values before transforming them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpectrumWindow {
+    /// No taper. Right for a whole grid, which wraps around seamlessly.
+    #[default]
+    None,
+    /// A 2D Hann window, which suppresses the leakage caused by the hard edges
+    /// of a region cut out of a larger grid.
+    Hann,
+}
+
+/// The 2D power spectrum of one species, with the mean removed so the zero
+/// frequency term doesn't swamp everything else.
+///
+/// Frequencies are in cycles per cell. Power is stored in FFT order, with the
+/// zero frequency at index `(0, 0)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerSpectrum {
+    pub width: usize,
+    pub height: usize,
+    power: Vec<f32>,
+}
+
+impl PowerSpectrum {
+    /// The spectrum of the V concentration of a whole, periodic field.
+    pub fn of_v(field: &Field) -> Self {
+        let values: Vec<f32> = field.v_values().collect();
+        Self::from_values(field.width, field.height, &values, SpectrumWindow::None)
+    }
+
+    /// The spectrum of `width` by `height` values stored row by row.
+    pub fn from_values(
+        width: usize,
+        height: usize,
+        values: &[f32],
+        window: SpectrumWindow,
+    ) -> Self {
+        assert_eq!(
+            values.len(),
+            width * height,
+            "Values length must match the spectrum dimensions"
+        );
+
+        let mean = values.iter().sum::<f32>() / values.len().max(1) as f32;
+        let hann = |i: usize, n: usize| 0.5 - 0.5 * (2.0 * PI * i as f32 / n as f32).cos();
+        let mut buffer: Vec<Complex<f32>> = values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| {
+                let taper = match window {
+                    SpectrumWindow::None => 1.0,
+                    SpectrumWindow::Hann => hann(i % width, width) * hann(i / width, height),
+                };
+                Complex::new((value - mean) * taper, 0.0)
+            })
+            .collect();
+
+        // Transform the rows, then the columns by way of a transpose
+        let mut planner = FftPlanner::new();
+        planner.plan_fft_forward(width).process(&mut buffer);
+        let mut transposed = vec![Complex::new(0.0, 0.0); buffer.len()];
+        for y in 0..height {
+            for x in 0..width {
+                transposed[x * height + y] = buffer[y * width + x];
+            }
+        }
+        planner.plan_fft_forward(height).process(&mut transposed);
+
+        let normalization = (width * height) as f32;
+        let mut power = vec![0.0; buffer.len()];
+        for x in 0..width {
+            for y in 0..height {
+                power[y * width + x] = transposed[x * height + y].norm_sqr() / normalization;
+            }
+        }
+
+        Self {
+            width,
+            height,
+            power,
+        }
+    }
+
+    /// Power at frequency index `(kx, ky)`, in FFT order.
+    pub fn power(&self, kx: usize, ky: usize) -> f32 {
+        self.power[ky * self.width + kx]
+    }
+
+    /// The signed frequencies, in cycles per cell, of index `(kx, ky)`.
+    pub fn frequency(&self, kx: usize, ky: usize) -> (f32, f32) {
+        (
+            signed_frequency(kx, self.width),
+            signed_frequency(ky, self.height),
+        )
+    }
+
+    /// Power averaged over rings of equal wavenumber.
+    pub fn radial_profile(&self) -> RadialProfile {
+        let bin_width = 1.0 / self.width.min(self.height) as f32;
+        // Rings out to the Nyquist frequency of the coarser axis are complete
+        let bin_count = (0.5 / bin_width).round() as usize + 1;
+        let mut sums = vec![0.0f64; bin_count];
+        let mut counts = vec![0u32; bin_count];
+        for ky in 0..self.height {
+            for kx in 0..self.width {
+                let (fx, fy) = self.frequency(kx, ky);
+                let bin = ((fx * fx + fy * fy).sqrt() / bin_width).round() as usize;
+                if bin < bin_count {
+                    sums[bin] += self.power(kx, ky) as f64;
+                    counts[bin] += 1;
+                }
+            }
+        }
+
+        RadialProfile {
+            bin_width,
+            power: sums
+                .iter()
+                .zip(&counts)
+                .map(|(&sum, &count)| (sum / count.max(1) as f64) as f32)
+                .collect(),
+        }
+    }
+
+    /// How strongly the pattern prefers one direction, from the power-weighted
+    /// second moments of the wave vectors inside the Nyquist circle.
+    pub fn anisotropy(&self) -> Anisotropy {
+        let (mut xx, mut xy, mut yy) = (0.0f64, 0.0f64, 0.0f64);
+        for ky in 0..self.height {
+            for kx in 0..self.width {
+                let (fx, fy) = self.frequency(kx, ky);
+                if fx * fx + fy * fy > 0.25 {
+                    continue;
+                }
+                let power = self.power(kx, ky) as f64;
+                xx += power * (fx * fx) as f64;
+                xy += power * (fx * fy) as f64;
+                yy += power * (fy * fy) as f64;
+            }
+        }
+
+        let trace = xx + yy;
+        if trace <= 0.0 {
+            return Anisotropy {
+                ratio: 0.0,
+                orientation: 0.0,
+            };
+        }
+        let spread = (((xx - yy) / 2.0).powi(2) + xy * xy).sqrt();
+        Anisotropy {
+            ratio: (2.0 * spread / trace) as f32,
+            orientation: (0.5 * (2.0 * xy).atan2(xx - yy)) as f32,
+        }
+    }
+
+    /// The spectrum as an RGBA image the size of the grid, log-scaled, with the
+    /// zero frequency in the middle and rows running bottom to top.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let max = self
+            .power
+            .iter()
+            .copied()
+            .fold(0.0f32, f32::max)
+            .ln_1p()
+            .max(f32::EPSILON);
+        let mut pixels = Vec::with_capacity(self.power.len() * 4);
+        for y in 0..self.height {
+            let ky = (y + self.height - self.height / 2) % self.height;
+            for x in 0..self.width {
+                let kx = (x + self.width - self.width / 2) % self.width;
+                let level = (self.power(kx, ky).ln_1p() / max).sqrt();
+                let value = (level * 255.0).round() as u8;
+                pixels.extend_from_slice(&[value, value, value, 230]);
+            }
+        }
+        pixels
+    }
+}
+
+fn signed_frequency(index: usize, n: usize) -> f32 {
+    let index = if index <= n / 2 {
+        index as f32
+    } else {
+        index as f32 - n as f32
+    };
+    index / n as f32
+}
+
+/// Radially averaged power. Bin `i` covers wavenumbers within half a bin of
+/// `i * bin_width` cycles per cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialProfile {
+    pub bin_width: f32,
+    pub power: Vec<f32>,
+}
+
+impl RadialProfile {
+    pub fn wavenumber(&self, bin: usize) -> f32 {
+        bin as f32 * self.bin_width
+    }
+
+    /// The wavenumber carrying the most power, refined between bins with a
+    /// parabola through the peak and its neighbours. The zero frequency bin is
+    /// skipped. Returns `None` for a featureless field.
+    pub fn peak_wavenumber(&self) -> Option<f32> {
+        let (bin, &peak) = self
+            .power
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        if peak <= 0.0 {
+            return None;
+        }
+
+        let offset = match (self.power.get(bin - 1), self.power.get(bin + 1)) {
+            (Some(&left), Some(&right)) if bin > 1 => {
+                let curvature = left - 2.0 * peak + right;
+                if curvature < 0.0 {
+                    (0.5 * (left - right) / curvature).clamp(-0.5, 0.5)
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        };
+        Some((bin as f32 + offset) * self.bin_width)
+    }
+}
+
+/// Directional preference of a pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anisotropy {
+    /// 0 for a pattern with no preferred direction, such as spots or a
+    /// labyrinth, up to 1 for perfectly parallel stripes.
+    pub ratio: f32,
+    /// Angle of the dominant wave vector from the x axis, in radians between
+    /// -π/2 and π/2. Stripes run perpendicular to it.
+    pub orientation: f32,
+}
+
+/// The characteristic length scale and direction of a pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpectralAnalysis {
+    /// Cycles per cell of the strongest ring in the spectrum.
+    pub dominant_wavenumber: Option<f32>,
+    /// Distance in cells between repeats of the pattern.
+    pub dominant_wavelength: Option<f32>,
+    pub anisotropy: Anisotropy,
+    pub radial_profile: RadialProfile,
+}
+
+impl SpectralAnalysis {
+    /// Analyses the V concentration of a whole field.
+    pub fn of_v(field: &Field) -> Self {
+        Self::of_spectrum(&PowerSpectrum::of_v(field))
+    }
+
+    pub fn of_spectrum(spectrum: &PowerSpectrum) -> Self {
+        let radial_profile = spectrum.radial_profile();
+        let dominant_wavenumber = radial_profile.peak_wavenumber();
+        Self {
+            dominant_wavenumber,
+            dominant_wavelength: dominant_wavenumber.map(|k| 1.0 / k),
+            anisotropy: spectrum.anisotropy(),
+            radial_profile,
+        }
+    }
+}
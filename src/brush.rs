@@ -0,0 +1,74 @@
+/// How a brush's effect fades from its centre to its edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BrushFalloff {
+    /// Full strength everywhere inside the radius.
+    Constant = 0,
+    /// Fades linearly to nothing at the edge.
+    Linear = 1,
+    /// Fades with `(1 - d²)²`, giving soft edges.
+    #[default]
+    Smooth = 2,
+}
+
+/// A single dab of paint on the simulation grid.
+///
+/// Cells inside the brush are blended towards `target` by `strength` scaled by
+/// the falloff at their distance from the centre.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrushStroke {
+    /// Centre in simulation cells, with row 0 at the bottom of the grid.
+    pub x: f32,
+    pub y: f32,
+    /// Radius in simulation cells.
+    pub radius: f32,
+    pub falloff: BrushFalloff,
+    /// The `(u, v)` values painted cells are pulled towards.
+    pub target: (f32, f32),
+    /// Blend factor at the centre of the brush, from 0 to 1.
+    pub strength: f32,
+}
+
+impl BrushStroke {
+    /// A stroke that seeds the reaction.
+    pub fn seed(x: f32, y: f32, radius: f32) -> Self {
+        Self {
+            x,
+            y,
+            radius,
+            falloff: BrushFalloff::Smooth,
+            target: (0.5, 0.99),
+            strength: 1.0,
+        }
+    }
+
+    /// Checks that the stroke has a finite centre, strength and target and a
+    /// radius that is finite and not negative.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.radius.is_finite() && self.radius >= 0.0) {
+            return Err(format!(
+                "brush radius is {}, but must be finite and not negative",
+                self.radius
+            ));
+        }
+        let values = [self.x, self.y, self.strength, self.target.0, self.target.1];
+        if !values.iter().all(|value| value.is_finite()) {
+            return Err(format!(
+                "brush stroke has a value that isn't finite: {:?}",
+                self
+            ));
+        }
+        Ok(())
+    }
+
+    /// A stroke that clears the reaction back to its resting state.
+    pub fn erase(x: f32, y: f32, radius: f32) -> Self {
+        Self {
+            x,
+            y,
+            radius,
+            falloff: BrushFalloff::Smooth,
+            target: (1.0, 0.0),
+            strength: 1.0,
+        }
+    }
+}
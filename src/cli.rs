@@ -0,0 +1,213 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use gray_scott_reaction_diffusion::NutrientMapping;
+use gray_scott_reaction_diffusion::nutrient_map::Channel;
+use gray_scott_reaction_diffusion::renderer::UpscaleFilter;
+use gray_scott_reaction_diffusion::seeders::SeederSpec;
+use std::path::PathBuf;
+
+/// Smallest and largest simulation cells per logical pixel of the window.
+pub const MIN_GRID_SCALE: f32 = 0.25;
+pub const MAX_GRID_SCALE: f32 = 2.0;
+
+fn parse_grid_scale(value: &str) -> Result<f32, String> {
+    let scale: f32 = value
+        .parse()
+        .map_err(|_| format!("`{}` is not a number", value))?;
+    if (MIN_GRID_SCALE..=MAX_GRID_SCALE).contains(&scale) {
+        Ok(scale)
+    } else {
+        Err(format!(
+            "scale must be between {} and {}",
+            MIN_GRID_SCALE, MAX_GRID_SCALE
+        ))
+    }
+}
+
+fn parse_nutrient_speed(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(speed) if speed.is_finite() => Ok(speed),
+        _ => Err(format!("`{}` is not a number", value)),
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum UpscaleFilterArg {
+    Nearest,
+    Bilinear,
+}
+
+impl From<UpscaleFilterArg> for UpscaleFilter {
+    fn from(filter: UpscaleFilterArg) -> Self {
+        match filter {
+            UpscaleFilterArg::Nearest => UpscaleFilter::Nearest,
+            UpscaleFilterArg::Bilinear => UpscaleFilter::Bilinear,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ChannelArg {
+    Luma,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl From<ChannelArg> for Channel {
+    fn from(channel: ChannelArg) -> Self {
+        match channel {
+            ChannelArg::Luma => Channel::Luma,
+            ChannelArg::Red => Channel::Red,
+            ChannelArg::Green => Channel::Green,
+            ChannelArg::Blue => Channel::Blue,
+            ChannelArg::Alpha => Channel::Alpha,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "gray_scott_reaction_diffusion", version, about)]
+pub struct Args {
+    /// Initial condition seeder: noise, center-square, center-disc, random-circles,
+    /// perforated-sheet, perlin-noise, worley-noise, lines, dot-grid, image or
+    /// text, optionally with parameters such as `random-circles:count=10,max-radius=8`
+    #[arg(long, value_parser = SeederSpec::parse)]
+    pub seeder: Option<SeederSpec>,
+
+    /// Image whose dark areas seed the reaction (enables the image seeder)
+    #[arg(long)]
+    pub seed_image: Option<PathBuf>,
+
+    /// Text whose shape seeds the reaction (enables the text seeder)
+    #[arg(long)]
+    pub seed_text: Option<String>,
+
+    /// PNG or JPEG image stretched over the grid as the nutrient pattern, so
+    /// that patterns grow fastest where it is brightest
+    #[arg(long)]
+    pub nutrient_image: Option<PathBuf>,
+
+    /// Channel of the nutrient image to read
+    #[arg(long, value_enum, default_value_t = ChannelArg::Luma)]
+    pub nutrient_channel: ChannelArg,
+
+    /// Speed of the animated nutrient patterns, where 2 is twice as fast and a
+    /// negative speed runs them backwards
+    #[arg(
+        long,
+        default_value_t = 1.0,
+        allow_negative_numbers = true,
+        value_parser = parse_nutrient_speed
+    )]
+    pub nutrient_speed: f32,
+
+    /// Rates the nutrient pattern scales: feed, kill, feed-and-kill or
+    /// diffusion, or strengths for each such as `feed=1,kill=0.1,du=0,dv=0.5`
+    #[arg(long, default_value = "feed", value_parser = NutrientMapping::parse)]
+    pub nutrient_mapping: NutrientMapping,
+
+    /// GPU memory, in MiB, set aside for rewind checkpoints
+    #[arg(long, default_value_t = 256)]
+    pub history_budget_mb: u64,
+
+    /// Number of simulation steps between rewind checkpoints
+    #[arg(long, default_value_t = 100)]
+    pub checkpoint_interval: u64,
+
+    /// Preset file, or directory of them, to load after the built-in presets
+    /// and those in the user preset directory
+    #[arg(long)]
+    pub presets: Option<PathBuf>,
+
+    /// Seconds over which switching presets morphs the rates from the old
+    /// preset's to the new one's. 0 switches instantly
+    #[arg(long, default_value_t = 2.0)]
+    pub preset_transition_secs: f32,
+
+    /// Seconds attract mode stays on each preset before morphing to another
+    #[arg(long, default_value_t = 30.0)]
+    pub attract_dwell_secs: f32,
+
+    /// Keep the simulation grid at its starting size instead of resizing it
+    /// with the window
+    #[arg(long)]
+    pub fixed_grid: bool,
+
+    /// Simulation cells per logical pixel of the window, from 0.25 to 2
+    #[arg(long, default_value_t = 1.0, value_parser = parse_grid_scale)]
+    pub scale: f32,
+
+    /// How the simulation is stretched to fill the window
+    #[arg(long, value_enum, default_value_t = UpscaleFilterArg::Nearest)]
+    pub upscale_filter: UpscaleFilterArg,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Tools that run without opening a window.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Find the homogeneous steady states of a parameter set and whether
+    /// diffusion makes any of them form a Turing pattern
+    Stability {
+        /// Feed rate f
+        feed_rate: f32,
+
+        /// Kill rate k
+        kill_rate: f32,
+
+        /// Diffusion rate of U
+        #[arg(long, default_value_t = 1.0)]
+        delta_u: f32,
+
+        /// Diffusion rate of V
+        #[arg(long, default_value_t = 0.5)]
+        delta_v: f32,
+
+        /// Also print the dispersion relation of each state as CSV
+        #[arg(long)]
+        dispersion: bool,
+    },
+
+    /// Simulate a grid of feed and kill rates on the CPU and draw what each
+    /// one turns into, with the presets marked
+    PhaseDiagram {
+        /// Where to write the diagram. The samples are written alongside it as CSV
+        #[arg(long, default_value = "phase_diagram.png")]
+        output: PathBuf,
+
+        #[arg(long, default_value_t = 0.01)]
+        feed_min: f32,
+
+        #[arg(long, default_value_t = 0.09)]
+        feed_max: f32,
+
+        #[arg(long, default_value_t = 0.03)]
+        kill_min: f32,
+
+        #[arg(long, default_value_t = 0.07)]
+        kill_max: f32,
+
+        /// Number of feed and kill rates sampled along each axis
+        #[arg(long, default_value_t = 32)]
+        samples: usize,
+
+        /// Width and height of each simulation in cells
+        #[arg(long, default_value_t = 64)]
+        grid_size: usize,
+
+        /// Steps each simulation runs for unless it settles sooner
+        #[arg(long, default_value_t = 10_000)]
+        steps: u64,
+
+        /// Initial condition seeder and its parameters, as for --seeder
+        #[arg(long, default_value = "noise", value_parser = SeederSpec::parse)]
+        seeder: SeederSpec,
+
+        /// Seed shared by every simulation
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+    },
+}
@@ -0,0 +1,487 @@
+use crate::field::UVPair;
+use crate::readback::{
+    REDUCTION_WORKGROUP_COUNT, ReductionParams, StagingBuffer, active_workgroups,
+};
+use bytemuck::{Pod, Zeroable};
+use std::collections::VecDeque;
+
+/// How much the grid changed over one step, with enough about V to tell a
+/// pattern that has died out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChangeSample {
+    /// The step the change led up to.
+    pub step_count: u64,
+    pub cell_count: usize,
+    /// Square root of the summed squared change of U and V over every cell.
+    pub l2_norm: f32,
+    pub mean_v: f32,
+    pub max_v: f32,
+}
+
+impl ChangeSample {
+    /// Measures the change from `previous` to `current` on the CPU. Gives the
+    /// same results as the GPU pass, up to rounding.
+    pub fn between(previous: &[UVPair], current: &[UVPair], step_count: u64) -> Self {
+        assert_eq!(
+            previous.len(),
+            current.len(),
+            "Both states must be the same size"
+        );
+
+        let mut sum_of_squares = 0.0f64;
+        let mut sum_v = 0.0f64;
+        let mut max_v = 0.0f32;
+        for (before, after) in previous.iter().zip(current) {
+            let (du, dv) = (after.u - before.u, after.v - before.v);
+            sum_of_squares += (du * du + dv * dv) as f64;
+            sum_v += after.v as f64;
+            max_v = max_v.max(after.v);
+        }
+        Self {
+            step_count,
+            cell_count: current.len(),
+            l2_norm: sum_of_squares.sqrt() as f32,
+            mean_v: (sum_v / current.len().max(1) as f64) as f32,
+            max_v,
+        }
+    }
+
+    /// The change per cell, which unlike the L2 norm doesn't grow with the grid.
+    pub fn rms_change(&self) -> f32 {
+        self.l2_norm / (self.cell_count.max(1) as f32).sqrt()
+    }
+}
+
+/// A simulation settling into a state that is no longer worth watching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvergenceEvent {
+    /// The grid has stopped changing.
+    SteadyState { step_count: u64 },
+    /// V has vanished everywhere, leaving the trivial U = 1 state.
+    DiedOut { step_count: u64 },
+    /// Mean V repeats every `period` steps.
+    Oscillating { step_count: u64, period: u64 },
+}
+
+impl ConvergenceEvent {
+    pub fn step_count(&self) -> u64 {
+        match *self {
+            ConvergenceEvent::SteadyState { step_count }
+            | ConvergenceEvent::DiedOut { step_count }
+            | ConvergenceEvent::Oscillating { step_count, .. } => step_count,
+        }
+    }
+}
+
+/// Watches a stream of [`ChangeSample`]s, taken every `sample_interval`
+/// steps, for a simulation that has stopped doing anything interesting.
+///
+/// Each event is reported once when the simulation enters that state. A
+/// simulation that starts changing again can report it again later.
+#[derive(Debug, Clone)]
+pub struct ConvergenceMonitor {
+    /// Steps between samples.
+    pub sample_interval: u64,
+    /// RMS change per cell below which a step counts as still.
+    pub steady_tolerance: f32,
+    /// Max V below which the pattern counts as gone.
+    pub died_out_threshold: f32,
+    /// Consecutive still samples needed for a steady state.
+    pub patience: usize,
+    /// Samples of mean V searched for a repeating cycle. Cycles longer than
+    /// half of this go undetected.
+    pub oscillation_window: usize,
+    /// Autocorrelation mean V must reach at a lag for it to count as a period.
+    pub min_correlation: f32,
+    still_samples: usize,
+    mean_v_history: VecDeque<(u64, f32)>,
+    reported: Option<ConvergenceEvent>,
+}
+
+impl Default for ConvergenceMonitor {
+    fn default() -> Self {
+        Self {
+            sample_interval: 10,
+            steady_tolerance: 1e-6,
+            died_out_threshold: 1e-3,
+            patience: 5,
+            oscillation_window: 256,
+            min_correlation: 0.95,
+            still_samples: 0,
+            mean_v_history: VecDeque::new(),
+            reported: None,
+        }
+    }
+}
+
+impl ConvergenceMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets everything seen so far, as after a reseed.
+    pub fn reset(&mut self) {
+        self.still_samples = 0;
+        self.mean_v_history.clear();
+        self.reported = None;
+    }
+
+    /// The state last reported, if the simulation is still in it.
+    pub fn state(&self) -> Option<ConvergenceEvent> {
+        self.reported
+    }
+
+    /// Takes in the next sample, returning an event if the simulation has
+    /// just settled.
+    pub fn record(&mut self, sample: &ChangeSample) -> Option<ConvergenceEvent> {
+        // Going back in time, as on a rewind, makes the history meaningless
+        if self
+            .mean_v_history
+            .back()
+            .is_some_and(|&(step_count, _)| sample.step_count <= step_count)
+        {
+            self.reset();
+        }
+
+        self.mean_v_history
+            .push_back((sample.step_count, sample.mean_v));
+        while self.mean_v_history.len() > self.oscillation_window {
+            self.mean_v_history.pop_front();
+        }
+        if sample.rms_change() < self.steady_tolerance {
+            self.still_samples += 1;
+        } else {
+            self.still_samples = 0;
+        }
+
+        let step_count = sample.step_count;
+        let state = if sample.max_v < self.died_out_threshold {
+            Some(ConvergenceEvent::DiedOut { step_count })
+        } else if self.still_samples >= self.patience {
+            Some(ConvergenceEvent::SteadyState { step_count })
+        } else {
+            self.oscillation_period()
+                .map(|period| ConvergenceEvent::Oscillating { step_count, period })
+        };
+
+        let is_new = match (state, self.reported) {
+            (Some(state), Some(reported)) => {
+                std::mem::discriminant(&state) != std::mem::discriminant(&reported)
+            }
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if state.is_none() || is_new {
+            self.reported = state;
+        }
+        if is_new { state } else { None }
+    }
+
+    /// The period in steps of a cycle in mean V, found as the first peak in
+    /// its autocorrelation after the correlation goes negative.
+    fn oscillation_period(&self) -> Option<u64> {
+        let n = self.mean_v_history.len();
+        if n < self.oscillation_window.max(8) {
+            return None;
+        }
+
+        // Remove any linear drift, so a pattern that is still growing doesn't
+        // look periodic
+        let values: Vec<f64> = self.mean_v_history.iter().map(|&(_, v)| v as f64).collect();
+        let mean_i = (n - 1) as f64 / 2.0;
+        let mean_v = values.iter().sum::<f64>() / n as f64;
+        let (mut covariance, mut variance_i) = (0.0, 0.0);
+        for (i, &v) in values.iter().enumerate() {
+            covariance += (i as f64 - mean_i) * (v - mean_v);
+            variance_i += (i as f64 - mean_i).powi(2);
+        }
+        let slope = covariance / variance_i;
+        let residuals: Vec<f64> = values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| v - mean_v - slope * (i as f64 - mean_i))
+            .collect();
+        let variance = residuals.iter().map(|r| r * r).sum::<f64>() / n as f64;
+        if variance.sqrt() < 1e-6 {
+            return None;
+        }
+
+        let correlation = |lag: usize| {
+            let sum: f64 = (0..n - lag)
+                .map(|i| residuals[i] * residuals[i + lag])
+                .sum();
+            sum / ((n - lag) as f64 * variance)
+        };
+        let correlations: Vec<f64> = (0..=n / 2 + 1).map(correlation).collect();
+        let crossing = correlations.iter().position(|&c| c < 0.0)?;
+        let lag = (crossing.max(1)..=n / 2).find(|&lag| {
+            correlations[lag] >= correlations[lag - 1] && correlations[lag] >= correlations[lag + 1]
+        })?;
+        if correlations[lag] < self.min_correlation as f64 {
+            return None;
+        }
+
+        let (first, last) = (self.mean_v_history[0].0, self.mean_v_history[n - 1].0);
+        let interval = (last - first) as f64 / (n - 1) as f64;
+        Some((lag as f64 * interval).round() as u64)
+    }
+}
+
+/// Mirrors `Partial` in convergence.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct Partial {
+    sum_of_squares: UVPair,
+    sum_v: f32,
+    max_v: f32,
+}
+
+const PARTIALS_SIZE: u64 = (REDUCTION_WORKGROUP_COUNT * std::mem::size_of::<Partial>()) as u64;
+
+/// Reduces the change between the two grid buffers to a [`ChangeSample`] on
+/// the GPU, reading the result back without stalling the simulation.
+pub(crate) struct ConvergencePass {
+    cell_count: usize,
+    params_buffer: wgpu::Buffer,
+    partials_buffer: wgpu::Buffer,
+    staging: StagingBuffer,
+    /// A collected sample nobody has taken yet.
+    latest: Option<ChangeSample>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_groups: [wgpu::BindGroup; 2],
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl ConvergencePass {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        uvs_buffers: &[wgpu::Buffer; 2],
+        cell_count: usize,
+    ) -> Self {
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Convergence Bind Group Layout"),
+            entries: &[
+                storage_entry(0, true),
+                storage_entry(1, true),
+                storage_entry(2, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Convergence Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Convergence Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/convergence.wgsl").into()),
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Convergence Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Convergence Params Buffer"),
+            size: std::mem::size_of::<ReductionParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let partials_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Convergence Partials Buffer"),
+            size: PARTIALS_SIZE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = StagingBuffer::new(device, "Convergence Staging Buffer", PARTIALS_SIZE);
+
+        let bind_groups = create_bind_groups(
+            device,
+            &bind_group_layout,
+            uvs_buffers,
+            &partials_buffer,
+            &params_buffer,
+        );
+
+        let pass = Self {
+            cell_count,
+            params_buffer,
+            partials_buffer,
+            staging,
+            latest: None,
+            bind_group_layout,
+            bind_groups,
+            pipeline,
+        };
+        pass.write_params(queue);
+        pass
+    }
+
+    /// Points the pass at a new pair of grid buffers after a resize.
+    pub(crate) fn rebind(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        uvs_buffers: &[wgpu::Buffer; 2],
+        cell_count: usize,
+    ) {
+        self.cell_count = cell_count;
+        self.bind_groups = create_bind_groups(
+            device,
+            &self.bind_group_layout,
+            uvs_buffers,
+            &self.partials_buffer,
+            &self.params_buffer,
+        );
+        self.write_params(queue);
+    }
+
+    fn write_params(&self, queue: &wgpu::Queue) {
+        let params = ReductionParams::new(self.cell_count);
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+    }
+
+    fn is_idle(&self) -> bool {
+        self.staging.is_idle()
+    }
+
+    /// Encodes a reduction of the change from the other buffer to
+    /// `uvs_buffers[buffer_index]`, the state after `step_count`. Call
+    /// [`Self::start_mapping`] once the encoder has been submitted. Does
+    /// nothing if a reduction is already in flight.
+    pub(crate) fn encode(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        buffer_index: usize,
+        step_count: u64,
+    ) {
+        if !self.is_idle() {
+            return;
+        }
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Convergence Pass"),
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &self.bind_groups[buffer_index], &[]);
+            compute_pass.dispatch_workgroups(REDUCTION_WORKGROUP_COUNT as u32, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(
+            &self.partials_buffer,
+            0,
+            self.staging.buffer(),
+            0,
+            PARTIALS_SIZE,
+        );
+
+        self.staging.mark_encoded(step_count);
+    }
+
+    /// Starts mapping the results of a submitted reduction.
+    pub(crate) fn start_mapping(&mut self) {
+        self.staging.start_mapping();
+    }
+
+    /// Whether a reduction has been submitted and not yet collected.
+    pub(crate) fn is_submitted(&self) -> bool {
+        self.staging.is_submitted()
+    }
+
+    /// Collects a finished reduction, if there is one. The device must have
+    /// been polled for mapping to make progress.
+    pub(crate) fn collect(&mut self) -> Result<(), wgpu::BufferAsyncError> {
+        let cell_count = self.cell_count;
+        let sample = self.staging.collect(|data, step_count| {
+            combine(bytemuck::cast_slice(data), step_count, cell_count)
+        })?;
+        if sample.is_some() {
+            self.latest = sample;
+        }
+        Ok(())
+    }
+
+    /// Hands over the most recently collected sample, once.
+    pub(crate) fn take(&mut self) -> Option<ChangeSample> {
+        self.latest.take()
+    }
+}
+
+/// Adds up the per-workgroup change in double precision.
+fn combine(partials: &[Partial], step_count: u64, cell_count: usize) -> ChangeSample {
+    let mut sum_of_squares = 0.0f64;
+    let mut sum_v = 0.0f64;
+    let mut max_v = 0.0f32;
+    for partial in &partials[..active_workgroups(cell_count)] {
+        sum_of_squares += partial.sum_of_squares.u as f64 + partial.sum_of_squares.v as f64;
+        sum_v += partial.sum_v as f64;
+        max_v = max_v.max(partial.max_v);
+    }
+
+    ChangeSample {
+        step_count,
+        cell_count,
+        l2_norm: sum_of_squares.sqrt() as f32,
+        mean_v: (sum_v / cell_count.max(1) as f64) as f32,
+        max_v,
+    }
+}
+
+/// Bind group `i` measures the change into `uvs_buffers[i]` from the other one.
+fn create_bind_groups(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uvs_buffers: &[wgpu::Buffer; 2],
+    partials_buffer: &wgpu::Buffer,
+    params_buffer: &wgpu::Buffer,
+) -> [wgpu::BindGroup; 2] {
+    [0, 1].map(|i| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("Convergence Bind Group {}", i)),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uvs_buffers[i].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uvs_buffers[1 - i].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: partials_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    })
+}
@@ -0,0 +1,275 @@
+use crate::convergence::{ChangeSample, ConvergenceEvent, ConvergenceMonitor};
+use crate::field::{Field, UVPair};
+use crate::gray_scott_model::TIME_STEP;
+use crate::nutrient_graph::NutrientGraph;
+use crate::nutrient_mapping::NutrientMapping;
+use crate::nutrient_presets::{NutrientParams, NutrientPattern};
+use rayon::prelude::*;
+use std::iter;
+
+/// A CPU implementation of the same model as [`crate::ReactionDiffusionSystem`],
+/// for running headless where there is no GPU, such as batch tools and tests.
+///
+/// Each step matches reaction_diffusion.wgsl up to floating point rounding.
+pub struct CpuReactionDiffusionSystem {
+    pub width: usize,
+    pub height: usize,
+    feed_rate: f32,
+    kill_rate: f32,
+    delta_u: f32,
+    delta_v: f32,
+    nutrient: NutrientGraph,
+    nutrient_mapping: NutrientMapping,
+    /// The nutrient factor of every cell, recomputed when the graph changes
+    /// and, if it is animated, every step.
+    nutrient_factors: Vec<f32>,
+    uvs: Vec<UVPair>,
+    next_uvs: Vec<UVPair>,
+    step_count: u64,
+}
+
+impl CpuReactionDiffusionSystem {
+    pub fn new(
+        width: usize,
+        height: usize,
+        feed_rate: f32,
+        kill_rate: f32,
+        delta_u: f32,
+        delta_v: f32,
+    ) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "Reaction diffusion system must be at least 1x1 but {}x{} was passed",
+            width,
+            height
+        );
+
+        let uvs: Vec<UVPair> = iter::repeat_n(UVPair { u: 1.0, v: 0.0 }, width * height).collect();
+        let mut system = Self {
+            width,
+            height,
+            feed_rate,
+            kill_rate,
+            delta_u,
+            delta_v,
+            nutrient: NutrientGraph::default(),
+            nutrient_mapping: NutrientMapping::default(),
+            nutrient_factors: Vec::new(),
+            next_uvs: uvs.clone(),
+            uvs,
+            step_count: 0,
+        };
+        system.update_nutrient_factors();
+        system
+    }
+
+    pub fn uvs(&self) -> &[UVPair] {
+        &self.uvs
+    }
+
+    /// A copy of the current grid.
+    pub fn field(&self) -> Field {
+        Field::new(self.width, self.height, self.step_count, self.uvs.clone())
+    }
+
+    pub fn set(&mut self, x: isize, y: isize, v: (f32, f32)) {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        self.uvs[y * self.width + x] = UVPair {
+            u: v.0.clamp(-1.0, 1.0),
+            v: v.1.clamp(-1.0, 1.0),
+        };
+    }
+
+    pub fn set_all(&mut self, values: &[(f32, f32)]) {
+        assert_eq!(
+            values.len(),
+            self.width * self.height,
+            "Values length must match grid size"
+        );
+        for (uv, &(u, v)) in self.uvs.iter_mut().zip(values) {
+            *uv = UVPair {
+                u: u.clamp(-1.0, 1.0),
+                v: v.clamp(-1.0, 1.0),
+            };
+        }
+    }
+
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    pub fn feed_rate(&self) -> f32 {
+        self.feed_rate
+    }
+
+    pub fn kill_rate(&self) -> f32 {
+        self.kill_rate
+    }
+
+    pub fn delta_u(&self) -> f32 {
+        self.delta_u
+    }
+
+    pub fn delta_v(&self) -> f32 {
+        self.delta_v
+    }
+
+    pub fn update_rates(&mut self, feed_rate: f32, kill_rate: f32) {
+        self.feed_rate = feed_rate;
+        self.kill_rate = kill_rate;
+    }
+
+    /// Sets the feed and kill rates along with the diffusion rates of U and V.
+    pub fn update_params(&mut self, feed_rate: f32, kill_rate: f32, delta_u: f32, delta_v: f32) {
+        self.update_rates(feed_rate, kill_rate);
+        self.delta_u = delta_u;
+        self.delta_v = delta_v;
+    }
+
+    /// Switches to a single pattern with its default parameters. Unknown
+    /// patterns are uniform, like the shader's default case.
+    pub fn set_nutrient_pattern(&mut self, pattern_index: u32, is_reversed: bool) {
+        let pattern = NutrientPattern::from_u32(pattern_index).unwrap_or(NutrientPattern::Uniform);
+        let params = NutrientParams {
+            is_reversed,
+            ..pattern.default_params()
+        };
+        self.set_nutrient_graph(NutrientGraph::with_params(pattern, params))
+            .expect("A single pattern with its default parameters is valid");
+    }
+
+    /// Replaces the nutrient patterns that scale the feed rate, or leaves them
+    /// alone if the graph fails [`NutrientGraph::validate`], so that it
+    /// behaves the same as on the GPU.
+    pub fn set_nutrient_graph(&mut self, graph: NutrientGraph) -> Result<(), String> {
+        graph
+            .validate()
+            .map_err(|e| format!("Invalid nutrient graph {}: {}", graph, e))?;
+        self.nutrient = graph;
+        self.update_nutrient_factors();
+        Ok(())
+    }
+
+    pub fn nutrient_graph(&self) -> &NutrientGraph {
+        &self.nutrient
+    }
+
+    pub fn nutrient_mapping(&self) -> NutrientMapping {
+        self.nutrient_mapping
+    }
+
+    /// Sets which rates the nutrient factor scales, and how strongly.
+    ///
+    /// Panics if a strength isn't finite, as on the GPU.
+    pub fn set_nutrient_mapping(&mut self, mapping: NutrientMapping) {
+        assert!(
+            mapping.is_finite(),
+            "Nutrient mapping strengths must be finite but {} was passed",
+            mapping
+        );
+        self.nutrient_mapping = mapping;
+    }
+
+    fn update_nutrient_factors(&mut self) {
+        let time = (self.step_count as f64 * TIME_STEP) as f32;
+        self.nutrient_factors = self.nutrient.factors(self.width, self.height, time);
+    }
+
+    /// Advances the simulation by one step.
+    pub fn update(&mut self) {
+        if self.nutrient.is_animated() {
+            self.update_nutrient_factors();
+        }
+
+        let width = self.width as isize;
+        let height = self.height as isize;
+        let uvs = &self.uvs;
+        let factors = &self.nutrient_factors;
+        let mapping = self.nutrient_mapping;
+        let (feed_rate, kill_rate) = (self.feed_rate, self.kill_rate);
+        let (delta_u, delta_v) = (self.delta_u, self.delta_v);
+
+        let cell =
+            |x: isize, y: isize| uvs[(y.rem_euclid(height) * width + x.rem_euclid(width)) as usize];
+
+        self.next_uvs
+            .par_chunks_mut(self.width)
+            .enumerate()
+            .for_each(|(y, row)| {
+                let y = y as isize;
+                for (x, out) in row.iter_mut().enumerate() {
+                    let x = x as isize;
+                    let uv = cell(x, y);
+
+                    // The same 3x3 stencil as get_laplacian in the shader
+                    let mut laplacian_u = -uv.u;
+                    let mut laplacian_v = -uv.v;
+                    for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                        let neighbour = cell(x + dx, y + dy);
+                        laplacian_u += neighbour.u * 0.2;
+                        laplacian_v += neighbour.v * 0.2;
+                    }
+                    for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                        let neighbour = cell(x + dx, y + dy);
+                        laplacian_u += neighbour.u * 0.05;
+                        laplacian_v += neighbour.v * 0.05;
+                    }
+
+                    let reaction_rate = uv.u * uv.v * uv.v;
+                    let [feed_scale, kill_scale, delta_u_scale, delta_v_scale] =
+                        mapping.scales(factors[(y * width + x) as usize]);
+                    let effective_feed_rate = feed_rate * feed_scale;
+                    let effective_kill_rate = kill_rate * kill_scale;
+                    let du = delta_u * delta_u_scale * laplacian_u - reaction_rate
+                        + effective_feed_rate * (1.0 - uv.u);
+                    let dv = delta_v * delta_v_scale * laplacian_v + reaction_rate
+                        - (effective_kill_rate + effective_feed_rate) * uv.v;
+
+                    *out = UVPair {
+                        u: (uv.u + du).clamp(0.0, 1.0),
+                        v: (uv.v + dv).clamp(0.0, 1.0),
+                    };
+                }
+            });
+
+        std::mem::swap(&mut self.uvs, &mut self.next_uvs);
+        self.step_count += 1;
+    }
+
+    /// Advances the simulation by `steps` steps.
+    pub fn run(&mut self, steps: u64) {
+        for _ in 0..steps {
+            self.update();
+        }
+    }
+
+    /// How much the grid changed over the last step. Zero before the first.
+    pub fn change_sample(&self) -> ChangeSample {
+        if self.step_count == 0 {
+            return ChangeSample::between(&self.uvs, &self.uvs, 0);
+        }
+        // After a step the previous state is left in the other buffer
+        ChangeSample::between(&self.next_uvs, &self.uvs, self.step_count)
+    }
+
+    /// Runs for up to `max_steps` steps, sampling the change every
+    /// `monitor.sample_interval` steps, and stops early at the first event.
+    pub fn run_until_converged(
+        &mut self,
+        max_steps: u64,
+        monitor: &mut ConvergenceMonitor,
+    ) -> Option<ConvergenceEvent> {
+        let interval = monitor.sample_interval.max(1);
+        let mut remaining = max_steps;
+        while remaining > 0 {
+            let steps = interval.min(remaining);
+            self.run(steps);
+            remaining -= steps;
+            if let Some(event) = monitor.record(&self.change_sample()) {
+                return Some(event);
+            }
+        }
+        None
+    }
+}
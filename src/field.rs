@@ -0,0 +1,132 @@
+use bytemuck::{Pod, Zeroable};
+
+/// The concentrations of both species in one cell.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Default, Pod, Zeroable)]
+pub struct UVPair {
+    pub u: f32,
+    pub v: f32,
+}
+
+/// A rectangle of cells, with row 0 at the bottom of the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// How cells are interpolated when a grid is resampled to a new size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResampleMode {
+    Nearest,
+    #[default]
+    Bilinear,
+}
+
+/// A copy of the whole simulation grid at one step.
+///
+/// Cells are stored row by row with row 0 at the bottom of the window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub width: usize,
+    pub height: usize,
+    /// The step the simulation had reached when this copy was taken.
+    pub step_count: u64,
+    data: Vec<UVPair>,
+}
+
+impl Field {
+    pub fn new(width: usize, height: usize, step_count: u64, data: Vec<UVPair>) -> Self {
+        assert_eq!(
+            data.len(),
+            width * height,
+            "Field data length must match its dimensions"
+        );
+        Self {
+            width,
+            height,
+            step_count,
+            data,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[UVPair] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<UVPair> {
+        self.data
+    }
+
+    /// The cell at `(x, y)`, wrapping around the edges like the simulation does.
+    pub fn get(&self, x: isize, y: isize) -> UVPair {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        self.data[y * self.width + x]
+    }
+
+    /// Scales the field to `width` by `height` cells. Cell centres are mapped
+    /// onto each other and sampling wraps around the edges, so patterns stay
+    /// seamless across the periodic boundary.
+    pub fn resample(&self, width: usize, height: usize, mode: ResampleMode) -> Field {
+        let scale_x = self.width as f32 / width as f32;
+        let scale_y = self.height as f32 / height as f32;
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let source_y = (y as f32 + 0.5) * scale_y - 0.5;
+            for x in 0..width {
+                let source_x = (x as f32 + 0.5) * scale_x - 0.5;
+                data.push(match mode {
+                    ResampleMode::Nearest => {
+                        self.get(source_x.round() as isize, source_y.round() as isize)
+                    }
+                    ResampleMode::Bilinear => self.sample_bilinear(source_x, source_y),
+                });
+            }
+        }
+        Field::new(width, height, self.step_count, data)
+    }
+
+    fn sample_bilinear(&self, x: f32, y: f32) -> UVPair {
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+        let (x0, y0) = (x0 as isize, y0 as isize);
+        let lerp = |a: UVPair, b: UVPair, t: f32| UVPair {
+            u: a.u + (b.u - a.u) * t,
+            v: a.v + (b.v - a.v) * t,
+        };
+        let bottom = lerp(self.get(x0, y0), self.get(x0 + 1, y0), tx);
+        let top = lerp(self.get(x0, y0 + 1), self.get(x0 + 1, y0 + 1), tx);
+        lerp(bottom, top, ty)
+    }
+
+    /// A copy of the cells inside `region`.
+    pub fn crop(&self, region: Region) -> Field {
+        assert!(
+            region.x + region.width <= self.width && region.y + region.height <= self.height,
+            "Region {:?} does not fit in the {}x{} field",
+            region,
+            self.width,
+            self.height
+        );
+        let data = (region.y..region.y + region.height)
+            .flat_map(|y| {
+                let start = y * self.width + region.x;
+                self.data[start..start + region.width].iter().copied()
+            })
+            .collect();
+        Field::new(region.width, region.height, self.step_count, data)
+    }
+
+    pub fn u_values(&self) -> impl Iterator<Item = f32> + '_ {
+        self.data.iter().map(|uv| uv.u)
+    }
+
+    pub fn v_values(&self) -> impl Iterator<Item = f32> + '_ {
+        self.data.iter().map(|uv| uv.v)
+    }
+}
@@ -1,7 +1,24 @@
+use crate::brush::BrushStroke;
+use crate::convergence::{ChangeSample, ConvergencePass};
+use crate::field::{Field, Region, ResampleMode, UVPair};
+use crate::history::{self, CheckpointHistory};
+use crate::nutrient_graph::{NutrientField, NutrientGraph};
+use crate::nutrient_mapping::NutrientMapping;
+use crate::nutrient_presets::{NutrientParams, NutrientPattern};
+use crate::probes::{Probe, ProbeSampler, ProbeSeries};
+use crate::readback::{self, Readback, ReadbackData, ReadbackHandle, ReadbackRing};
+use crate::snapshot::Snapshot;
+use crate::statistics::{FieldStatistics, StatisticsPass};
 use bytemuck::{Pod, Zeroable};
+use std::io;
 use std::iter;
+use std::path::Path;
+use std::sync::Arc;
 use wgpu::util::DeviceExt;
 
+/// Simulated time covered by one step of the explicit Euler integrator.
+pub const TIME_STEP: f64 = 1.0;
+
 #[repr(C)]
 #[derive(Copy, Clone, Pod, Zeroable)]
 struct SimulationParams {
@@ -11,15 +28,31 @@ struct SimulationParams {
     delta_v: f32,
     width: u32,
     height: u32,
-    nutrient_pattern: u32, // 0 = uniform, 1 = checkerboard, etc.
-    is_nutrient_pattern_reversed: u32,
+    /// Simulated time, which drives animated nutrient patterns.
+    time: f32,
+    _padding: u32,
+    /// Strengths with which the nutrient factor scales feed, kill, delta U
+    /// and delta V.
+    nutrient_mapping: [f32; 4],
 }
 
 #[repr(C)]
 #[derive(Copy, Clone, Pod, Zeroable)]
-struct UVPair {
-    u: f32,
-    v: f32,
+struct BrushParams {
+    center_x: f32,
+    center_y: f32,
+    radius: f32,
+    falloff: u32,
+    target_u: f32,
+    target_v: f32,
+    strength: f32,
+    width: u32,
+    height: u32,
+    origin_x: i32,
+    origin_y: i32,
+    extent_x: u32,
+    extent_y: u32,
+    _padding: [u32; 3],
 }
 
 #[allow(dead_code)]
@@ -30,9 +63,23 @@ pub struct ReactionDiffusionSystem {
     kill_rate: f32,
     delta_u: f32,
     delta_v: f32,
-    nutrient_pattern: u32,
-    is_nutrient_pattern_reversed: bool,
+    nutrient: NutrientGraph,
+    nutrient_mapping: NutrientMapping,
     uvs: Vec<UVPair>,
+    step_count: u64,
+    seed: u64,
+    history: CheckpointHistory,
+    readbacks: ReadbackRing,
+    nutrient_readbacks: ReadbackRing<NutrientField>,
+    /// Bumped whenever the nutrient field may have changed other than by
+    /// animation, so that an unchanged field isn't read back twice.
+    nutrient_revision: u64,
+    last_nutrient_field_key: Option<(u64, u64)>,
+    probes: ProbeSampler,
+    statistics: StatisticsPass,
+    statistics_interval: Option<u64>,
+    convergence: ConvergencePass,
+    convergence_interval: Option<u64>,
 
     // GPU resources
     device: wgpu::Device,
@@ -40,8 +87,17 @@ pub struct ReactionDiffusionSystem {
     uvs_buffers: [wgpu::Buffer; 2], // Double buffering
     current_buffer: usize,
     params_buffer: wgpu::Buffer,
+    nutrient_buffer: wgpu::Buffer,
+    nutrient_map_buffer: wgpu::Buffer,
+    nutrient_field_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
     bind_groups: [wgpu::BindGroup; 2], // Double buffering
     compute_pipeline: wgpu::ComputePipeline,
+    nutrient_field_pipeline: wgpu::ComputePipeline,
+    brush_params_buffer: wgpu::Buffer,
+    brush_bind_group_layout: wgpu::BindGroupLayout,
+    brush_bind_groups: [wgpu::BindGroup; 2], // One per UVs buffer
+    brush_pipeline: wgpu::ComputePipeline,
 }
 
 impl ReactionDiffusionSystem {
@@ -67,9 +123,7 @@ impl ReactionDiffusionSystem {
         );
 
         let vec_capacity = width * height;
-        let uvs: Vec<UVPair> = iter::repeat(UVPair { u: 1.0, v: 0.0 })
-            .take(vec_capacity)
-            .collect();
+        let uvs: Vec<UVPair> = iter::repeat_n(UVPair { u: 1.0, v: 0.0 }, vec_capacity).collect();
 
         // Initialize wgpu
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -98,33 +152,7 @@ impl ReactionDiffusionSystem {
             .unwrap();
 
         // Create double buffers
-        let uvs_buffers = [
-            device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("UVs Buffer 0"),
-                size: (vec_capacity * std::mem::size_of::<UVPair>()) as u64,
-                usage: wgpu::BufferUsages::STORAGE
-                    | wgpu::BufferUsages::COPY_DST
-                    | wgpu::BufferUsages::COPY_SRC,
-                mapped_at_creation: true,
-            }),
-            device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("UVs Buffer 1"),
-                size: (vec_capacity * std::mem::size_of::<UVPair>()) as u64,
-                usage: wgpu::BufferUsages::STORAGE
-                    | wgpu::BufferUsages::COPY_DST
-                    | wgpu::BufferUsages::COPY_SRC,
-                mapped_at_creation: true,
-            }),
-        ];
-
-        // Write initial UVs data to both buffers
-        for buffer in &uvs_buffers {
-            let slice = buffer.slice(..);
-            slice
-                .get_mapped_range_mut()
-                .copy_from_slice(bytemuck::cast_slice(&uvs));
-            buffer.unmap();
-        }
+        let uvs_buffers = create_uvs_buffers(&device, &uvs);
 
         let params = SimulationParams {
             feed_rate,
@@ -133,8 +161,9 @@ impl ReactionDiffusionSystem {
             delta_v,
             width: width as u32,
             height: height as u32,
-            nutrient_pattern: 0, // Start with uniform pattern
-            is_nutrient_pattern_reversed: 0,
+            time: 0.0,
+            _padding: 0,
+            nutrient_mapping: NutrientMapping::default().as_array(),
         };
 
         let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -143,6 +172,16 @@ impl ReactionDiffusionSystem {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
+        // Start with a uniform nutrient pattern
+        let nutrient = NutrientGraph::default();
+        let nutrient_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Nutrient Graph Buffer"),
+            contents: bytemuck::cast_slice(&[nutrient.to_gpu()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let nutrient_map_buffer = create_nutrient_map_buffer(&device, &nutrient, width, height);
+        let nutrient_field_buffer = create_nutrient_field_buffer(&device, width, height);
+
         // Create bind group layout and pipeline
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Bind Group Layout"),
@@ -177,6 +216,36 @@ impl ReactionDiffusionSystem {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -200,45 +269,83 @@ impl ReactionDiffusionSystem {
             entry_point: "main",
         });
 
-        // Create bind groups for both buffers (input/output swapped)
-        let bind_groups = [
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Bind Group 0"),
-                layout: &bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: uvs_buffers[0].as_entire_binding(), // input
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: uvs_buffers[1].as_entire_binding(), // output
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: params_buffer.as_entire_binding(),
-                    },
-                ],
-            }),
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Bind Group 1"),
-                layout: &bind_group_layout,
+        let nutrient_field_pipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Nutrient Field Pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: "nutrient_field",
+            });
+
+        let bind_groups = create_bind_groups(
+            &device,
+            &bind_group_layout,
+            &uvs_buffers,
+            &params_buffer,
+            &nutrient_buffer,
+            &nutrient_map_buffer,
+            &nutrient_field_buffer,
+        );
+
+        let brush_params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Brush Params Buffer"),
+            size: std::mem::size_of::<BrushParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let brush_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Brush Bind Group Layout"),
                 entries: &[
-                    wgpu::BindGroupEntry {
+                    wgpu::BindGroupLayoutEntry {
                         binding: 0,
-                        resource: uvs_buffers[1].as_entire_binding(), // input
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    wgpu::BindGroupEntry {
+                    wgpu::BindGroupLayoutEntry {
                         binding: 1,
-                        resource: uvs_buffers[0].as_entire_binding(), // output
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: params_buffer.as_entire_binding(),
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
                 ],
-            }),
-        ];
+            });
+
+        let brush_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Brush Pipeline Layout"),
+                bind_group_layouts: &[&brush_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let brush_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Brush Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/brush.wgsl").into()),
+        });
+
+        let brush_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Brush Pipeline"),
+            layout: Some(&brush_pipeline_layout),
+            module: &brush_shader,
+            entry_point: "main",
+        });
+
+        let brush_bind_groups = create_brush_bind_groups(
+            &device,
+            &brush_bind_group_layout,
+            &uvs_buffers,
+            &brush_params_buffer,
+        );
 
         Self {
             width,
@@ -247,24 +354,105 @@ impl ReactionDiffusionSystem {
             kill_rate,
             delta_u,
             delta_v,
-            nutrient_pattern: 0,
-            is_nutrient_pattern_reversed: false,
+            nutrient,
+            nutrient_mapping: NutrientMapping::default(),
             uvs,
+            step_count: 0,
+            seed: 0,
+            history: CheckpointHistory::new(
+                (vec_capacity * std::mem::size_of::<UVPair>()) as u64,
+                history::DEFAULT_MEMORY_BUDGET,
+                history::DEFAULT_CHECKPOINT_INTERVAL,
+            ),
+            readbacks: ReadbackRing::new(),
+            nutrient_readbacks: ReadbackRing::new(),
+            nutrient_revision: 0,
+            last_nutrient_field_key: None,
+            probes: ProbeSampler::new(&device),
+            statistics: StatisticsPass::new(&device, &queue, &uvs_buffers, vec_capacity),
+            statistics_interval: None,
+            convergence: ConvergencePass::new(&device, &queue, &uvs_buffers, vec_capacity),
+            convergence_interval: None,
             device,
             queue,
             uvs_buffers,
             current_buffer: 0,
             params_buffer,
+            nutrient_buffer,
+            nutrient_map_buffer,
+            nutrient_field_buffer,
+            bind_group_layout,
             bind_groups,
             compute_pipeline,
+            nutrient_field_pipeline,
+            brush_params_buffer,
+            brush_bind_group_layout,
+            brush_bind_groups,
+            brush_pipeline,
         }
     }
 
-    pub fn uvs(&mut self) -> &[(f32, f32)] {
-        // Only read back when needed
+    /// Starts copying the current grid back to the CPU without waiting for it.
+    ///
+    /// Returns `None` if too many readbacks are already in flight; poll those to
+    /// completion first.
+    pub fn request_readback(&mut self) -> Option<ReadbackHandle> {
+        self.readbacks.start(
+            &self.device,
+            &self.queue,
+            &self.uvs_buffers[self.current_buffer],
+            (self.width, self.height),
+            self.step_count,
+            Some(readback::MAX_PENDING_READBACKS),
+        )
+    }
+
+    /// Checks on a readback without blocking.
+    pub fn poll_readback(&mut self, handle: ReadbackHandle) -> Readback {
+        self.device.poll(wgpu::Maintain::Poll);
+        self.readbacks.take(handle)
+    }
+
+    /// Blocks until a readback completes.
+    pub fn wait_readback(
+        &mut self,
+        handle: ReadbackHandle,
+    ) -> Result<Field, wgpu::BufferAsyncError> {
+        wait_for(&self.device, &mut self.readbacks, handle)
+    }
+
+    /// Copies the current grid back to the CPU, blocking until it arrives.
+    pub fn read_field(&mut self) -> Field {
+        let handle = self
+            .readbacks
+            .start(
+                &self.device,
+                &self.queue,
+                &self.uvs_buffers[self.current_buffer],
+                (self.width, self.height),
+                self.step_count,
+                None,
+            )
+            .expect("A readback without a limit always starts");
+        self.wait_readback(handle)
+            .expect("Failed to map the readback buffer")
+    }
+
+    /// Copies a rectangle of the current grid back to the CPU, blocking until
+    /// it arrives. Only the cells inside the region are transferred.
+    pub fn read_region(&mut self, region: Region) -> Field {
+        assert!(
+            region.x + region.width <= self.width && region.y + region.height <= self.height,
+            "Region {:?} does not fit in the {}x{} grid",
+            region,
+            self.width,
+            self.height
+        );
+
+        let cell_size = std::mem::size_of::<UVPair>();
         let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Staging Buffer"),
-            size: (self.width * self.height * std::mem::size_of::<UVPair>()) as u64,
+            label: Some("Region Staging Buffer"),
+            size: (region.width.max(1) * region.height.max(1) * cell_size) as u64,
             usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -272,16 +460,18 @@ impl ReactionDiffusionSystem {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Readback Encoder"),
+                label: Some("Region Readback Encoder"),
             });
-
-        encoder.copy_buffer_to_buffer(
-            &self.uvs_buffers[1 - self.current_buffer],
-            0,
-            &staging_buffer,
-            0,
-            staging_buffer.size(),
-        );
+        // Each row of the region is contiguous in the grid buffer
+        for row in 0..region.height {
+            encoder.copy_buffer_to_buffer(
+                &self.uvs_buffers[self.current_buffer],
+                (((region.y + row) * self.width + region.x) * cell_size) as u64,
+                &staging_buffer,
+                (row * region.width * cell_size) as u64,
+                (region.width * cell_size) as u64,
+            );
+        }
         self.queue.submit(Some(encoder.finish()));
 
         let buffer_slice = staging_buffer.slice(..);
@@ -292,50 +482,240 @@ impl ReactionDiffusionSystem {
         self.device.poll(wgpu::Maintain::Wait);
         rx.recv().unwrap().unwrap();
 
-        let data = buffer_slice.get_mapped_range();
-        self.uvs = bytemuck::cast_slice(&data).to_vec();
-        drop(data);
+        let mut data: Vec<UVPair> = bytemuck::cast_slice(&buffer_slice.get_mapped_range()).to_vec();
         staging_buffer.unmap();
+        data.truncate(region.width * region.height);
 
-        unsafe { std::mem::transmute(self.uvs.as_slice()) }
+        Field::new(region.width, region.height, self.step_count, data)
     }
 
-    pub fn set(&mut self, x: isize, y: isize, v: (f32, f32)) {
-        let index = self.get_index(x, y);
-        let v = (v.0.clamp(-1.0, 1.0), v.1.clamp(-1.0, 1.0));
+    /// Starts evaluating the nutrient factor of every cell with the same
+    /// shader code the simulation steps with, and copying the result back to
+    /// the CPU without waiting for it.
+    ///
+    /// Returns `None` if the field can't have changed since the last request,
+    /// as only animated patterns change between steps, or if too many
+    /// readbacks are already in flight.
+    pub fn request_nutrient_field(&mut self) -> Option<ReadbackHandle<NutrientField>> {
+        let key = self.nutrient_field_key();
+        if self.last_nutrient_field_key == Some(key) {
+            return None;
+        }
+        let handle = self.start_nutrient_field_readback(Some(readback::MAX_PENDING_READBACKS))?;
+        self.last_nutrient_field_key = Some(key);
+        Some(handle)
+    }
 
-        // Update CPU-side data
-        self.uvs[index] = UVPair { u: v.0, v: v.1 };
+    /// Checks on a nutrient field readback without blocking.
+    pub fn poll_nutrient_field(
+        &mut self,
+        handle: ReadbackHandle<NutrientField>,
+    ) -> Readback<NutrientField> {
+        self.device.poll(wgpu::Maintain::Poll);
+        let readback = self.nutrient_readbacks.take(handle);
+        if let Readback::Failed(_) = readback {
+            // Let the next request try again
+            self.last_nutrient_field_key = None;
+        }
+        readback
+    }
 
-        // Update GPU buffer
-        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Staging Buffer"),
-            size: std::mem::size_of::<UVPair>() as u64,
-            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::MAP_WRITE,
-            mapped_at_creation: true,
-        });
+    /// Evaluates the nutrient factor of every cell like
+    /// [`Self::request_nutrient_field`], blocking until the values arrive.
+    pub fn read_nutrient_field(&mut self) -> NutrientField {
+        let handle = self
+            .start_nutrient_field_readback(None)
+            .expect("A readback without a limit always starts");
+        wait_for(&self.device, &mut self.nutrient_readbacks, handle)
+            .expect("Failed to map the nutrient field readback buffer")
+    }
 
-        // Write the new value
-        let slice = staging_buffer.slice(..);
-        let mut view = slice.get_mapped_range_mut();
-        view.copy_from_slice(bytemuck::cast_slice(&[UVPair { u: v.0, v: v.1 }]));
-        drop(view);
-        staging_buffer.unmap();
+    /// What the nutrient field depends on: the graph and grid, and the time if
+    /// the graph is animated.
+    fn nutrient_field_key(&self) -> (u64, u64) {
+        let time = if self.nutrient.is_animated() {
+            self.step_count
+        } else {
+            0
+        };
+        (self.nutrient_revision, time)
+    }
+
+    fn start_nutrient_field_readback(
+        &mut self,
+        limit: Option<usize>,
+    ) -> Option<ReadbackHandle<NutrientField>> {
+        if limit.is_some_and(|limit| self.nutrient_readbacks.is_full(limit)) {
+            return None;
+        }
+        self.queue.write_buffer(
+            &self.params_buffer,
+            std::mem::offset_of!(SimulationParams, time) as u64,
+            bytemuck::bytes_of(&(self.simulated_time() as f32)),
+        );
 
-        // Copy to the main buffer
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Set Value Encoder"),
+                label: Some("Nutrient Field Encoder"),
             });
-        encoder.copy_buffer_to_buffer(
-            &staging_buffer,
-            0,
-            &self.uvs_buffers[self.current_buffer],
-            (index * std::mem::size_of::<UVPair>()) as u64,
-            std::mem::size_of::<UVPair>() as u64,
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Nutrient Field Pass"),
+            });
+            compute_pass.set_pipeline(&self.nutrient_field_pipeline);
+            compute_pass.set_bind_group(0, &self.bind_groups[self.current_buffer], &[]);
+            compute_pass.dispatch_workgroups(
+                (self.width as u32).div_ceil(8),
+                (self.height as u32).div_ceil(8),
+                1,
+            );
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        self.nutrient_readbacks.start(
+            &self.device,
+            &self.queue,
+            &self.nutrient_field_buffer,
+            (self.width, self.height),
+            self.step_count,
+            limit,
+        )
+    }
+
+    /// Starts sampling the cell at `(x, y)` after every step under `name`,
+    /// replacing any probe with the same name. Changing the probe set discards
+    /// samples that have not been taken yet.
+    pub fn add_probe(&mut self, name: impl Into<String>, x: usize, y: usize) {
+        assert!(
+            x < self.width && y < self.height,
+            "Probe ({}, {}) is outside the {}x{} grid",
+            x,
+            y,
+            self.width,
+            self.height
         );
+        let name = name.into();
+        let mut probes: Vec<Probe> = self
+            .probes
+            .probes()
+            .iter()
+            .filter(|p| p.name != name)
+            .cloned()
+            .collect();
+        probes.push(Probe { name, x, y });
+        self.probes
+            .set_probes(&self.device, &self.uvs_buffers, self.width, probes);
+    }
+
+    pub fn remove_probe(&mut self, name: &str) {
+        let probes: Vec<Probe> = self
+            .probes
+            .probes()
+            .iter()
+            .filter(|p| p.name != name)
+            .cloned()
+            .collect();
+        self.probes
+            .set_probes(&self.device, &self.uvs_buffers, self.width, probes);
+    }
+
+    pub fn clear_probes(&mut self) {
+        self.probes
+            .set_probes(&self.device, &self.uvs_buffers, self.width, Vec::new());
+    }
+
+    pub fn probes(&self) -> &[Probe] {
+        self.probes.probes()
+    }
+
+    /// Sets how many steps of samples are kept between calls to
+    /// [`Self::take_probe_series`]. Older samples are overwritten once full.
+    pub fn set_probe_capacity(&mut self, steps: usize) {
+        self.probes
+            .set_capacity(&self.device, &self.uvs_buffers, self.width, steps);
+    }
+
+    /// Returns the U and V time series of every probe since the last call.
+    pub fn take_probe_series(&mut self) -> Vec<ProbeSeries> {
+        self.probes.take_series(&self.device, &self.queue)
+    }
+
+    /// Reduces the grid to statistics every `steps` steps on the GPU, or stops
+    /// doing so if `None`. Results are picked up with [`Self::latest_statistics`].
+    pub fn set_statistics_interval(&mut self, steps: Option<u64>) {
+        self.statistics_interval = steps.map(|steps| steps.max(1));
+    }
+
+    pub fn statistics_interval(&self) -> Option<u64> {
+        self.statistics_interval
+    }
+
+    /// The newest statistics gathered by the periodic GPU pass, without
+    /// blocking. A reduction whose readback fails is skipped.
+    pub fn latest_statistics(&mut self) -> Option<&FieldStatistics> {
+        if self.statistics.is_submitted() {
+            self.device.poll(wgpu::Maintain::Poll);
+            let _ = self.statistics.collect();
+        }
+        self.statistics.latest()
+    }
+
+    /// Computes statistics of the current grid on the GPU, blocking until
+    /// they arrive.
+    pub fn compute_statistics(&mut self) -> Result<FieldStatistics, wgpu::BufferAsyncError> {
+        // Only one reduction can be in flight, so let a periodic one finish first
+        while !self.statistics.is_idle() {
+            self.device.poll(wgpu::Maintain::Wait);
+            self.statistics.collect()?;
+        }
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Statistics Encoder"),
+            });
+        self.statistics
+            .encode(&mut encoder, self.current_buffer, self.step_count);
         self.queue.submit(Some(encoder.finish()));
+        self.statistics.start_mapping();
+
+        while !self.statistics.is_idle() {
+            self.device.poll(wgpu::Maintain::Wait);
+            self.statistics.collect()?;
+        }
+        Ok(self
+            .statistics
+            .latest()
+            .cloned()
+            .expect("A completed reduction leaves statistics behind"))
+    }
+
+    /// Measures the change over a step every `steps` steps on the GPU, or stops
+    /// doing so if `None`. Samples are picked up with [`Self::take_change_sample`]
+    /// and fed to a [`crate::convergence::ConvergenceMonitor`].
+    pub fn set_convergence_interval(&mut self, steps: Option<u64>) {
+        self.convergence_interval = steps.map(|steps| steps.max(1));
+    }
+
+    pub fn convergence_interval(&self) -> Option<u64> {
+        self.convergence_interval
+    }
+
+    /// The newest change sample, without blocking. Each sample is returned
+    /// once; a reduction whose readback fails is skipped.
+    pub fn take_change_sample(&mut self) -> Option<ChangeSample> {
+        if self.convergence.is_submitted() {
+            self.device.poll(wgpu::Maintain::Poll);
+            let _ = self.convergence.collect();
+        }
+        self.convergence.take()
+    }
+
+    /// Copies the current grid back to the CPU, blocking until it arrives.
+    pub fn uvs(&mut self) -> &[UVPair] {
+        self.uvs = self.read_field().into_vec();
+        &self.uvs
     }
 
     pub fn set_all(&mut self, values: &[(f32, f32)]) {
@@ -384,15 +764,143 @@ impl ReactionDiffusionSystem {
         self.queue.submit(Some(encoder.finish()));
     }
 
-    fn get_index(&self, x: isize, y: isize) -> usize {
-        let width = self.width as isize;
-        let height = self.height as isize;
-        let x = (x + width) % width;
-        let y = (y + height) % height;
-        (y * width + x) as usize
+    /// Changes the grid to `width` by `height` cells, carrying the current
+    /// state over by resampling it. Probes are moved to the same relative
+    /// position and checkpoints from before the resize are discarded; the step
+    /// count carries on.
+    pub fn resize(&mut self, width: usize, height: usize, mode: ResampleMode) {
+        assert!(
+            width > 0 && height > 0,
+            "Reaction diffusion system must be at least 1x1 but {}x{} was passed",
+            width,
+            height
+        );
+        if width == self.width && height == self.height {
+            return;
+        }
+
+        let field = self.read_field().resample(width, height, mode);
+        let probes: Vec<Probe> = self
+            .probes
+            .probes()
+            .iter()
+            .map(|p| Probe {
+                name: p.name.clone(),
+                x: p.x * width / self.width,
+                y: p.y * height / self.height,
+            })
+            .collect();
+
+        self.width = width;
+        self.height = height;
+        self.uvs = field.into_vec();
+        self.uvs_buffers = create_uvs_buffers(&self.device, &self.uvs);
+        self.current_buffer = 0;
+        self.nutrient_map_buffer =
+            create_nutrient_map_buffer(&self.device, &self.nutrient, width, height);
+        self.nutrient_field_buffer = create_nutrient_field_buffer(&self.device, width, height);
+        self.nutrient_revision += 1;
+        self.rebuild_bind_groups();
+        self.brush_bind_groups = create_brush_bind_groups(
+            &self.device,
+            &self.brush_bind_group_layout,
+            &self.uvs_buffers,
+            &self.brush_params_buffer,
+        );
+        self.write_params();
+        self.probes
+            .set_probes(&self.device, &self.uvs_buffers, self.width, probes);
+        self.statistics
+            .rebind(&self.device, &self.queue, &self.uvs_buffers, width * height);
+        self.convergence
+            .rebind(&self.device, &self.queue, &self.uvs_buffers, width * height);
+        self.history
+            .set_buffer_size((width * height * std::mem::size_of::<UVPair>()) as u64);
+        self.restart_history();
+    }
+
+    /// Paints a brush stroke into the current state with a single compute
+    /// dispatch covering the brush's bounding box. A box wider or taller than
+    /// the grid is cut down to one grid's worth centred on the brush, so that
+    /// no two invocations touch the same cell. The box is moved by whole grids
+    /// to start inside the grid, as the shader can't wrap negative cells.
+    /// Returns an error for a stroke that fails [`BrushStroke::validate`].
+    pub fn apply_brush(&mut self, stroke: &BrushStroke) -> Result<(), String> {
+        stroke.validate()?;
+        if stroke.radius == 0.0 {
+            return Ok(());
+        }
+
+        // Only one grid's worth of the box is ever painted, so the padded
+        // extent can't overflow however large the radius is
+        let extent = ((stroke.radius * 2.0).ceil() as u32)
+            .min(self.width.max(self.height) as u32)
+            .saturating_add(2);
+        let axis = |center: f32, size: usize| {
+            // Far-off centres would push the origin past the range of an i32
+            let center = center.rem_euclid(size as f32);
+            let (origin, extent) = if extent as usize <= size {
+                ((center - stroke.radius).floor() as i32, extent)
+            } else {
+                ((center - size as f32 / 2.0).floor() as i32, size as u32)
+            };
+            let wrapped = origin.rem_euclid(size as i32);
+            (center + (wrapped - origin) as f32, wrapped, extent)
+        };
+        let (center_x, origin_x, extent_x) = axis(stroke.x, self.width);
+        let (center_y, origin_y, extent_y) = axis(stroke.y, self.height);
+
+        let params = BrushParams {
+            center_x,
+            center_y,
+            radius: stroke.radius,
+            falloff: stroke.falloff as u32,
+            target_u: stroke.target.0.clamp(0.0, 1.0),
+            target_v: stroke.target.1.clamp(0.0, 1.0),
+            strength: stroke.strength,
+            width: self.width as u32,
+            height: self.height as u32,
+            origin_x,
+            origin_y,
+            extent_x,
+            extent_y,
+            _padding: [0; 3],
+        };
+        self.queue.write_buffer(
+            &self.brush_params_buffer,
+            0,
+            bytemuck::cast_slice(&[params]),
+        );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Brush Encoder"),
+            });
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Brush Pass"),
+            });
+
+            compute_pass.set_pipeline(&self.brush_pipeline);
+            compute_pass.set_bind_group(0, &self.brush_bind_groups[self.current_buffer], &[]);
+            compute_pass.dispatch_workgroups(extent_x.div_ceil(8), extent_y.div_ceil(8), 1);
+        }
+
+        self.queue.submit(Some(encoder.finish()));
+        Ok(())
     }
 
     pub fn update(&mut self) {
+        if self.nutrient.is_animated() {
+            self.queue.write_buffer(
+                &self.params_buffer,
+                std::mem::offset_of!(SimulationParams, time) as u64,
+                bytemuck::bytes_of(&(self.simulated_time() as f32)),
+            );
+        }
+
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -407,65 +915,281 @@ impl ReactionDiffusionSystem {
             compute_pass.set_pipeline(&self.compute_pipeline);
             compute_pass.set_bind_group(0, &self.bind_groups[self.current_buffer], &[]);
             compute_pass.dispatch_workgroups(
-                (self.width as u32 + 7) / 8,
-                (self.height as u32 + 7) / 8,
+                (self.width as u32).div_ceil(8),
+                (self.height as u32).div_ceil(8),
                 1,
             );
         }
 
+        self.step_count += 1;
+        self.probes.sample(
+            &self.queue,
+            &mut encoder,
+            1 - self.current_buffer,
+            self.step_count,
+        );
+        if self.history.is_due(self.step_count) {
+            self.history.record(
+                &self.device,
+                &mut encoder,
+                &self.uvs_buffers[1 - self.current_buffer],
+                self.step_count,
+            );
+        }
+        if self
+            .statistics_interval
+            .is_some_and(|interval| self.step_count.is_multiple_of(interval))
+        {
+            self.statistics
+                .encode(&mut encoder, 1 - self.current_buffer, self.step_count);
+        }
+        if self
+            .convergence_interval
+            .is_some_and(|interval| self.step_count.is_multiple_of(interval))
+        {
+            self.convergence
+                .encode(&mut encoder, 1 - self.current_buffer, self.step_count);
+        }
+
         self.queue.submit(Some(encoder.finish()));
+        self.statistics.start_mapping();
+        self.convergence.start_mapping();
         self.current_buffer = 1 - self.current_buffer; // Toggle between 0 and 1
     }
 
-    pub fn update_rates(&mut self, feed_rate: f32, kill_rate: f32) {
-        self.feed_rate = feed_rate;
-        self.kill_rate = kill_rate;
-
-        // Update the params buffer with new rates
-        let params = SimulationParams {
-            feed_rate,
-            kill_rate,
-            delta_u: self.delta_u,
-            delta_v: self.delta_v,
-            width: self.width as u32,
-            height: self.height as u32,
-            nutrient_pattern: self.nutrient_pattern,
-            is_nutrient_pattern_reversed: if self.is_nutrient_pattern_reversed {
-                1
-            } else {
-                0
-            },
-        };
-
-        let staging_buffer = self
+    /// Checkpoints the current state, starting a fresh history.
+    fn restart_history(&mut self) {
+        self.history.clear();
+        let mut encoder = self
             .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Staging Buffer"),
-                contents: bytemuck::cast_slice(&[params]),
-                usage: wgpu::BufferUsages::COPY_SRC,
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Checkpoint Encoder"),
             });
+        self.history.record(
+            &self.device,
+            &mut encoder,
+            &self.uvs_buffers[self.current_buffer],
+            self.step_count,
+        );
+        self.queue.submit(Some(encoder.finish()));
+    }
 
+    /// Winds the grid back to the newest checkpoint taken before the current
+    /// step. Simulation resumes from that point on the next update. Returns
+    /// `false` if there was nothing to go back to.
+    pub fn rewind(&mut self) -> bool {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Update Rates Encoder"),
+                label: Some("Rewind Encoder"),
             });
-        encoder.copy_buffer_to_buffer(
-            &staging_buffer,
-            0,
-            &self.params_buffer,
-            0,
-            std::mem::size_of::<SimulationParams>() as u64,
-        );
+        let Some(step_count) = self.history.rewind(
+            &mut encoder,
+            &self.uvs_buffers[self.current_buffer],
+            self.step_count,
+        ) else {
+            return false;
+        };
         self.queue.submit(Some(encoder.finish()));
+        self.step_count = step_count;
+        true
     }
 
+    pub fn history(&self) -> &CheckpointHistory {
+        &self.history
+    }
+
+    /// Sets how much GPU memory checkpoints may use. Discards existing checkpoints.
+    pub fn set_history_budget(&mut self, bytes: u64) {
+        self.history.set_memory_budget(bytes);
+        self.restart_history();
+    }
+
+    pub fn set_checkpoint_interval(&mut self, steps: u64) {
+        self.history.set_interval(steps);
+    }
+
+    /// Number of steps simulated since the grid was last seeded.
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    /// Simulated time since the grid was last seeded.
+    pub fn simulated_time(&self) -> f64 {
+        self.step_count as f64 * TIME_STEP
+    }
+
+    /// The seed the current run's initial condition was generated from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Records the seed of a freshly seeded initial condition and restarts the
+    /// step count.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.step_count = 0;
+        self.restart_history();
+    }
+
+    pub fn feed_rate(&self) -> f32 {
+        self.feed_rate
+    }
+
+    pub fn kill_rate(&self) -> f32 {
+        self.kill_rate
+    }
+
+    pub fn delta_u(&self) -> f32 {
+        self.delta_u
+    }
+
+    pub fn delta_v(&self) -> f32 {
+        self.delta_v
+    }
+
+    pub fn nutrient_graph(&self) -> &NutrientGraph {
+        &self.nutrient
+    }
+
+    pub fn nutrient_mapping(&self) -> NutrientMapping {
+        self.nutrient_mapping
+    }
+
+    /// Sets which rates the nutrient factor scales, and how strongly.
+    ///
+    /// Panics if a strength isn't finite.
+    pub fn set_nutrient_mapping(&mut self, mapping: NutrientMapping) {
+        assert!(
+            mapping.is_finite(),
+            "Nutrient mapping strengths must be finite but {} was passed",
+            mapping
+        );
+        self.nutrient_mapping = mapping;
+        self.write_params();
+    }
+
+    /// Captures the full simulation state, reading the grid back from the GPU.
+    pub fn snapshot(&mut self) -> Snapshot {
+        let uvs = self.uvs().iter().map(|uv| (uv.u, uv.v)).collect();
+        Snapshot {
+            width: self.width,
+            height: self.height,
+            feed_rate: self.feed_rate,
+            kill_rate: self.kill_rate,
+            delta_u: self.delta_u,
+            delta_v: self.delta_v,
+            nutrient: self.nutrient.clone(),
+            nutrient_mapping: self.nutrient_mapping,
+            step_count: self.step_count,
+            seed: self.seed,
+            uvs,
+        }
+    }
+
+    /// Replaces the simulation state with a snapshot, resizing the grid to the
+    /// snapshot's dimensions if they differ. A snapshot that fails
+    /// [`Snapshot::validate`] is rejected before anything changes.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        snapshot
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // The grid is overwritten below, so the cheapest resampling will do
+        self.resize(snapshot.width, snapshot.height, ResampleMode::Nearest);
+        self.delta_u = snapshot.delta_u;
+        self.delta_v = snapshot.delta_v;
+        self.set_nutrient_graph(snapshot.nutrient.clone())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.set_nutrient_mapping(snapshot.nutrient_mapping);
+        self.update_rates(snapshot.feed_rate, snapshot.kill_rate);
+        self.set_all(&snapshot.uvs);
+        self.step_count = snapshot.step_count;
+        self.seed = snapshot.seed;
+        self.restart_history();
+        Ok(())
+    }
+
+    /// Writes the full simulation state to `path`. See [`Snapshot`] for the format.
+    pub fn save_state(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.snapshot().save(path)
+    }
+
+    /// Loads a state written by [`Self::save_state`].
+    pub fn load_state(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let snapshot = Snapshot::load(path)?;
+        self.restore(&snapshot)
+    }
+
+    /// Exports the U/V data as a NumPy `.npy` or `.npz` file, chosen by extension.
+    pub fn export_numpy(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.snapshot().export_numpy(path)
+    }
+
+    pub fn update_rates(&mut self, feed_rate: f32, kill_rate: f32) {
+        self.feed_rate = feed_rate;
+        self.kill_rate = kill_rate;
+        self.write_params();
+    }
+
+    /// Sets the feed and kill rates along with the diffusion rates of U and V.
+    pub fn update_params(&mut self, feed_rate: f32, kill_rate: f32, delta_u: f32, delta_v: f32) {
+        self.delta_u = delta_u;
+        self.delta_v = delta_v;
+        self.update_rates(feed_rate, kill_rate);
+    }
+
+    /// Switches to a single pattern with its default parameters. Unknown
+    /// patterns are uniform.
     pub fn set_nutrient_pattern(&mut self, pattern_index: u32, is_reversed: bool) {
-        // Update CPU-side state
-        self.nutrient_pattern = pattern_index;
-        self.is_nutrient_pattern_reversed = is_reversed;
+        let pattern = NutrientPattern::from_u32(pattern_index).unwrap_or(NutrientPattern::Uniform);
+        let params = NutrientParams {
+            is_reversed,
+            ..pattern.default_params()
+        };
+        self.set_nutrient_graph(NutrientGraph::with_params(pattern, params))
+            .expect("A single pattern with its default parameters is valid");
+    }
+
+    /// Replaces the nutrient patterns that scale the feed rate, or leaves them
+    /// alone if the graph fails [`NutrientGraph::validate`].
+    pub fn set_nutrient_graph(&mut self, graph: NutrientGraph) -> Result<(), String> {
+        graph
+            .validate()
+            .map_err(|e| format!("Invalid nutrient graph {}: {}", graph, e))?;
+        let is_same_image = match (graph.image_map(), self.nutrient.image_map()) {
+            (Some(new), Some(old)) => Arc::ptr_eq(new, old),
+            (new, old) => new.is_none() && old.is_none(),
+        };
+        self.nutrient = graph;
+        self.nutrient_revision += 1;
+        self.queue.write_buffer(
+            &self.nutrient_buffer,
+            0,
+            bytemuck::cast_slice(&[self.nutrient.to_gpu()]),
+        );
+        // Resampling an image is slow, so only do it when it changes
+        if !is_same_image {
+            self.nutrient_map_buffer =
+                create_nutrient_map_buffer(&self.device, &self.nutrient, self.width, self.height);
+            self.rebuild_bind_groups();
+        }
+        Ok(())
+    }
+
+    fn rebuild_bind_groups(&mut self) {
+        self.bind_groups = create_bind_groups(
+            &self.device,
+            &self.bind_group_layout,
+            &self.uvs_buffers,
+            &self.params_buffer,
+            &self.nutrient_buffer,
+            &self.nutrient_map_buffer,
+            &self.nutrient_field_buffer,
+        );
+    }
 
-        // Update GPU-side state
+    /// Uploads the CPU-side parameters to the params buffer.
+    fn write_params(&self) {
         let params = SimulationParams {
             feed_rate: self.feed_rate,
             kill_rate: self.kill_rate,
@@ -473,8 +1197,9 @@ impl ReactionDiffusionSystem {
             delta_v: self.delta_v,
             width: self.width as u32,
             height: self.height as u32,
-            nutrient_pattern: pattern_index,
-            is_nutrient_pattern_reversed: if is_reversed { 1 } else { 0 },
+            time: self.simulated_time() as f32,
+            _padding: 0,
+            nutrient_mapping: self.nutrient_mapping.as_array(),
         };
 
         let staging_buffer = self
@@ -488,7 +1213,7 @@ impl ReactionDiffusionSystem {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Update Nutrient Pattern Encoder"),
+                label: Some("Update Params Encoder"),
             });
         encoder.copy_buffer_to_buffer(
             &staging_buffer,
@@ -500,8 +1225,143 @@ impl ReactionDiffusionSystem {
         self.queue.submit(Some(encoder.finish()));
     }
 
+    /// Reverses every pattern in the nutrient graph.
     pub fn toggle_nutrient_pattern_reversal(&mut self) {
-        self.is_nutrient_pattern_reversed = !self.is_nutrient_pattern_reversed;
-        self.set_nutrient_pattern(self.nutrient_pattern, self.is_nutrient_pattern_reversed);
+        let mut graph = self.nutrient.clone();
+        graph.toggle_reversal();
+        self.set_nutrient_graph(graph)
+            .expect("Reversing patterns keeps a graph valid");
     }
 }
+
+/// Blocks until a readback from `readbacks` completes.
+fn wait_for<T: ReadbackData>(
+    device: &wgpu::Device,
+    readbacks: &mut ReadbackRing<T>,
+    mut handle: ReadbackHandle<T>,
+) -> Result<T, wgpu::BufferAsyncError> {
+    loop {
+        device.poll(wgpu::Maintain::Wait);
+        match readbacks.take(handle) {
+            Readback::Ready(data) => return Ok(data),
+            Readback::Failed(e) => return Err(e),
+            Readback::Pending(pending) => handle = pending,
+        }
+    }
+}
+
+/// Creates the pair of grid buffers, both filled with `uvs`.
+fn create_uvs_buffers(device: &wgpu::Device, uvs: &[UVPair]) -> [wgpu::Buffer; 2] {
+    [0, 1].map(|i| {
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("UVs Buffer {}", i)),
+            contents: bytemuck::cast_slice(uvs),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+        })
+    })
+}
+
+/// Creates the buffer holding the nutrient graph's image resampled to the
+/// grid, or a single placeholder value if the graph has no image.
+fn create_nutrient_map_buffer(
+    device: &wgpu::Device,
+    nutrient: &NutrientGraph,
+    width: usize,
+    height: usize,
+) -> wgpu::Buffer {
+    let values = match nutrient.image_map() {
+        Some(map) => map.resampled(width, height),
+        None => vec![1.0],
+    };
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Nutrient Map Buffer"),
+        contents: bytemuck::cast_slice(&values),
+        usage: wgpu::BufferUsages::STORAGE,
+    })
+}
+
+/// Creates the buffer the shader writes every cell's nutrient factor into.
+fn create_nutrient_field_buffer(
+    device: &wgpu::Device,
+    width: usize,
+    height: usize,
+) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Nutrient Field Buffer"),
+        size: (width * height * std::mem::size_of::<f32>()) as u64,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    })
+}
+
+/// Creates bind groups for both buffers, with input and output swapped.
+fn create_bind_groups(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uvs_buffers: &[wgpu::Buffer; 2],
+    params_buffer: &wgpu::Buffer,
+    nutrient_buffer: &wgpu::Buffer,
+    nutrient_map_buffer: &wgpu::Buffer,
+    nutrient_field_buffer: &wgpu::Buffer,
+) -> [wgpu::BindGroup; 2] {
+    [0, 1].map(|i| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("Bind Group {}", i)),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uvs_buffers[i].as_entire_binding(), // input
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uvs_buffers[1 - i].as_entire_binding(), // output
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: nutrient_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: nutrient_map_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: nutrient_field_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    })
+}
+
+/// The brush paints in place into whichever buffer holds the current state,
+/// so it gets one bind group per buffer.
+fn create_brush_bind_groups(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uvs_buffers: &[wgpu::Buffer; 2],
+    brush_params_buffer: &wgpu::Buffer,
+) -> [wgpu::BindGroup; 2] {
+    [0, 1].map(|i| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("Brush Bind Group {}", i)),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uvs_buffers[i].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: brush_params_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    })
+}
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+
+/// Default amount of GPU memory given over to checkpoints.
+pub const DEFAULT_MEMORY_BUDGET: u64 = 256 * 1024 * 1024;
+/// Default number of simulation steps between checkpoints.
+pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 100;
+
+struct Checkpoint {
+    slot: usize,
+    step_count: u64,
+}
+
+/// A ring of GPU-side copies of the U/V grid taken every few steps, so a run
+/// can be wound back after a bad parameter change.
+///
+/// Checkpoint buffers are allocated lazily, up to as many as fit in the memory
+/// budget. Once full, the oldest checkpoint's buffer is reused.
+pub struct CheckpointHistory {
+    slots: Vec<wgpu::Buffer>,
+    free_slots: Vec<usize>,
+    checkpoints: VecDeque<Checkpoint>,
+    buffer_size: u64,
+    memory_budget: u64,
+    capacity: usize,
+    interval: u64,
+}
+
+impl CheckpointHistory {
+    pub fn new(buffer_size: u64, memory_budget: u64, interval: u64) -> Self {
+        Self {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            checkpoints: VecDeque::new(),
+            buffer_size,
+            memory_budget,
+            capacity: (memory_budget / buffer_size.max(1)) as usize,
+            interval: interval.max(1),
+        }
+    }
+
+    /// Maximum number of checkpoints kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of checkpoints currently held.
+    pub fn len(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checkpoints.is_empty()
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    pub fn set_interval(&mut self, interval: u64) {
+        self.interval = interval.max(1);
+    }
+
+    /// Changes the memory budget. Existing checkpoints are discarded and their
+    /// buffers released.
+    pub fn set_memory_budget(&mut self, memory_budget: u64) {
+        self.memory_budget = memory_budget;
+        self.release();
+    }
+
+    /// Changes the size of the grid being checkpointed. Existing checkpoints
+    /// are discarded and their buffers released.
+    pub fn set_buffer_size(&mut self, buffer_size: u64) {
+        self.buffer_size = buffer_size;
+        self.release();
+    }
+
+    /// Whether a checkpoint is due after reaching `step_count`.
+    pub fn is_due(&self, step_count: u64) -> bool {
+        self.capacity > 0 && step_count.is_multiple_of(self.interval)
+    }
+
+    /// Records a copy of `source` as the state at `step_count`.
+    pub fn record(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::Buffer,
+        step_count: u64,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        // Reusing the current step would leave two checkpoints for one state
+        self.discard_from(step_count);
+
+        let slot = if let Some(slot) = self.free_slots.pop() {
+            slot
+        } else if self.slots.len() < self.capacity {
+            self.slots
+                .push(device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Checkpoint Buffer"),
+                    size: self.buffer_size,
+                    usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }));
+            self.slots.len() - 1
+        } else {
+            self.drop_oldest();
+            self.free_slots.pop().unwrap()
+        };
+
+        encoder.copy_buffer_to_buffer(source, 0, &self.slots[slot], 0, self.buffer_size);
+        self.checkpoints.push_back(Checkpoint { slot, step_count });
+    }
+
+    /// Finds the newest checkpoint from before `step_count`, discarding any
+    /// newer ones, and copies it into `destination`. Returns the step count of
+    /// the restored state, or `None` if there is nothing older to go back to.
+    pub fn rewind(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        destination: &wgpu::Buffer,
+        step_count: u64,
+    ) -> Option<u64> {
+        self.discard_from(step_count);
+
+        // Keep the checkpoint itself so the run can be rewound to it again
+        let checkpoint = self.checkpoints.back()?;
+        encoder.copy_buffer_to_buffer(
+            &self.slots[checkpoint.slot],
+            0,
+            destination,
+            0,
+            self.buffer_size,
+        );
+        Some(checkpoint.step_count)
+    }
+
+    /// Forgets every checkpoint, keeping the buffers for reuse.
+    pub fn clear(&mut self) {
+        self.checkpoints.clear();
+        self.free_slots = (0..self.slots.len()).collect();
+    }
+
+    fn release(&mut self) {
+        self.capacity = (self.memory_budget / self.buffer_size.max(1)) as usize;
+        self.slots.clear();
+        self.free_slots.clear();
+        self.checkpoints.clear();
+    }
+
+    /// Discards checkpoints taken at or after `step_count`.
+    fn discard_from(&mut self, step_count: u64) {
+        while self
+            .checkpoints
+            .back()
+            .is_some_and(|c| c.step_count >= step_count)
+        {
+            let checkpoint = self.checkpoints.pop_back().unwrap();
+            self.free_slots.push(checkpoint.slot);
+        }
+    }
+
+    fn drop_oldest(&mut self) {
+        if let Some(checkpoint) = self.checkpoints.pop_front() {
+            self.free_slots.push(checkpoint.slot);
+        }
+    }
+}
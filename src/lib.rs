@@ -1,10 +1,33 @@
+pub mod analysis;
+pub mod brush;
+pub mod convergence;
+pub mod cpu_model;
+pub mod field;
 pub mod gray_scott_model;
+pub mod history;
 pub mod lut_manager;
 pub mod model_presets;
+pub mod nutrient_graph;
+pub mod nutrient_map;
+pub mod nutrient_mapping;
 pub mod nutrient_presets;
+pub mod phase_diagram;
+pub mod probes;
+pub mod readback;
 pub mod renderer;
+pub mod seeders;
+pub mod snapshot;
+pub mod stability;
+pub mod statistics;
 
 // Re-export commonly used items
+pub use cpu_model::CpuReactionDiffusionSystem;
+pub use field::{Field, Region, ResampleMode, UVPair};
 pub use gray_scott_model::ReactionDiffusionSystem;
 pub use lut_manager::LutData;
-pub use nutrient_presets::NutrientPattern;
+pub use nutrient_graph::{NutrientField, NutrientGraph};
+pub use nutrient_map::NutrientMap;
+pub use nutrient_mapping::NutrientMapping;
+pub use nutrient_presets::{NutrientParams, NutrientPattern};
+pub use seeders::Seeder;
+pub use statistics::FieldStatistics;
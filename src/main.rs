@@ -1,11 +1,35 @@
+mod cli;
+mod nutrient_overlay;
+mod statistics_graph;
+
 use circular_queue::CircularQueue;
+use clap::Parser;
+use cli::{Args, Command};
 use fontdue::Font;
 use gray_scott_reaction_diffusion::{
-    LutData, NutrientPattern, ReactionDiffusionSystem, lut_manager::LutManager, model_presets,
-    renderer::Renderer,
+    Field, LutData, NutrientField, NutrientGraph, NutrientMap, NutrientMapping, NutrientParams,
+    NutrientPattern, ReactionDiffusionSystem, Region, ResampleMode, Seeder,
+    analysis::{
+        MitosisTracker, PowerSpectrum, Segmentation, SegmentationOptions, SpectralAnalysis,
+        SpectrumWindow, SpotCountChange,
+    },
+    brush::BrushStroke,
+    convergence::{ConvergenceEvent, ConvergenceMonitor},
+    lut_manager::LutManager,
+    model_presets::{self, Preset, PresetRegistry},
+    phase_diagram::{Marker, PhaseDiagram, PhaseDiagramConfig},
+    probes,
+    readback::{Readback, ReadbackHandle},
+    renderer::{OverlayRect, Renderer, UpscaleFilter},
+    seeders::{self, SeederSpec},
+    stability::StabilityAnalysis,
 };
-use log::error;
+use log::{error, info, warn};
+use nutrient_overlay::NutrientView;
 use rand::Rng;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use winit::dpi::LogicalSize;
 use winit::event::{Event, MouseButton};
@@ -27,6 +51,156 @@ fn calculate_window_dimensions(monitor: &winit::monitor::MonitorHandle) -> (u32,
     (logical_size.width as u32, logical_size.height as u32)
 }
 
+/// The grid size that gives `scale` simulation cells per logical pixel of `window`.
+fn grid_size_for_window(window: &Window, scale: f32) -> (usize, usize) {
+    let size = window.inner_size().to_logical::<f64>(window.scale_factor());
+    (
+        ((size.width * scale as f64).round() as usize).max(1),
+        ((size.height * scale as f64).round() as usize).max(1),
+    )
+}
+
+/// Steps between GPU statistics reductions while the statistics graph is shown.
+const STATISTICS_INTERVAL: u64 = 10;
+
+/// Largest region, in cells, whose spectrum is shown. Bigger grids are
+/// analysed through a window at their centre to keep the FFT cheap.
+const SPECTRUM_MAX_SIZE: usize = 256;
+
+/// How often the spectrum overlay is recomputed while shown.
+const SPECTRUM_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Least time between nutrient field readbacks while the overlay is shown.
+/// Only animated patterns are read more than once.
+const NUTRIENT_OVERLAY_REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How often the pattern is segmented while spot tracking is on.
+const SEGMENTATION_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Simulation speeds, in steps per frame, stepped through with - and =.
+const SPEED_STEPS: [f32; 9] = [0.125, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0];
+
+/// The resolution scales stepped through with Page Up and Page Down.
+const GRID_SCALE_STEPS: [f32; 7] = [0.25, 0.375, 0.5, 0.75, 1.0, 1.5, 2.0];
+
+/// Where the quicksave and quickload keys read and write the simulation state.
+const QUICKSAVE_PATH: &str = "quicksave.gsrd";
+
+/// How long attract mode takes to morph from one preset to the next.
+const ATTRACT_TRANSITION_DURATION: Duration = Duration::from_secs(10);
+
+/// Seeds `system` from `seeder` with a fresh random seed.
+fn seed_system(system: &mut ReactionDiffusionSystem, seeder: &dyn Seeder) {
+    let seed = rand::random();
+    let values = seeder.generate(system.width, system.height, seed);
+    system.set_all(&values);
+    system.set_seed(seed);
+}
+
+/// Runs a command-line tool in place of the visualizer.
+fn run_command(command: &Command) {
+    match *command {
+        Command::Stability {
+            feed_rate,
+            kill_rate,
+            delta_u,
+            delta_v,
+            dispersion,
+        } => {
+            let analysis = StabilityAnalysis::new(feed_rate, kill_rate, delta_u, delta_v);
+            println!("{}", analysis);
+            if dispersion {
+                println!("\nstate,u,v,wavenumber,growth_rate");
+                for (index, stability) in analysis.states.iter().enumerate() {
+                    let relation = &stability.dispersion;
+                    for (wavenumber, growth_rate) in
+                        relation.wavenumbers.iter().zip(&relation.growth_rates)
+                    {
+                        println!(
+                            "{},{},{},{},{}",
+                            index, stability.state.u, stability.state.v, wavenumber, growth_rate
+                        );
+                    }
+                }
+            }
+        }
+        Command::PhaseDiagram {
+            ref output,
+            feed_min,
+            feed_max,
+            kill_min,
+            kill_max,
+            samples,
+            grid_size,
+            steps,
+            ref seeder,
+            seed,
+        } => {
+            let mut seeders = seeders::builtin();
+            let seeder_index = match seeder.apply(&mut seeders) {
+                Ok(index) => index,
+                Err(e) => {
+                    eprintln!("Invalid seeder: {}", e);
+                    return;
+                }
+            };
+            let config = PhaseDiagramConfig {
+                feed_range: (feed_min, feed_max),
+                kill_range: (kill_min, kill_max),
+                feed_samples: samples.max(1),
+                kill_samples: samples.max(1),
+                grid_size: grid_size.max(1),
+                max_steps: steps,
+                seed,
+                delta_u: model_presets::DEFAULT_DELTA_U,
+                delta_v: model_presets::DEFAULT_DELTA_V,
+            };
+            let diagram = PhaseDiagram::compute_with_progress(
+                config,
+                seeders[seeder_index].as_ref(),
+                |finished, total| {
+                    if finished % (total / 20).max(1) == 0 || finished == total {
+                        eprintln!("{}/{} points simulated", finished, total);
+                    }
+                },
+            );
+
+            let font = Font::from_bytes(
+                include_bytes!("../Texturina-VariableFont_opsz,wght.ttf").as_ref(),
+                fontdue::FontSettings::default(),
+            )
+            .expect("Font is valid");
+            // Only presets simulated with the same diffusion rates as the
+            // diagram belong on it
+            let markers: Vec<Marker> = PresetRegistry::builtin()
+                .iter()
+                .filter(|preset| preset.name != model_presets::CUSTOM.name)
+                .filter(|preset| {
+                    (preset.delta_u, preset.delta_v)
+                        == (diagram.config.delta_u, diagram.config.delta_v)
+                })
+                .map(|preset| Marker {
+                    name: preset.name.to_string(),
+                    feed_rate: preset.feed_rate,
+                    kill_rate: preset.kill_rate,
+                })
+                .collect();
+            if let Err(e) = diagram.render(&font, &markers).save(output) {
+                eprintln!("Failed to write {}: {}", output.display(), e);
+                return;
+            }
+            let csv_path = output.with_extension("csv");
+            let written = std::fs::File::create(&csv_path)
+                .and_then(|file| diagram.write_csv(std::io::BufWriter::new(file)));
+            if let Err(e) = written {
+                eprintln!("Failed to write {}: {}", csv_path.display(), e);
+                return;
+            }
+            println!("Wrote {} and {}", output.display(), csv_path.display());
+        }
+    }
+}
+
 // Helper function for linear interpolation of u8 values
 fn lerp_u8(a: u8, b: u8, t: f32) -> u8 {
     (a as f32 * (1.0 - t) + b as f32 * t).round() as u8
@@ -35,6 +209,11 @@ fn lerp_u8(a: u8, b: u8, t: f32) -> u8 {
 fn main() {
     let _ = dotenv::dotenv();
     env_logger::init();
+    let args = Args::parse();
+    if let Some(command) = &args.command {
+        run_command(command);
+        return;
+    }
     let event_loop = EventLoop::new().unwrap();
     let mut input = WinitInputHelper::new();
 
@@ -43,16 +222,13 @@ fn main() {
         .primary_monitor()
         .expect("No primary monitor found");
     let (window_width, window_height) = calculate_window_dimensions(&monitor);
-    let aspect_ratio = window_width as f32 / window_height as f32;
-    let model_height = window_height as usize;
-    let model_width = (model_height as f32 * aspect_ratio) as usize;
 
     let window = {
         let size = LogicalSize::new(window_width as f64, window_height as f64);
         match WindowBuilder::new()
             .with_title("Gray Scott Reaction Diffusion")
             .with_inner_size(size)
-            .with_min_inner_size(size)
+            .with_min_inner_size(LogicalSize::new(320.0, 240.0))
             .build(&event_loop)
         {
             Ok(w) => w,
@@ -63,15 +239,19 @@ fn main() {
         }
     };
 
+    // The simulation grid is sized relative to the window rather than matching it
+    let (model_width, model_height) = grid_size_for_window(&window, args.scale);
+
     // Create the renderer
     let mut renderer = futures::executor::block_on(Renderer::new(
         &window,
         model_width as u32,
         model_height as u32,
     ));
+    renderer.set_upscale_filter(args.upscale_filter.into());
 
     // Create the world asynchronously
-    let mut world = futures::executor::block_on(World::new(model_width, model_height));
+    let mut world = futures::executor::block_on(World::new(model_width, model_height, &args));
 
     // Initialize the selected LUT
     let available_luts = world.lut_manager.get_available_luts();
@@ -142,6 +322,100 @@ fn main() {
             if input.key_pressed(KeyCode::KeyN) {
                 world.fill_with_noise();
             }
+            if input.key_pressed(KeyCode::KeyS) {
+                let shift_held =
+                    input.key_held(KeyCode::ShiftLeft) || input.key_held(KeyCode::ShiftRight);
+                world.cycle_seeder(shift_held);
+            }
+            if input.key_pressed(KeyCode::KeyR) {
+                world.reseed();
+            }
+            if input.key_pressed(KeyCode::F5) {
+                world.quicksave();
+            }
+            if input.key_pressed(KeyCode::F6) {
+                world.export_numpy();
+            }
+            if input.key_pressed(KeyCode::F9) {
+                world.quickload(&mut renderer);
+            }
+            if input.key_pressed(KeyCode::KeyK) {
+                let shift_held =
+                    input.key_held(KeyCode::ShiftLeft) || input.key_held(KeyCode::ShiftRight);
+                if shift_held {
+                    world.export_probes();
+                } else {
+                    world.add_probe_at_mouse(&window);
+                }
+            }
+            if input.key_pressed(KeyCode::BracketLeft) {
+                world.brush_radius = (world.brush_radius / 1.25).max(1.0);
+            }
+            if input.key_pressed(KeyCode::BracketRight) {
+                world.brush_radius = (world.brush_radius * 1.25).min(500.0);
+            }
+            if input.key_pressed(KeyCode::KeyW) {
+                world.follow_window = !world.follow_window;
+                info!(
+                    "Simulation grid {} the window size",
+                    if world.follow_window {
+                        "follows"
+                    } else {
+                        "no longer follows"
+                    }
+                );
+                if world.follow_window {
+                    world.fit_grid_to_window(&mut renderer, &window);
+                }
+            }
+            if input.key_pressed(KeyCode::PageUp) {
+                world.step_grid_scale(true, &mut renderer, &window);
+            }
+            if input.key_pressed(KeyCode::PageDown) {
+                world.step_grid_scale(false, &mut renderer, &window);
+            }
+            if input.key_pressed(KeyCode::KeyL) {
+                let filter = match renderer.upscale_filter() {
+                    UpscaleFilter::Nearest => UpscaleFilter::Bilinear,
+                    UpscaleFilter::Bilinear => UpscaleFilter::Nearest,
+                };
+                info!("Upscaling with the {:?} filter", filter);
+                renderer.set_upscale_filter(filter);
+            }
+            if input.key_pressed(KeyCode::Space) {
+                world.toggle_pause();
+            }
+            if input.key_pressed(KeyCode::Period) {
+                let shift_held =
+                    input.key_held(KeyCode::ShiftLeft) || input.key_held(KeyCode::ShiftRight);
+                world.step(if shift_held { 10 } else { 1 });
+            }
+            if input.key_pressed(KeyCode::Equal) {
+                world.step_speed(true);
+            }
+            if input.key_pressed(KeyCode::Minus) {
+                world.step_speed(false);
+            }
+            if input.key_pressed(KeyCode::KeyH) {
+                world.toggle_statistics(&mut renderer);
+            }
+            if input.key_pressed(KeyCode::KeyO) {
+                world.show_spectrum = !world.show_spectrum;
+                if !world.show_spectrum {
+                    renderer.clear_overlay("spectrum");
+                    world.spectral_analysis = None;
+                }
+                world.last_spectrum_update = None;
+            }
+            if input.key_pressed(KeyCode::KeyC) {
+                world.toggle_segmentation();
+            }
+            if input.key_pressed(KeyCode::KeyE) {
+                world.toggle_auto_reseed();
+            }
+            if input.key_pressed(KeyCode::Backspace) && !world.reaction_diffusion_system.rewind() {
+                info!("No earlier checkpoint to rewind to");
+            }
             if input.key_pressed(KeyCode::KeyG) {
                 let shift_held =
                     input.key_held(KeyCode::ShiftLeft) || input.key_held(KeyCode::ShiftRight);
@@ -149,9 +423,8 @@ fn main() {
                 world.is_psychedelic_paused = false;
                 world.is_lut_transitioning = false;
 
-                let available_luts = world.lut_manager.get_available_luts();
-                if !available_luts.is_empty() {
-                    let len = available_luts.len();
+                let len = world.lut_manager.get_available_luts().len();
+                if len > 0 {
                     let new_lut_index = if shift_held {
                         if world.current_lut_index == 0 {
                             len - 1
@@ -161,28 +434,24 @@ fn main() {
                     } else {
                         (world.current_lut_index + 1) % len
                     };
-
-                    if let Ok(new_lut_data) =
-                        world.lut_manager.load_lut(&available_luts[new_lut_index])
-                    {
-                        world.current_lut_data = new_lut_data.clone();
-                        world.current_lut_index = new_lut_index;
-                        world.pending_target_lut_index = new_lut_index;
-                        world.source_lut_for_transition = world.current_lut_data.clone();
-                        world.target_lut_for_transition = world.current_lut_data.clone();
-                        renderer.update_lut(&world.current_lut_data);
-                    } else {
-                        error!(
-                            "Failed to instantly load LUT: {}",
-                            available_luts[new_lut_index]
-                        );
-                    }
+                    world.set_lut(new_lut_index, &mut renderer);
                 }
             }
             if input.key_pressed(KeyCode::KeyP) {
                 let shift_held =
                     input.key_held(KeyCode::ShiftLeft) || input.key_held(KeyCode::ShiftRight);
-                world.cycle_preset(shift_held);
+                world.cycle_preset(shift_held, &mut renderer);
+            }
+            if input.key_pressed(KeyCode::KeyA) {
+                world.toggle_attract_mode();
+            }
+            if input.key_pressed(KeyCode::KeyM) {
+                world.save_bookmark();
+            }
+            if input.key_pressed(KeyCode::KeyJ) {
+                let shift_held =
+                    input.key_held(KeyCode::ShiftLeft) || input.key_held(KeyCode::ShiftRight);
+                world.jump_to_bookmark(shift_held, &mut renderer);
             }
             if input.key_pressed(KeyCode::KeyU) {
                 let shift_held =
@@ -195,6 +464,16 @@ fn main() {
             if input.key_pressed(KeyCode::KeyF) {
                 world.reverse_current_lut(&mut renderer);
             }
+            if input.key_pressed(KeyCode::KeyI) {
+                let shift_held =
+                    input.key_held(KeyCode::ShiftLeft) || input.key_held(KeyCode::ShiftRight);
+                world.cycle_nutrient_mapping(shift_held);
+            }
+            if input.key_pressed(KeyCode::KeyV) {
+                let shift_held =
+                    input.key_held(KeyCode::ShiftLeft) || input.key_held(KeyCode::ShiftRight);
+                world.cycle_nutrient_view(shift_held, &mut renderer);
+            }
             if input.key_pressed(KeyCode::KeyY) {
                 world.is_current_nutrient_pattern_reversed =
                     !world.is_current_nutrient_pattern_reversed;
@@ -243,12 +522,19 @@ fn main() {
             // Resize the window
             if let Some(size) = input.window_resized() {
                 renderer.resize(size);
+                if world.follow_window {
+                    world.fit_grid_to_window(&mut renderer, &window);
+                }
             }
 
             // Update internal state and request a redraw
             window.request_redraw();
             world.update(&window);
 
+            // Preset morphing and attract mode
+            world.advance_preset_transition();
+            world.advance_attract_mode(&mut renderer);
+
             // LUT transition and animation logic (runs every frame if a transition is active)
             if world.is_lut_transitioning {
                 let elapsed = world.lut_transition_start_time.elapsed();
@@ -315,14 +601,16 @@ fn main() {
 
                 let fps_sum: i32 = fps_values.iter().sum();
                 let avg_fps = fps_sum as f32 / fps_values.len() as f32;
-                let (feed_rate, kill_rate) = world.get_current_preset_rates();
+                // The model's rates rather than the preset's, so morphing shows
+                let system = &world.reaction_diffusion_system;
                 window.set_title(&format!(
-                    "Gray Scott Reaction Diffusion - {} (f={:.4}, k={:.4}) - {} - {} - FPS: {:.1}",
-                    world.get_current_preset_name(),
-                    feed_rate,
-                    kill_rate,
+                    "Gray Scott Reaction Diffusion - {} (f={:.4}, k={:.4}) - {} - {} - {} - FPS: {:.1}",
+                    world.current_preset().name,
+                    system.feed_rate(),
+                    system.kill_rate(),
                     world.get_current_nutrient_pattern_name(),
                     world.get_current_lut_name(&renderer),
+                    world.playback_status(),
                     avg_fps * 30.0
                 ));
             }
@@ -338,15 +626,39 @@ pub struct World {
     /// x ranges from 0 to window_width, y ranges from 0 to window_height.
     /// These are raw window coordinates before any scaling to simulation space.
     pub mouse_xy: (f32, f32),
+    pub presets: PresetRegistry,
     pub current_preset_index: usize,
+    /// The preset whose rates the arrow keys adjust.
+    pub custom_preset_index: usize,
+    /// Indices of the presets saved from the app, in the order they were saved.
+    pub bookmarks: Vec<usize>,
+    pub is_preset_transitioning: bool,
+    pub preset_transition_start_time: Instant,
+    pub preset_transition_duration: Duration,
+    /// How long switching presets by hand takes to morph the rates.
+    pub manual_preset_transition_duration: Duration,
+    /// Feed, kill and diffusion rates the transition started from.
+    pub source_params_for_transition: (f32, f32, f32, f32),
+    /// Whether the app wanders between presets by itself, reseeding whenever
+    /// the pattern dies out.
+    pub is_attract_mode_active: bool,
+    pub attract_dwell_duration: Duration,
+    /// When attract mode moves on to another preset.
+    pub next_attract_change_time: Instant,
     pub current_nutrient_pattern: NutrientPattern,
     pub is_current_nutrient_pattern_reversed: bool,
+    /// The image from `--nutrient-image` or a loaded state, which U cycles to
+    /// after the last pattern.
+    pub nutrient_image: Option<Arc<NutrientMap>>,
+    /// Whether the nutrient image is in use rather than
+    /// `current_nutrient_pattern`.
+    pub is_nutrient_image_selected: bool,
+    /// Speed of the animated nutrient patterns.
+    pub nutrient_speed: f32,
     pub show_help: bool,
     pub font: Font,
     pub lut_manager: LutManager,
     pub current_lut_index: usize,
-    pub custom_feed_rate: f32,
-    pub custom_kill_rate: f32,
     pub is_psychedelic_mode_active: bool,
     pub is_lut_transitioning: bool,
     pub lut_transition_start_time: Instant,
@@ -358,24 +670,79 @@ pub struct World {
     pub psychedelic_pause_duration: Duration,
     pub psychedelic_pause_end_time: Instant,
     pub is_psychedelic_paused: bool,
+    pub seeders: Vec<Box<dyn Seeder>>,
+    pub current_seeder_index: usize,
+    /// Brush radius in simulation cells.
+    pub brush_radius: f32,
+    /// The readback whose result will be drawn next, if one is in flight.
+    pub pending_readback: Option<ReadbackHandle>,
+    /// Whether the simulation grid is resized to match the window.
+    pub follow_window: bool,
+    /// Simulation cells per logical pixel of the window.
+    pub grid_scale: f32,
+    pub is_paused: bool,
+    /// Simulation steps run per frame. Fractional speeds run a step every few frames.
+    pub speed: f32,
+    /// Fractional steps carried over between frames at speeds below one.
+    step_accumulator: f32,
+    /// Steps requested with [`World::step`] that have not run yet.
+    queued_steps: u64,
+    pub show_statistics: bool,
+    /// Mean V of each statistics sample shown in the graph, oldest first.
+    mean_v_history: VecDeque<f32>,
+    /// The step of the statistics last drawn into the graph.
+    last_graphed_step: Option<u64>,
+    pub show_spectrum: bool,
+    pub spectral_analysis: Option<SpectralAnalysis>,
+    last_spectrum_update: Option<Instant>,
+    pub nutrient_view: NutrientView,
+    /// The nutrient field last read back, kept so that changing the view
+    /// doesn't need another readback.
+    nutrient_field: Option<NutrientField>,
+    pending_nutrient_readback: Option<ReadbackHandle<NutrientField>>,
+    last_nutrient_field_request: Option<Instant>,
+    pub show_segmentation: bool,
+    pub segmentation: Option<Segmentation>,
+    mitosis_tracker: MitosisTracker,
+    last_segmentation_update: Option<Instant>,
+    pub auto_reseed: bool,
+    convergence_monitor: ConvergenceMonitor,
+    /// The step of the last reseed, so samples taken before it are ignored.
+    reseeded_at_step: u64,
 }
 
 impl World {
-    async fn new(model_width: usize, model_height: usize) -> Self {
+    async fn new(model_width: usize, model_height: usize, args: &Args) -> Self {
+        // Add the user's presets after the built-in ones
+        let mut presets = PresetRegistry::builtin();
+        let user_preset_dir = model_presets::user_preset_dir().filter(|dir| dir.is_dir());
+        for path in user_preset_dir.iter().chain(&args.presets) {
+            let errors = presets.load(path);
+            if errors.is_empty() {
+                info!("Loaded presets from {}", path.display());
+            }
+            for e in errors {
+                error!("Failed to load presets: {}", e);
+            }
+        }
+
         // Set initial preset to Undulating
-        let current_preset_index = 6;
-        let (feed_rate, kill_rate) = match current_preset_index {
-            0 => model_presets::BRAIN_CORAL,
-            1 => model_presets::FINGERPRINT,
-            2 => model_presets::MITOSIS,
-            3 => model_presets::RIPPLES,
-            4 => model_presets::SOLITON_COLLAPSE,
-            5 => model_presets::U_SKATE_WORLD,
-            6 => model_presets::UNDULATING,
-            7 => model_presets::WORMS,
-            8 => model_presets::CUSTOM,
-            _ => unreachable!(),
-        };
+        let current_preset_index = presets
+            .position_by_name(&model_presets::UNDULATING.name)
+            .expect("Undulating is a built-in preset");
+        let custom_preset_index = presets
+            .position_by_name(&model_presets::CUSTOM.name)
+            .expect("Custom is a built-in preset");
+        let initial_preset = presets[current_preset_index].clone();
+
+        // Bookmark the presets saved from the app in earlier sessions
+        let bookmarks = model_presets::saved_presets_path()
+            .filter(|path| path.exists())
+            .and_then(|path| model_presets::load_presets(path).ok())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|preset| presets.position_by_name(&preset.name))
+            .collect();
 
         // Load the font
         let font = Font::from_bytes(
@@ -384,14 +751,65 @@ impl World {
         )
         .expect("Font is valid");
 
+        let nutrient_image =
+            args.nutrient_image.as_ref().and_then(|path| {
+                match NutrientMap::open(path, args.nutrient_channel.into()) {
+                    Ok(map) => Some(Arc::new(map)),
+                    Err(e) => {
+                        error!("Failed to load nutrient image {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            });
+
+        // Collect the available seeders, adding the ones that need input from the command line
+        let mut seeders = seeders::builtin();
+        if let Some(path) = &args.seed_image {
+            match seeders::ImageSeeder::open(path) {
+                Ok(seeder) => seeders.push(Box::new(seeder)),
+                Err(e) => error!("Failed to load seed image {}: {}", path.display(), e),
+            }
+        }
+        if let Some(text) = &args.seed_text {
+            seeders.push(Box::new(seeders::TextSeeder::new(
+                text.as_str(),
+                font.clone(),
+            )));
+        }
+
+        // An explicit --seeder wins, otherwise prefer whatever input was given,
+        // then the initial preset's recommendation
+        let requested_seeder = args.seeder.clone().or_else(|| {
+            let name = if args.seed_image.is_some() {
+                "Image"
+            } else if args.seed_text.is_some() {
+                "Text"
+            } else {
+                initial_preset.seeder.as_deref()?
+            };
+            SeederSpec::parse(name).ok()
+        });
+        let current_seeder_index = match requested_seeder {
+            Some(spec) => spec.apply(&mut seeders).unwrap_or_else(|e| {
+                error!("Invalid or unavailable seeder: {}", e);
+                seeders::position_by_name(&seeders, &spec.name).unwrap_or(0)
+            }),
+            None => 0,
+        };
+
         // Initialize LUT manager
         let lut_manager = LutManager::new();
 
-        // Find the index of MATPLOTLIB_gist_ncar_r in available LUTs
+        // Find the index of the initial preset's LUT, or MATPLOTLIB_gist_ncar_r,
+        // in available LUTs
         let available_luts = lut_manager.get_available_luts();
+        let initial_lut = initial_preset
+            .lut
+            .as_deref()
+            .unwrap_or("MATPLOTLIB_gist_ncar_r");
         let current_lut_index = available_luts
             .iter()
-            .position(|name| name == "MATPLOTLIB_gist_ncar_r")
+            .position(|name| name == initial_lut)
             .unwrap_or(0);
 
         // Create the world instance
@@ -401,22 +819,43 @@ impl World {
             reaction_diffusion_system: ReactionDiffusionSystem::new(
                 model_width,
                 model_height,
-                feed_rate,
-                kill_rate,
-                1.0,
-                0.5,
+                initial_preset.feed_rate,
+                initial_preset.kill_rate,
+                initial_preset.delta_u,
+                initial_preset.delta_v,
             )
             .await,
             mouse_xy: (0.0, 0.0),
+            presets,
             current_preset_index,
-            current_nutrient_pattern: NutrientPattern::RadialGradient,
+            custom_preset_index,
+            bookmarks,
+            is_preset_transitioning: false,
+            preset_transition_start_time: Instant::now(),
+            preset_transition_duration: Duration::ZERO,
+            manual_preset_transition_duration: Duration::from_secs_f32(
+                args.preset_transition_secs.max(0.0),
+            ),
+            source_params_for_transition: (
+                initial_preset.feed_rate,
+                initial_preset.kill_rate,
+                initial_preset.delta_u,
+                initial_preset.delta_v,
+            ),
+            is_attract_mode_active: false,
+            attract_dwell_duration: Duration::from_secs_f32(args.attract_dwell_secs.max(0.0)),
+            next_attract_change_time: Instant::now(),
+            current_nutrient_pattern: initial_preset
+                .nutrient_pattern
+                .unwrap_or(NutrientPattern::RadialGradient),
             is_current_nutrient_pattern_reversed: false,
+            is_nutrient_image_selected: nutrient_image.is_some(),
+            nutrient_image,
+            nutrient_speed: args.nutrient_speed,
             show_help: false,
             font,
             lut_manager,
             current_lut_index,
-            custom_feed_rate: model_presets::CUSTOM.0,
-            custom_kill_rate: model_presets::CUSTOM.1,
             is_psychedelic_mode_active: false,
             is_lut_transitioning: false,
             lut_transition_start_time: Instant::now(),
@@ -443,82 +882,307 @@ impl World {
             psychedelic_pause_duration: Duration::from_secs(5),
             psychedelic_pause_end_time: Instant::now(),
             is_psychedelic_paused: false,
+            seeders,
+            current_seeder_index,
+            brush_radius: 5.0,
+            pending_readback: None,
+            follow_window: !args.fixed_grid,
+            grid_scale: args.scale,
+            is_paused: false,
+            speed: 1.0,
+            step_accumulator: 0.0,
+            queued_steps: 0,
+            show_statistics: false,
+            mean_v_history: VecDeque::new(),
+            last_graphed_step: None,
+            show_spectrum: false,
+            spectral_analysis: None,
+            last_spectrum_update: None,
+            nutrient_view: NutrientView::Off,
+            nutrient_field: None,
+            pending_nutrient_readback: None,
+            last_nutrient_field_request: None,
+            show_segmentation: false,
+            segmentation: None,
+            mitosis_tracker: MitosisTracker::new(),
+            last_segmentation_update: None,
+            auto_reseed: false,
+            convergence_monitor: ConvergenceMonitor::new(),
+            reseeded_at_step: 0,
         };
 
-        // Fill with initial random noise
-        world.fill_with_noise();
+        world
+            .reaction_diffusion_system
+            .set_history_budget(args.history_budget_mb.saturating_mul(1024 * 1024));
+        world
+            .reaction_diffusion_system
+            .set_checkpoint_interval(args.checkpoint_interval);
+
+        // Seed the initial state
+        world.reseed();
 
         // Set the initial nutrient pattern
-        world.reaction_diffusion_system.set_nutrient_pattern(
-            world.current_nutrient_pattern.as_u32(),
-            world.is_current_nutrient_pattern_reversed,
-        );
+        world.apply_current_nutrient();
+        world
+            .reaction_diffusion_system
+            .set_nutrient_mapping(args.nutrient_mapping);
 
         world
     }
 
     fn clear_screen(&mut self) {
-        let values: Vec<(f32, f32)> = vec![
-            (1.0, 0.0);
-            self.reaction_diffusion_system.width
-                * self.reaction_diffusion_system.height
-        ];
-        self.reaction_diffusion_system.set_all(&values);
+        seed_system(&mut self.reaction_diffusion_system, &seeders::Clear);
     }
 
     fn fill_with_noise(&mut self) {
-        let values: Vec<(f32, f32)> = (0..self.reaction_diffusion_system.height)
-            .flat_map(|_y| {
-                (0..self.reaction_diffusion_system.width).map(move |_| {
-                    if rand::random::<f32>() < 0.05 {
-                        // 5% chance to add noise
-                        let u = 0.5 + rand::random::<f32>() * 0.5; // Random U between 0.5 and 1.0
-                        let v = 0.2 + rand::random::<f32>() * 0.6; // Random V between 0.2 and 0.8
-                        (u, v)
-                    } else {
-                        (1.0, 0.0) // Default empty state
-                    }
-                })
-            })
-            .collect();
+        seed_system(
+            &mut self.reaction_diffusion_system,
+            &seeders::RandomNoise::default(),
+        );
+    }
 
-        self.reaction_diffusion_system.set_all(&values);
+    /// Re-seeds the grid with the currently selected seeder.
+    fn reseed(&mut self) {
+        seed_system(
+            &mut self.reaction_diffusion_system,
+            self.seeders[self.current_seeder_index].as_ref(),
+        );
+        self.convergence_monitor.reset();
+        self.reseeded_at_step = self.reaction_diffusion_system.step_count();
     }
 
-    fn cycle_preset(&mut self, reverse: bool) {
-        if reverse {
-            self.current_preset_index = if self.current_preset_index == 0 {
-                8
-            } else {
-                self.current_preset_index - 1
-            };
+    fn cycle_seeder(&mut self, reverse: bool) {
+        let len = self.seeders.len();
+        self.current_seeder_index = if reverse {
+            (self.current_seeder_index + len - 1) % len
+        } else {
+            (self.current_seeder_index + 1) % len
+        };
+        self.reseed();
+    }
+
+    fn current_preset(&self) -> &Preset {
+        &self.presets[self.current_preset_index]
+    }
+
+    fn cycle_preset(&mut self, reverse: bool, renderer: &mut Renderer) {
+        let len = self.presets.len();
+        self.current_preset_index = if reverse {
+            (self.current_preset_index + len - 1) % len
+        } else {
+            (self.current_preset_index + 1) % len
+        };
+        self.apply_current_preset(self.manual_preset_transition_duration, renderer);
+    }
+
+    /// Morphs the model parameters to the current preset's over
+    /// `transition_duration`, switching to the seeder, nutrient pattern and LUT
+    /// it recommends. The grid is left alone, so the pattern carries on
+    /// evolving under the new parameters.
+    fn apply_current_preset(&mut self, transition_duration: Duration, renderer: &mut Renderer) {
+        let preset = self.current_preset().clone();
+        let system = &self.reaction_diffusion_system;
+        // A transition cut short starts the next one from wherever it got to
+        self.source_params_for_transition = (
+            system.feed_rate(),
+            system.kill_rate(),
+            system.delta_u(),
+            system.delta_v(),
+        );
+        self.is_preset_transitioning = true;
+        self.preset_transition_start_time = Instant::now();
+        self.preset_transition_duration = transition_duration;
+        self.advance_preset_transition();
+        if let Some(name) = &preset.seeder {
+            match seeders::position_by_name(&self.seeders, name) {
+                Some(index) => self.current_seeder_index = index,
+                None => error!("Preset {} asks for unknown seeder {}", preset.name, name),
+            }
+        }
+        if let Some(pattern) = preset.nutrient_pattern {
+            self.current_nutrient_pattern = pattern;
+            self.is_current_nutrient_pattern_reversed = preset.is_nutrient_pattern_reversed;
+            self.is_nutrient_image_selected = false;
+            self.apply_current_nutrient();
+        }
+        if let Some(name) = &preset.lut {
+            let available_luts = self.lut_manager.get_available_luts();
+            match available_luts.iter().position(|lut| lut == name) {
+                Some(index) => self.set_lut(index, renderer),
+                None => error!("Preset {} asks for unknown LUT {}", preset.name, name),
+            }
+        }
+    }
+
+    /// Saves the current parameters as a preset named `Bookmark N`, with the
+    /// first number no preset has taken yet.
+    fn save_bookmark(&mut self) {
+        let name = (self.bookmarks.len() + 1..)
+            .map(|n| format!("Bookmark {}", n))
+            .find(|name| self.presets.position_by_name(name).is_none())
+            .expect("Some bookmark number is free");
+        self.save_preset(name);
+    }
+
+    /// Saves the current parameters, nutrient pattern, seeder and LUT as a
+    /// preset named `name` in the saved presets file, replacing any saved
+    /// preset with that name, and bookmarks it.
+    ///
+    /// The nutrient image can't be saved in a preset, so while it's selected
+    /// the preset keeps whatever nutrient pattern is current when it's applied.
+    pub fn save_preset(&mut self, name: impl Into<Cow<'static, str>>) {
+        let Some(path) = model_presets::saved_presets_path() else {
+            error!("No config directory to save presets in");
+            return;
+        };
+
+        let name = name.into();
+        if self.is_nutrient_image_selected {
+            warn!(
+                "Preset {} won't include the nutrient image, only the parameters",
+                name
+            );
+        }
+        // Only built-in seeders and LUTs can be loaded back
+        let seeder = self.seeders[self.current_seeder_index].name();
+        let seeder = seeders::position_by_name(&seeders::builtin(), seeder)
+            .map(|_| seeder.to_string().into());
+        let lut = Some(self.current_lut_data.name.clone())
+            .filter(|name| self.lut_manager.get_available_luts().contains(name))
+            .map(Into::into);
+        let nutrient_pattern =
+            Some(self.current_nutrient_pattern).filter(|_| !self.is_nutrient_image_selected);
+        let system = &self.reaction_diffusion_system;
+        let preset = Preset {
+            name,
+            description: format!("Saved from {}.", self.current_preset().name).into(),
+            feed_rate: system.feed_rate(),
+            kill_rate: system.kill_rate(),
+            delta_u: system.delta_u(),
+            delta_v: system.delta_v(),
+            seeder,
+            nutrient_pattern,
+            is_nutrient_pattern_reversed: nutrient_pattern.is_some()
+                && self.is_current_nutrient_pattern_reversed,
+            lut,
+        };
+
+        if let Err(e) = model_presets::save_preset(&path, &preset) {
+            error!("Failed to save preset: {}", e);
+            return;
+        }
+        info!("Saved preset {} to {}", preset.name, path.display());
+        let index = self.presets.add(preset);
+        if !self.bookmarks.contains(&index) {
+            self.bookmarks.push(index);
+        }
+    }
+
+    /// Switches to the next bookmarked preset, or the first one if the current
+    /// preset isn't bookmarked.
+    fn jump_to_bookmark(&mut self, reverse: bool, renderer: &mut Renderer) {
+        let len = self.bookmarks.len();
+        if len == 0 {
+            info!("No bookmarked presets yet");
+            return;
+        }
+        let position = self
+            .bookmarks
+            .iter()
+            .position(|&index| index == self.current_preset_index);
+        let next = match (position, reverse) {
+            (Some(position), false) => (position + 1) % len,
+            (Some(position), true) => (position + len - 1) % len,
+            (None, false) => 0,
+            (None, true) => len - 1,
+        };
+        self.current_preset_index = self.bookmarks[next];
+        self.apply_current_preset(self.manual_preset_transition_duration, renderer);
+    }
+
+    /// Moves the model parameters along the preset transition, if one is in
+    /// progress.
+    fn advance_preset_transition(&mut self) {
+        if !self.is_preset_transitioning {
+            return;
+        }
+        let elapsed = self.preset_transition_start_time.elapsed();
+        let progress = if self.preset_transition_duration.is_zero() {
+            1.0
         } else {
-            self.current_preset_index = (self.current_preset_index + 1) % 9;
-        }
-
-        let (feed_rate, kill_rate) = match self.current_preset_index {
-            0 => model_presets::BRAIN_CORAL,
-            1 => model_presets::FINGERPRINT,
-            2 => model_presets::MITOSIS,
-            3 => model_presets::RIPPLES,
-            4 => model_presets::SOLITON_COLLAPSE,
-            5 => model_presets::U_SKATE_WORLD,
-            6 => model_presets::UNDULATING,
-            7 => model_presets::WORMS,
-            8 => (self.custom_feed_rate, self.custom_kill_rate),
-            _ => unreachable!(),
+            (elapsed.as_secs_f32() / self.preset_transition_duration.as_secs_f32()).min(1.0)
         };
+
+        let lerp = |a: f32, b: f32| a + (b - a) * progress;
+        let (feed_rate, kill_rate, delta_u, delta_v) = self.source_params_for_transition;
+        let target = self.current_preset();
+        let params = (
+            lerp(feed_rate, target.feed_rate),
+            lerp(kill_rate, target.kill_rate),
+            lerp(delta_u, target.delta_u),
+            lerp(delta_v, target.delta_v),
+        );
         self.reaction_diffusion_system
-            .update_rates(feed_rate, kill_rate);
+            .update_params(params.0, params.1, params.2, params.3);
+
+        if progress >= 1.0 {
+            self.is_preset_transitioning = false;
+        }
+    }
+
+    /// Starts or stops wandering between presets.
+    pub fn toggle_attract_mode(&mut self) {
+        self.is_attract_mode_active = !self.is_attract_mode_active;
+        info!(
+            "Attract mode {}",
+            if self.is_attract_mode_active {
+                "on"
+            } else {
+                "off"
+            }
+        );
+        self.next_attract_change_time = Instant::now() + self.attract_dwell_duration;
+        self.convergence_monitor.reset();
+        self.update_convergence_interval();
     }
 
+    /// Morphs slowly to a random preset other than the current one and Custom,
+    /// once attract mode has dwelt on the current one long enough.
+    fn advance_attract_mode(&mut self, renderer: &mut Renderer) {
+        if !self.is_attract_mode_active
+            || self.is_preset_transitioning
+            || Instant::now() < self.next_attract_change_time
+        {
+            return;
+        }
+        let candidates: Vec<usize> = (0..self.presets.len())
+            .filter(|&index| {
+                index != self.current_preset_index && index != self.custom_preset_index
+            })
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+
+        self.current_preset_index = candidates[rand::thread_rng().gen_range(0..candidates.len())];
+        info!("Attract mode moving to {}", self.current_preset().name);
+        self.apply_current_preset(ATTRACT_TRANSITION_DURATION, renderer);
+        self.next_attract_change_time =
+            Instant::now() + ATTRACT_TRANSITION_DURATION + self.attract_dwell_duration;
+    }
+
+    /// Cycles through the patterns, then the nutrient image if there is one.
     fn cycle_nutrient_pattern(&mut self, reverse: bool) {
         let patterns = NutrientPattern::all();
-        let current_idx = patterns
-            .iter()
-            .position(|&p| p == self.current_nutrient_pattern)
-            .unwrap();
-        let len = patterns.len();
+        let current_idx = if self.is_nutrient_image_selected {
+            patterns.len()
+        } else {
+            patterns
+                .iter()
+                .position(|&p| p == self.current_nutrient_pattern)
+                .unwrap()
+        };
+        let len = patterns.len() + self.nutrient_image.is_some() as usize;
 
         let new_idx = if reverse {
             if current_idx == 0 {
@@ -530,11 +1194,130 @@ impl World {
             (current_idx + 1) % len
         };
 
-        self.current_nutrient_pattern = patterns[new_idx];
-        self.reaction_diffusion_system.set_nutrient_pattern(
-            self.current_nutrient_pattern.as_u32(),
-            self.is_current_nutrient_pattern_reversed,
+        self.is_nutrient_image_selected = new_idx == patterns.len();
+        if let Some(&pattern) = patterns.get(new_idx) {
+            self.current_nutrient_pattern = pattern;
+        }
+        self.apply_current_nutrient();
+    }
+
+    /// Cycles through the named nutrient mappings. A custom mapping from the
+    /// command line moves on to the first or last of them.
+    fn cycle_nutrient_mapping(&mut self, reverse: bool) {
+        let mappings = NutrientMapping::named();
+        let current = self.reaction_diffusion_system.nutrient_mapping();
+        let len = mappings.len();
+        let new_idx = match mappings.iter().position(|(_, m)| *m == current) {
+            Some(idx) if reverse => (idx + len - 1) % len,
+            Some(idx) => (idx + 1) % len,
+            None if reverse => len - 1,
+            None => 0,
+        };
+        let (name, mapping) = mappings[new_idx];
+        self.reaction_diffusion_system.set_nutrient_mapping(mapping);
+        info!("Nutrient pattern now scales: {}", name);
+    }
+
+    /// Cycles through the ways of showing the nutrient field.
+    fn cycle_nutrient_view(&mut self, reverse: bool, renderer: &mut Renderer) {
+        self.nutrient_view = self.nutrient_view.cycled(reverse);
+        renderer.set_simulation_rect(self.nutrient_view.simulation_rect());
+        if self.nutrient_view == NutrientView::Off {
+            renderer.clear_overlay("nutrient");
+        } else {
+            self.draw_nutrient_overlay(renderer);
+        }
+        info!("Nutrient view: {}", self.nutrient_view.name());
+    }
+
+    /// Hands the selected nutrient pattern or image to the simulation.
+    fn apply_current_nutrient(&mut self) {
+        let graph = match &self.nutrient_image {
+            Some(map) if self.is_nutrient_image_selected => {
+                let params = NutrientParams {
+                    is_reversed: self.is_current_nutrient_pattern_reversed,
+                    ..NutrientParams::default()
+                };
+                NutrientGraph::image_with_params(map.clone(), params)
+            }
+            _ => {
+                let pattern = self.current_nutrient_pattern;
+                let params = NutrientParams {
+                    is_reversed: self.is_current_nutrient_pattern_reversed,
+                    speed: self.nutrient_speed,
+                    ..pattern.default_params()
+                };
+                NutrientGraph::with_params(pattern, params)
+            }
+        };
+        if let Err(e) = self.reaction_diffusion_system.set_nutrient_graph(graph) {
+            error!("Failed to set the nutrient pattern: {}", e);
+        }
+    }
+
+    fn quicksave(&mut self) {
+        match self.reaction_diffusion_system.save_state(QUICKSAVE_PATH) {
+            Ok(()) => info!("Saved simulation state to {}", QUICKSAVE_PATH),
+            Err(e) => error!("Failed to save simulation state: {}", e),
+        }
+    }
+
+    fn quickload(&mut self, renderer: &mut Renderer) {
+        if let Err(e) = self.reaction_diffusion_system.load_state(QUICKSAVE_PATH) {
+            error!("Failed to load simulation state: {}", e);
+            return;
+        }
+        info!("Loaded simulation state from {}", QUICKSAVE_PATH);
+        // The saved grid may not match the window
+        let system = &self.reaction_diffusion_system;
+        renderer.set_simulation_size(system.width as u32, system.height as u32);
+        self.is_preset_transitioning = false;
+
+        // Bring the app's view of the parameters in line with the loaded state
+        let system = &self.reaction_diffusion_system;
+        // A combination of patterns keeps the last pattern chosen for U to cycle from
+        let nutrient = system.nutrient_graph();
+        if let Some(map) = nutrient.image_map() {
+            self.nutrient_image = Some(map.clone());
+        }
+        if let Some((pattern, params)) = nutrient.as_pattern() {
+            self.current_nutrient_pattern = pattern;
+            self.is_current_nutrient_pattern_reversed = params.is_reversed;
+            self.nutrient_speed = params.speed;
+            self.is_nutrient_image_selected = false;
+        } else if let NutrientGraph::Image(_, params) = nutrient {
+            self.is_current_nutrient_pattern_reversed = params.is_reversed;
+            self.is_nutrient_image_selected = true;
+        }
+        let (feed_rate, kill_rate) = (system.feed_rate(), system.kill_rate());
+        let (delta_u, delta_v) = (system.delta_u(), system.delta_v());
+        let preset = self.current_preset();
+        if (
+            preset.feed_rate,
+            preset.kill_rate,
+            preset.delta_u,
+            preset.delta_v,
+        ) != (feed_rate, kill_rate, delta_u, delta_v)
+        {
+            // The loaded parameters may not belong to any preset, so carry them in Custom
+            self.current_preset_index = self.custom_preset_index;
+            let custom = self.presets.get_mut(self.custom_preset_index).unwrap();
+            custom.feed_rate = feed_rate;
+            custom.kill_rate = kill_rate;
+            custom.delta_u = delta_u;
+            custom.delta_v = delta_v;
+        }
+    }
+
+    fn export_numpy(&mut self) {
+        let path = format!(
+            "state_{:08}.npz",
+            self.reaction_diffusion_system.step_count()
         );
+        match self.reaction_diffusion_system.export_numpy(&path) {
+            Ok(()) => info!("Exported simulation state to {}", path),
+            Err(e) => error!("Failed to export simulation state: {}", e),
+        }
     }
 
     fn get_current_lut_name(&self, renderer: &Renderer) -> String {
@@ -550,131 +1333,641 @@ impl World {
         }
     }
 
-    fn get_current_preset_name(&self) -> &'static str {
-        match self.current_preset_index {
-            0 => "Brain Coral",
-            1 => "Fingerprint",
-            2 => "Mitosis",
-            3 => "Ripples",
-            4 => "Soliton Collapse",
-            5 => "U-Skate World",
-            6 => "Undulating",
-            7 => "Worms",
-            8 => "Custom",
-            _ => unreachable!(),
+    /// The current preset's name, noting whether the rates are still morphing
+    /// towards it and whether attract mode is on. The Custom rates are
+    /// annotated with what linear stability analysis predicts for them.
+    fn preset_summary(&self) -> String {
+        let preset = self.current_preset();
+        let mut summary = preset.name.to_string();
+        if self.current_preset_index == self.custom_preset_index {
+            let analysis = StabilityAnalysis::new(
+                preset.feed_rate,
+                preset.kill_rate,
+                preset.delta_u,
+                preset.delta_v,
+            );
+            let prediction = match analysis.most_unstable_mode() {
+                Some(mode) => format!("Turing unstable, wavelength {:.1} cells", mode.wavelength),
+                None => "not Turing unstable".to_string(),
+            };
+            summary += &format!(
+                " (f {:.4}, k {:.4}: {})",
+                preset.feed_rate, preset.kill_rate, prediction
+            );
         }
-    }
-
-    fn get_current_preset_rates(&self) -> (f32, f32) {
-        match self.current_preset_index {
-            0 => model_presets::BRAIN_CORAL,
-            1 => model_presets::FINGERPRINT,
-            2 => model_presets::MITOSIS,
-            3 => model_presets::RIPPLES,
-            4 => model_presets::SOLITON_COLLAPSE,
-            5 => model_presets::U_SKATE_WORLD,
-            6 => model_presets::UNDULATING,
-            7 => model_presets::WORMS,
-            8 => (self.custom_feed_rate, self.custom_kill_rate),
-            _ => unreachable!(),
+        if self.is_preset_transitioning {
+            summary += ", morphing";
+        }
+        if self.is_attract_mode_active {
+            summary += ", attract mode";
         }
+        summary
     }
 
-    fn get_current_nutrient_pattern_name(&self) -> &'static str {
-        self.current_nutrient_pattern.name()
+    /// The nutrient graph in use, such as "Radial Gradient (Reversed)".
+    fn get_current_nutrient_pattern_name(&self) -> String {
+        self.reaction_diffusion_system.nutrient_graph().to_string()
     }
 
-    fn update(&mut self, window: &Window) {
+    /// The simulation cell under the mouse cursor.
+    fn mouse_to_simulation(&self, window: &Window) -> (f32, f32) {
         let physical_window_width = window.inner_size().width as f32;
         let physical_window_height = window.inner_size().height as f32;
+        let rect = self.nutrient_view.simulation_rect();
 
         // Convert physical mouse coordinates to simulation coordinates
-        let sim_x = ((self.mouse_xy.0 / physical_window_width)
+        let sim_x = (((self.mouse_xy.0 / physical_window_width - rect.x) / rect.width)
             * self.reaction_diffusion_system.width as f32)
-            .clamp(0.0, self.reaction_diffusion_system.width as f32 - 1.0)
-            as isize;
+            .clamp(0.0, self.reaction_diffusion_system.width as f32 - 1.0);
 
         // Invert Y coordinate (window origin is top-left, so we need to flip Y)
-        let sim_y = ((1.0 - (self.mouse_xy.1 / physical_window_height))
+        let sim_y = (((1.0 - (self.mouse_xy.1 / physical_window_height) - rect.y) / rect.height)
             * self.reaction_diffusion_system.height as f32)
-            .clamp(0.0, self.reaction_diffusion_system.height as f32 - 1.0)
-            as isize;
-
-        // Create a small area of effect
-        let radius = 5;
-        for dy in -radius..=radius {
-            for dx in -radius..=radius {
-                let nx = sim_x + dx;
-                let ny = sim_y + dy;
-                if nx >= 0
-                    && nx < self.reaction_diffusion_system.width as isize
-                    && ny >= 0
-                    && ny < self.reaction_diffusion_system.height as isize
-                {
-                    // Calculate normalized distance from center (0 to 1)
-                    let distance = ((dx * dx + dy * dy) as f32).sqrt() / radius as f32;
+            .clamp(0.0, self.reaction_diffusion_system.height as f32 - 1.0);
 
-                    // Smooth circular falloff
-                    let factor = if distance < 1.0 {
-                        (1.0 - distance * distance).powf(2.0)
-                    } else {
-                        0.0
-                    };
+        (sim_x, sim_y)
+    }
 
-                    // Apply nutrient pattern
-                    let nutrient_factor = 1.0; // The shader handles the nutrient pattern now
+    /// Whether the mouse cursor is over the part of the window showing the
+    /// simulation rather than the nutrient field beside it.
+    fn is_mouse_over_simulation(&self, window: &Window) -> bool {
+        let rect = self.nutrient_view.simulation_rect();
+        let x = self.mouse_xy.0 / window.inner_size().width as f32;
+        (rect.x..=rect.x + rect.width).contains(&x)
+    }
 
-                    if self.is_left_mouse_button_held_down {
-                        self.reaction_diffusion_system.set(
-                            nx,
-                            ny,
-                            (0.5, 0.99 * factor * nutrient_factor),
-                        );
-                    } else if self.is_right_mouse_button_held_down {
-                        // Right mouse button creates a void (clears the reaction)
-                        // Interpolate between current state and void state based on factor
-                        self.reaction_diffusion_system
-                            .set(nx, ny, (1.0 * factor, 0.0));
-                    }
+    fn add_probe_at_mouse(&mut self, window: &Window) {
+        let (x, y) = self.mouse_to_simulation(window);
+        let name = format!("probe{}", self.reaction_diffusion_system.probes().len() + 1);
+        info!("Added {} at ({}, {})", name, x as usize, y as usize);
+        self.reaction_diffusion_system
+            .add_probe(name, x as usize, y as usize);
+    }
+
+    fn export_probes(&mut self) {
+        let series = self.reaction_diffusion_system.take_probe_series();
+        if series.is_empty() {
+            info!("No probe samples to export");
+            return;
+        }
+        let path = format!(
+            "probes_{:08}.csv",
+            self.reaction_diffusion_system.step_count()
+        );
+        let result = std::fs::File::create(&path)
+            .and_then(|file| probes::write_csv(&series, std::io::BufWriter::new(file)));
+        match result {
+            Ok(()) => info!("Exported probe samples to {}", path),
+            Err(e) => error!("Failed to export probe samples: {}", e),
+        }
+    }
+
+    /// Resizes the simulation grid to the window size times the resolution
+    /// scale, carrying the current pattern over.
+    fn fit_grid_to_window(&mut self, renderer: &mut Renderer, window: &Window) {
+        let (width, height) = grid_size_for_window(window, self.grid_scale);
+        self.reaction_diffusion_system
+            .resize(width, height, ResampleMode::Bilinear);
+        renderer.set_simulation_size(width as u32, height as u32);
+    }
+
+    /// Moves to the next finer or coarser resolution scale.
+    fn step_grid_scale(&mut self, finer: bool, renderer: &mut Renderer, window: &Window) {
+        let next = if finer {
+            GRID_SCALE_STEPS
+                .iter()
+                .copied()
+                .find(|&scale| scale > self.grid_scale)
+        } else {
+            GRID_SCALE_STEPS
+                .iter()
+                .copied()
+                .rev()
+                .find(|&scale| scale < self.grid_scale)
+        };
+        if let Some(scale) = next {
+            self.grid_scale = scale;
+            self.fit_grid_to_window(renderer, window);
+            info!(
+                "Resolution scale {}x ({}x{} cells)",
+                scale, self.reaction_diffusion_system.width, self.reaction_diffusion_system.height
+            );
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.is_paused = true;
+        self.step_accumulator = 0.0;
+    }
+
+    pub fn resume(&mut self) {
+        self.is_paused = false;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.is_paused {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    /// Runs `steps` more steps on the next update, pausing first so the result
+    /// can be inspected.
+    pub fn step(&mut self, steps: u64) {
+        self.pause();
+        self.queued_steps += steps;
+    }
+
+    /// Sets how many steps run per frame.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(SPEED_STEPS[0], SPEED_STEPS[SPEED_STEPS.len() - 1]);
+        self.step_accumulator = 0.0;
+    }
+
+    /// Moves to the next faster or slower speed.
+    fn step_speed(&mut self, faster: bool) {
+        let next = if faster {
+            SPEED_STEPS
+                .iter()
+                .copied()
+                .find(|&speed| speed > self.speed)
+        } else {
+            SPEED_STEPS
+                .iter()
+                .copied()
+                .rev()
+                .find(|&speed| speed < self.speed)
+        };
+        if let Some(speed) = next {
+            self.set_speed(speed);
+            info!("Speed {}x", speed);
+        }
+    }
+
+    /// Step count, simulated time, speed and whether the simulation is paused.
+    fn playback_status(&self) -> String {
+        format!(
+            "Step {} (t={:.0}) - {}x{}",
+            self.reaction_diffusion_system.step_count(),
+            self.reaction_diffusion_system.simulated_time(),
+            self.speed,
+            if self.is_paused { " - PAUSED" } else { "" }
+        )
+    }
+
+    /// Shows or hides the statistics graph, running the GPU statistics pass
+    /// only while it is shown.
+    fn toggle_statistics(&mut self, renderer: &mut Renderer) {
+        self.show_statistics = !self.show_statistics;
+        if self.show_statistics {
+            self.reaction_diffusion_system
+                .set_statistics_interval(Some(STATISTICS_INTERVAL));
+        } else {
+            self.reaction_diffusion_system.set_statistics_interval(None);
+            renderer.clear_overlay("statistics");
+            self.mean_v_history.clear();
+            self.last_graphed_step = None;
+        }
+    }
+
+    /// Redraws the statistics graph when a new reduction has arrived.
+    fn update_statistics_graph(&mut self, renderer: &mut Renderer, window: &Window) {
+        let Some(statistics) = self.reaction_diffusion_system.latest_statistics() else {
+            return;
+        };
+        if self.last_graphed_step == Some(statistics.step_count) {
+            return;
+        }
+        self.last_graphed_step = Some(statistics.step_count);
+
+        if self.mean_v_history.len() == statistics_graph::GRAPH_WIDTH as usize {
+            self.mean_v_history.pop_front();
+        }
+        self.mean_v_history.push_back(statistics.v.mean);
+        let pixels = statistics_graph::draw(statistics, &self.mean_v_history);
+
+        // Drawn at twice its size in the bottom right corner
+        let window_size = window.inner_size();
+        let width = 2.0 * statistics_graph::GRAPH_WIDTH as f32 / window_size.width.max(1) as f32;
+        let height = 2.0 * statistics_graph::GRAPH_HEIGHT as f32 / window_size.height.max(1) as f32;
+        renderer.set_overlay(
+            "statistics",
+            statistics_graph::GRAPH_WIDTH,
+            statistics_graph::GRAPH_HEIGHT,
+            &pixels,
+            OverlayRect {
+                x: 0.98 - width,
+                y: 0.02,
+                width,
+                height,
+            },
+        );
+    }
+
+    /// One line per species summarising the latest statistics.
+    fn statistics_summary(&mut self) -> String {
+        match self.reaction_diffusion_system.latest_statistics() {
+            Some(statistics) => [("U", &statistics.u), ("V", &statistics.v)]
+                .iter()
+                .map(|(name, species)| {
+                    format!(
+                        "{}: mean {:.4}, sd {:.4}, min {:.4}, max {:.4}, mass {:.1}",
+                        name,
+                        species.mean,
+                        species.std_dev(),
+                        species.min,
+                        species.max,
+                        species.total_mass
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => "Statistics: press H to start gathering".to_string(),
+        }
+    }
+
+    /// Recomputes the power spectrum overlay from a freshly read back field,
+    /// at most once per refresh interval.
+    fn update_spectrum(&mut self, field: &Field, renderer: &mut Renderer, window: &Window) {
+        if self
+            .last_spectrum_update
+            .is_some_and(|time| time.elapsed() < SPECTRUM_REFRESH_INTERVAL)
+        {
+            return;
+        }
+        self.last_spectrum_update = Some(Instant::now());
+
+        let spectrum = if field.width <= SPECTRUM_MAX_SIZE && field.height <= SPECTRUM_MAX_SIZE {
+            PowerSpectrum::of_v(field)
+        } else {
+            let width = field.width.min(SPECTRUM_MAX_SIZE);
+            let height = field.height.min(SPECTRUM_MAX_SIZE);
+            let region = field.crop(Region {
+                x: (field.width - width) / 2,
+                y: (field.height - height) / 2,
+                width,
+                height,
+            });
+            let values: Vec<f32> = region.v_values().collect();
+            PowerSpectrum::from_values(width, height, &values, SpectrumWindow::Hann)
+        };
+
+        // Drawn as a square in the bottom left corner
+        let window_size = window.inner_size();
+        let side = 384.0f32.min(window_size.height as f32 * 0.45);
+        let pixels = spectrum.to_rgba();
+        renderer.set_overlay(
+            "spectrum",
+            spectrum.width as u32,
+            spectrum.height as u32,
+            &pixels,
+            OverlayRect {
+                x: 0.02,
+                y: 0.02,
+                width: side / window_size.width.max(1) as f32,
+                height: side / window_size.height.max(1) as f32,
+            },
+        );
+        self.spectral_analysis = Some(SpectralAnalysis::of_spectrum(&spectrum));
+    }
+
+    /// Picks up the nutrient field once it has been read back and asks for
+    /// it again when it may have changed, at most once per refresh interval.
+    /// The field is evaluated by the simulation's own shader, so it shows
+    /// exactly the factors the simulation uses.
+    fn update_nutrient_overlay(&mut self, renderer: &mut Renderer) {
+        // Polled even when hidden, so that the readback's buffer is freed
+        if let Some(handle) = self.pending_nutrient_readback.take() {
+            match self.reaction_diffusion_system.poll_nutrient_field(handle) {
+                Readback::Ready(field) => {
+                    self.nutrient_field = Some(field);
+                    self.draw_nutrient_overlay(renderer);
                 }
+                Readback::Pending(handle) => self.pending_nutrient_readback = Some(handle),
+                Readback::Failed(e) => error!("Failed to read back the nutrient field: {}", e),
+            }
+        }
+
+        if self.nutrient_view == NutrientView::Off
+            || self.pending_nutrient_readback.is_some()
+            || self
+                .last_nutrient_field_request
+                .is_some_and(|time| time.elapsed() < NUTRIENT_OVERLAY_REFRESH_INTERVAL)
+        {
+            return;
+        }
+        if let Some(handle) = self.reaction_diffusion_system.request_nutrient_field() {
+            self.pending_nutrient_readback = Some(handle);
+            self.last_nutrient_field_request = Some(Instant::now());
+        }
+    }
+
+    /// Draws the last nutrient field read back in the current view.
+    fn draw_nutrient_overlay(&mut self, renderer: &mut Renderer) {
+        let Some(field) = &self.nutrient_field else {
+            return;
+        };
+        if self.nutrient_view == NutrientView::Off {
+            return;
+        }
+        let pixels = nutrient_overlay::draw(field, self.nutrient_view);
+        let is_new = !renderer.has_overlay("nutrient");
+        renderer.set_overlay(
+            "nutrient",
+            field.width as u32,
+            field.height as u32,
+            &pixels,
+            self.nutrient_view.field_rect(),
+        );
+        // Keep the graphs readable on top of the field
+        if is_new {
+            renderer.send_overlay_to_back("nutrient");
+        }
+    }
+
+    /// The nutrient view and the range of the field it shows.
+    fn nutrient_view_summary(&self) -> String {
+        match &self.nutrient_field {
+            Some(field) if self.nutrient_view != NutrientView::Off => {
+                let (low, high) = field.range();
+                format!(
+                    "Nutrient View: {} (factor {:.2} to {:.2})",
+                    self.nutrient_view.name(),
+                    low,
+                    high
+                )
+            }
+            _ => format!("Nutrient View: {}", self.nutrient_view.name()),
+        }
+    }
+
+    /// The dominant wavelength and anisotropy of the pattern, if analysed.
+    fn spectrum_summary(&self) -> String {
+        match &self.spectral_analysis {
+            Some(analysis) => format!(
+                "Spectrum: wavelength {}, anisotropy {:.2} at {:.0}°",
+                analysis
+                    .dominant_wavelength
+                    .map_or("none".to_string(), |w| format!("{:.1} cells", w)),
+                analysis.anisotropy.ratio,
+                analysis.anisotropy.orientation.to_degrees()
+            ),
+            None => "Spectrum: press O to analyse".to_string(),
+        }
+    }
+
+    /// Starts or stops segmenting the pattern, tracking its spot count from scratch.
+    pub fn toggle_segmentation(&mut self) {
+        self.show_segmentation = !self.show_segmentation;
+        self.segmentation = None;
+        self.mitosis_tracker = MitosisTracker::new();
+        self.last_segmentation_update = None;
+    }
+
+    /// Segments a freshly read back field, at most once per refresh interval,
+    /// and logs changes in the spot count.
+    fn update_segmentation(&mut self, field: &Field) {
+        if self
+            .last_segmentation_update
+            .is_some_and(|time| time.elapsed() < SEGMENTATION_REFRESH_INTERVAL)
+        {
+            return;
+        }
+        self.last_segmentation_update = Some(Instant::now());
+
+        let segmentation = Segmentation::of_v(field, SegmentationOptions::default());
+        match self.mitosis_tracker.record(&segmentation) {
+            Some(SpotCountChange::Divided {
+                step_count,
+                from,
+                to,
+            }) => info!("Spots divided by step {}: {} -> {}", step_count, from, to),
+            Some(SpotCountChange::Vanished {
+                step_count,
+                from,
+                to,
+            }) => info!("Spots lost by step {}: {} -> {}", step_count, from, to),
+            None => {}
+        }
+        self.segmentation = Some(segmentation);
+    }
+
+    /// The component count, areas and pattern class, if segmented.
+    fn segmentation_summary(&self) -> String {
+        match &self.segmentation {
+            Some(segmentation) => {
+                let areas =
+                    segmentation
+                        .area_statistics()
+                        .map_or("no areas".to_string(), |areas| {
+                            format!(
+                                "area {}-{} (median {:.0})",
+                                areas.min, areas.max, areas.median
+                            )
+                        });
+                format!(
+                    "Segmentation: {} with {} components, {}, {} divisions",
+                    segmentation.classify().name(),
+                    segmentation.count(),
+                    areas,
+                    self.mitosis_tracker.divisions
+                )
             }
+            None => "Segmentation: press C to track components".to_string(),
         }
+    }
+
+    /// Starts or stops reseeding whenever the pattern dies out or freezes.
+    pub fn toggle_auto_reseed(&mut self) {
+        self.auto_reseed = !self.auto_reseed;
+        self.convergence_monitor.reset();
+        self.update_convergence_interval();
+    }
+
+    /// Samples the change between steps only while something needs to know
+    /// whether the pattern has died out or settled.
+    fn update_convergence_interval(&mut self) {
+        self.reaction_diffusion_system.set_convergence_interval(
+            if self.auto_reseed || self.is_attract_mode_active {
+                Some(self.convergence_monitor.sample_interval)
+            } else {
+                None
+            },
+        );
+    }
+
+    /// Feeds the newest change sample to the convergence monitor, reseeding
+    /// once the pattern has died out, or stopped changing with auto-reseed on.
+    fn check_convergence(&mut self) {
+        let Some(sample) = self.reaction_diffusion_system.take_change_sample() else {
+            return;
+        };
+        if sample.step_count <= self.reseeded_at_step {
+            return;
+        }
+        match self.convergence_monitor.record(&sample) {
+            Some(ConvergenceEvent::DiedOut { step_count }) => {
+                info!("Pattern died out by step {}, reseeding", step_count);
+                self.reseed();
+            }
+            Some(ConvergenceEvent::SteadyState { step_count }) if self.auto_reseed => {
+                info!("Pattern stopped changing by step {}, reseeding", step_count);
+                self.reseed();
+            }
+            Some(ConvergenceEvent::Oscillating { step_count, period }) => {
+                info!(
+                    "Pattern oscillating with a period of {} steps by step {}",
+                    period, step_count
+                );
+            }
+            Some(ConvergenceEvent::SteadyState { .. }) | None => {}
+        }
+    }
 
-        self.reaction_diffusion_system.update();
+    /// Whether auto-reseeding is on, and the state the pattern has settled in.
+    fn convergence_summary(&self) -> String {
+        if !self.auto_reseed {
+            return "Auto-reseed: off".to_string();
+        }
+        match self.convergence_monitor.state() {
+            Some(ConvergenceEvent::Oscillating { period, .. }) => {
+                format!("Auto-reseed: on, oscillating every {} steps", period)
+            }
+            _ => "Auto-reseed: on".to_string(),
+        }
+    }
+
+    fn update(&mut self, window: &Window) {
+        let (sim_x, sim_y) = self.mouse_to_simulation(window);
+        // Painting is ignored over the nutrient field shown beside the simulation
+        let can_paint = self.is_mouse_over_simulation(window);
+
+        let stroke = if can_paint && self.is_left_mouse_button_held_down {
+            Some(BrushStroke::seed(sim_x, sim_y, self.brush_radius))
+        } else if can_paint && self.is_right_mouse_button_held_down {
+            // Right mouse button creates a void (clears the reaction)
+            Some(BrushStroke::erase(sim_x, sim_y, self.brush_radius))
+        } else {
+            None
+        };
+        if let Some(stroke) = stroke
+            && let Err(e) = self.reaction_diffusion_system.apply_brush(&stroke)
+        {
+            error!("Failed to paint: {}", e);
+        }
+
+        let mut steps = std::mem::take(&mut self.queued_steps);
+        if !self.is_paused {
+            self.step_accumulator += self.speed;
+            let whole_steps = self.step_accumulator.floor();
+            self.step_accumulator -= whole_steps;
+            steps += whole_steps as u64;
+        }
+        for _ in 0..steps {
+            self.reaction_diffusion_system.update();
+        }
+        if self.auto_reseed || self.is_attract_mode_active {
+            self.check_convergence();
+        }
     }
 
     fn draw(&mut self, renderer: &mut Renderer, window: &Window) {
-        // Update the texture with the latest UV values
-        let uvs = self.reaction_diffusion_system.uvs();
-        renderer.update_texture(uvs);
+        // Update the texture with the latest UV values once they arrive, then
+        // start the next readback so the simulation never waits on the display
+        if let Some(handle) = self.pending_readback.take() {
+            match self.reaction_diffusion_system.poll_readback(handle) {
+                // A readback started before a resize no longer fits the texture
+                Readback::Ready(field)
+                    if renderer.simulation_size() == (field.width as u32, field.height as u32) =>
+                {
+                    renderer.update_texture(field.as_slice());
+                    if self.show_spectrum {
+                        self.update_spectrum(&field, renderer, window);
+                    }
+                    if self.show_segmentation {
+                        self.update_segmentation(&field);
+                    }
+                }
+                Readback::Ready(_) => {}
+                Readback::Pending(handle) => self.pending_readback = Some(handle),
+                Readback::Failed(e) => error!("Failed to read back the simulation: {}", e),
+            }
+        }
+        if self.pending_readback.is_none() {
+            self.pending_readback = self.reaction_diffusion_system.request_readback();
+        }
+        if self.show_statistics {
+            self.update_statistics_graph(renderer, window);
+        }
+        self.update_nutrient_overlay(renderer);
 
         // Handle help text visibility
         if self.show_help {
+            let statistics_summary = self.statistics_summary();
             let formatted_help = format!(
                 "Controls:
 Left Mouse Button: Click and drag to seed the reaction
 Right Mouse Button: Click and drag to erase/create voids in the reaction
+[ and ]: Shrink and grow the brush
 X: Clear the screen
 N: Fill the screen with noise
+S: Cycle through initial condition seeders and re-seed (hold SHIFT to cycle backwards)
+R: Re-seed with the current seeder
 G: Cycle through different color gradients (hold SHIFT to cycle backwards)
 P: Cycle through different reaction presets (hold SHIFT to cycle backwards)
-U: Cycle through different nutrient patterns (hold SHIFT to cycle backwards)
+A: Toggle attract mode, which wanders between presets and reseeds when the pattern dies out
+M: Save the current parameters as a bookmarked preset
+J: Jump to the next bookmarked preset (hold SHIFT to go backwards)
+U: Cycle through different nutrient patterns and the nutrient image (hold SHIFT to cycle backwards)
 F: Reverse current color gradient
 Y: Reverse current nutrient pattern
+I: Cycle through the rates the nutrient pattern scales (hold SHIFT to cycle backwards)
+V: Cycle through showing the nutrient field blended, as contours or side by side (hold SHIFT to cycle backwards)
 Z: Toggle psychedelic LUT animation
 Arrow Keys: Adjust feed rate (left/right) and kill rate (up/down) in Custom preset (hold SHIFT for finer control)
+F5: Quicksave the simulation state
+F6: Export the simulation state as a NumPy .npz file
+F9: Quickload the simulation state
+Backspace: Rewind to the previous checkpoint
+W: Toggle resizing the simulation grid with the window
+Page Up/Page Down: Raise and lower the simulation resolution
+L: Toggle smooth upscaling of the simulation
+Space: Pause or resume the simulation
+.: Advance a paused simulation by one step (hold SHIFT for ten)
+- and =: Slow down and speed up the simulation
+H: Toggle the statistics graph (U and V histograms with mean V over time)
+O: Toggle the power spectrum of V with its dominant wavelength
+C: Toggle segmenting the pattern into components and counting spot divisions
+E: Toggle reseeding automatically when the pattern dies out or stops changing
+K: Add a probe under the mouse (hold SHIFT to export probe samples as CSV)
 ? or \\: Toggle help overlay
 ESC: Exit the application
 
 Current Preset: {}
-Current Nutrient Pattern: {} {}",
-                self.get_current_preset_name(),
+Current Seeder: {}
+Resolution: {}x{} cells ({}x)
+{}
+{}
+{}
+{}
+{}
+Current Nutrient Pattern: {}
+Current Nutrient Mapping: {}
+{}",
+                self.preset_summary(),
+                self.seeders[self.current_seeder_index].name(),
+                self.reaction_diffusion_system.width,
+                self.reaction_diffusion_system.height,
+                self.grid_scale,
+                self.playback_status(),
+                statistics_summary,
+                self.spectrum_summary(),
+                self.segmentation_summary(),
+                self.convergence_summary(),
                 self.get_current_nutrient_pattern_name(),
-                if self.is_current_nutrient_pattern_reversed {
-                    "(Reversed)"
-                } else {
-                    ""
-                }
+                self.reaction_diffusion_system.nutrient_mapping(),
+                self.nutrient_view_summary(),
             );
 
             renderer.render_text(&formatted_help, &self.font, window.inner_size());
@@ -703,11 +1996,31 @@ Current Nutrient Pattern: {} {}",
     }
 
     fn update_custom_rates(&mut self, feed_delta: f32, kill_delta: f32) {
-        if self.current_preset_index == 8 {
-            self.custom_feed_rate = (self.custom_feed_rate + feed_delta).clamp(0.0, 0.1);
-            self.custom_kill_rate = (self.custom_kill_rate + kill_delta).clamp(0.0, 0.1);
+        if self.current_preset_index == self.custom_preset_index {
+            let custom = self.presets.get_mut(self.custom_preset_index).unwrap();
+            custom.feed_rate = (custom.feed_rate + feed_delta).clamp(0.0, model_presets::MAX_RATE);
+            custom.kill_rate = (custom.kill_rate + kill_delta).clamp(0.0, model_presets::MAX_RATE);
+            let (feed_rate, kill_rate) = custom.rates();
             self.reaction_diffusion_system
-                .update_rates(self.custom_feed_rate, self.custom_kill_rate);
+                .update_rates(feed_rate, kill_rate);
+        }
+    }
+
+    /// Switches straight to the LUT at `index` of the available LUTs, cutting
+    /// short any transition.
+    fn set_lut(&mut self, index: usize, renderer: &mut Renderer) {
+        let available_luts = self.lut_manager.get_available_luts();
+        match self.lut_manager.load_lut(&available_luts[index]) {
+            Ok(lut_data) => {
+                self.is_lut_transitioning = false;
+                self.current_lut_data = lut_data;
+                self.current_lut_index = index;
+                self.pending_target_lut_index = index;
+                self.source_lut_for_transition = self.current_lut_data.clone();
+                self.target_lut_for_transition = self.current_lut_data.clone();
+                renderer.update_lut(&self.current_lut_data);
+            }
+            Err(_) => error!("Failed to instantly load LUT: {}", available_luts[index]),
         }
     }
 
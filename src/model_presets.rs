@@ -1,9 +1,652 @@
-pub const BRAIN_CORAL: (f32, f32) = (0.0545, 0.062);
-pub const FINGERPRINT: (f32, f32) = (0.0545, 0.062);
-pub const MITOSIS: (f32, f32) = (0.0367, 0.0649);
-pub const RIPPLES: (f32, f32) = (0.018, 0.051);
-pub const SOLITON_COLLAPSE: (f32, f32) = (0.022, 0.06);
-pub const U_SKATE_WORLD: (f32, f32) = (0.062, 0.061);
-pub const UNDULATING: (f32, f32) = (0.026, 0.051);
-pub const WORMS: (f32, f32) = (0.078, 0.061);
-pub const CUSTOM: (f32, f32) = (0.035, 0.058); // Default values that can be adjusted
+use crate::lut_manager::LutManager;
+use crate::nutrient_presets::NutrientPattern;
+use crate::seeders::{self, normalize_name};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::ops::Index;
+use std::path::{Path, PathBuf};
+
+/// Diffusion rate of U shared by the built-in presets.
+pub const DEFAULT_DELTA_U: f32 = 1.0;
+/// Diffusion rate of V shared by the built-in presets.
+pub const DEFAULT_DELTA_V: f32 = 0.5;
+
+/// Largest feed or kill rate a preset may have. Pearson Sigma has the highest
+/// feed rate of the built-in presets.
+pub const MAX_RATE: f32 = 0.12;
+
+/// Largest diffusion rate the simulation stays stable at. The Laplacian
+/// stencil scales a checkerboard by -1.6, so each step multiplies it by
+/// `1 - 1.6 * D`, which grows without bound once `D` passes 1.25.
+pub const MAX_DIFFUSION_RATE: f32 = 1.25;
+
+/// A named set of model parameters, along with how it is best shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    pub name: Cow<'static, str>,
+    pub description: Cow<'static, str>,
+    pub feed_rate: f32,
+    pub kill_rate: f32,
+    pub delta_u: f32,
+    pub delta_v: f32,
+    /// Name of the seeder that suits the preset. `None` keeps the current one.
+    pub seeder: Option<Cow<'static, str>>,
+    /// `None` keeps the current nutrient pattern.
+    pub nutrient_pattern: Option<NutrientPattern>,
+    /// Whether `nutrient_pattern` is reversed.
+    pub is_nutrient_pattern_reversed: bool,
+    /// Name of the LUT that suits the preset. `None` keeps the current one.
+    pub lut: Option<Cow<'static, str>>,
+}
+
+impl Preset {
+    /// A preset with the given rates, the default diffusion rates and no
+    /// display recommendations.
+    pub const fn new(name: &'static str, description: &'static str, rates: (f32, f32)) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            description: Cow::Borrowed(description),
+            feed_rate: rates.0,
+            kill_rate: rates.1,
+            delta_u: DEFAULT_DELTA_U,
+            delta_v: DEFAULT_DELTA_V,
+            seeder: None,
+            nutrient_pattern: None,
+            is_nutrient_pattern_reversed: false,
+            lut: None,
+        }
+    }
+
+    /// Like [`Preset::new`], but recommending the seeder named `seeder` for
+    /// presets that only show their behaviour from a particular start.
+    pub const fn with_seeder(
+        name: &'static str,
+        description: &'static str,
+        rates: (f32, f32),
+        seeder: &'static str,
+    ) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            description: Cow::Borrowed(description),
+            feed_rate: rates.0,
+            kill_rate: rates.1,
+            delta_u: DEFAULT_DELTA_U,
+            delta_v: DEFAULT_DELTA_V,
+            seeder: Some(Cow::Borrowed(seeder)),
+            nutrient_pattern: None,
+            is_nutrient_pattern_reversed: false,
+            lut: None,
+        }
+    }
+
+    pub fn rates(&self) -> (f32, f32) {
+        (self.feed_rate, self.kill_rate)
+    }
+}
+
+pub const BRAIN_CORAL: Preset = Preset::new(
+    "Brain Coral",
+    "Winding stripes that fill the grid like the ridges of brain coral.",
+    (0.0545, 0.062),
+);
+pub const FINGERPRINT: Preset = Preset::new(
+    "Fingerprint",
+    "Stripes that settle into whorls and loops like a fingerprint.",
+    (0.037, 0.06),
+);
+pub const MITOSIS: Preset = Preset::new(
+    "Mitosis",
+    "Spots that grow, divide and fill the grid.",
+    (0.0367, 0.0649),
+);
+pub const RIPPLES: Preset = Preset::with_seeder(
+    "Ripples",
+    "Waves that spread out from the seed and break up into spots and loops.",
+    (0.018, 0.051),
+    "Perlin Noise",
+);
+pub const SOLITON_COLLAPSE: Preset = Preset::with_seeder(
+    "Soliton Collapse",
+    "Lone spots that drift, collide and collapse.",
+    (0.022, 0.06),
+    "Dot Grid",
+);
+pub const U_SKATE_WORLD: Preset = Preset::new(
+    "U-Skate World",
+    "Pearson's class pi: small gliders that skate across a still background.",
+    (0.062, 0.061),
+);
+pub const UNDULATING: Preset = Preset::new(
+    "Undulating",
+    "Pearson's class beta: patches that pulse and shift without ever settling.",
+    (0.026, 0.051),
+);
+pub const WORMS: Preset = Preset::new(
+    "Worms",
+    "Separate worm-like stripes that lengthen until they fill the grid.",
+    (0.078, 0.061),
+);
+
+// Pearson's classes, from "Complex Patterns in a Simple System" (1993), and
+// the rho and sigma classes Munafo added, at the rates xmorphia gives for
+// them. Classes beta and pi are Undulating and U-Skate World.
+pub const PEARSON_ALPHA: Preset = Preset::with_seeder(
+    "Pearson Alpha",
+    "Pearson's class alpha: fragments of spiral waves that chase each other forever.",
+    (0.010, 0.047),
+    "Perlin Noise",
+);
+pub const PEARSON_GAMMA: Preset = Preset::with_seeder(
+    "Pearson Gamma",
+    "Pearson's class gamma: stripes and spots that keep breaking up and reforming.",
+    (0.022, 0.051),
+    "Dot Grid",
+);
+pub const PEARSON_DELTA: Preset = Preset::new(
+    "Pearson Delta",
+    "Pearson's class delta: a sheet riddled with holes that slowly line up.",
+    (0.030, 0.055),
+);
+pub const PEARSON_EPSILON: Preset = Preset::with_seeder(
+    "Pearson Epsilon",
+    "Pearson's class epsilon: spots that divide and die in turn, never settling.",
+    (0.018, 0.055),
+    "Perlin Noise",
+);
+pub const PEARSON_ZETA: Preset = Preset::with_seeder(
+    "Pearson Zeta",
+    "Pearson's class zeta: spots that spread apart and then hold still.",
+    (0.022, 0.061),
+    "Dot Grid",
+);
+pub const PEARSON_ETA: Preset = Preset::new(
+    "Pearson Eta",
+    "Pearson's class eta: spots, some stretched into short worms.",
+    (0.034, 0.063),
+);
+pub const PEARSON_THETA: Preset = Preset::new(
+    "Pearson Theta",
+    "Pearson's class theta: stripes that branch and join into a labyrinth.",
+    (0.038, 0.061),
+);
+pub const PEARSON_IOTA: Preset = Preset::new(
+    "Pearson Iota",
+    "Pearson's class iota: a sheet pierced by scattered holes.",
+    (0.046, 0.0594),
+);
+pub const PEARSON_KAPPA: Preset = Preset::new(
+    "Pearson Kappa",
+    "Pearson's class kappa: stripes that grow from the seed until they fill the grid.",
+    (0.050, 0.063),
+);
+pub const PEARSON_LAMBDA: Preset = Preset::new(
+    "Pearson Lambda",
+    "Pearson's class lambda: spots that divide slowly until they fill the grid.",
+    (0.034, 0.065),
+);
+pub const PEARSON_MU: Preset = Preset::new(
+    "Pearson Mu",
+    "Pearson's class mu: worms that lengthen from their ends and fold around each other.",
+    (0.046, 0.065),
+);
+pub const PEARSON_NU: Preset = Preset::new(
+    "Pearson Nu",
+    "Pearson's class nu: a sparse scattering of stable spots.",
+    (0.054, 0.067),
+);
+pub const PEARSON_XI: Preset = Preset::with_seeder(
+    "Pearson Xi",
+    "Pearson's class xi: waves that spiral out from a single seed, given a large enough grid.",
+    (0.014, 0.047),
+    "Center Disc",
+);
+pub const PEARSON_RHO: Preset = Preset::with_seeder(
+    "Pearson Rho",
+    "Pearson's class rho: a sheet of V pierced by holes that hold their shape.",
+    (0.090, 0.059),
+    "Perforated Sheet",
+);
+pub const PEARSON_SIGMA: Preset = Preset::with_seeder(
+    "Pearson Sigma",
+    "Pearson's class sigma: a sheet of V whose holes fill in until it is uniform.",
+    (0.110, 0.0523),
+    "Perforated Sheet",
+);
+
+// Regions of the f-k plane Munafo named in xmorphia.
+pub const SOLITONS: Preset = Preset::new(
+    "Solitons",
+    "Stable spots that keep their distance from each other.",
+    (0.030, 0.062),
+);
+pub const PULSATING_SOLITONS: Preset = Preset::new(
+    "Pulsating Solitons",
+    "Spots that swell and shrink as they settle apart.",
+    (0.025, 0.060),
+);
+pub const MAZES: Preset = Preset::new(
+    "Mazes",
+    "Stripes that join into a maze with few dead ends.",
+    (0.029, 0.057),
+);
+pub const HOLES: Preset = Preset::new(
+    "Holes",
+    "A sheet with holes that push each other apart and then stop.",
+    (0.039, 0.058),
+);
+pub const CHAOS_AND_HOLES: Preset = Preset::new(
+    "Chaos and Holes",
+    "Holes that open, grow and close again without settling.",
+    (0.034, 0.056),
+);
+pub const MOVING_SPOTS: Preset = Preset::with_seeder(
+    "Moving Spots",
+    "Spots that wander, divide and collide.",
+    (0.014, 0.054),
+    "Perlin Noise",
+);
+/// Default values that can be adjusted.
+pub const CUSTOM: Preset = Preset::new(
+    "Custom",
+    "Rates adjusted by hand with the arrow keys.",
+    (0.035, 0.058),
+);
+
+/// An ordered list of presets with unique names.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PresetRegistry {
+    presets: Vec<Preset>,
+}
+
+impl PresetRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in presets, in the order the app cycles through them.
+    pub fn builtin() -> Self {
+        Self {
+            presets: vec![
+                BRAIN_CORAL,
+                FINGERPRINT,
+                MITOSIS,
+                RIPPLES,
+                SOLITON_COLLAPSE,
+                U_SKATE_WORLD,
+                UNDULATING,
+                WORMS,
+                PEARSON_ALPHA,
+                PEARSON_GAMMA,
+                PEARSON_DELTA,
+                PEARSON_EPSILON,
+                PEARSON_ZETA,
+                PEARSON_ETA,
+                PEARSON_THETA,
+                PEARSON_IOTA,
+                PEARSON_KAPPA,
+                PEARSON_LAMBDA,
+                PEARSON_MU,
+                PEARSON_NU,
+                PEARSON_XI,
+                PEARSON_RHO,
+                PEARSON_SIGMA,
+                SOLITONS,
+                PULSATING_SOLITONS,
+                MAZES,
+                HOLES,
+                CHAOS_AND_HOLES,
+                MOVING_SPOTS,
+                CUSTOM,
+            ],
+        }
+    }
+
+    /// Adds `preset`, replacing any preset with the same name. Returns its index.
+    pub fn add(&mut self, preset: Preset) -> usize {
+        match self.position_by_name(&preset.name) {
+            Some(index) => {
+                self.presets[index] = preset;
+                index
+            }
+            None => {
+                self.presets.push(preset);
+                self.presets.len() - 1
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.presets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Preset> {
+        self.presets.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Preset> {
+        self.presets.get_mut(index)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Preset> {
+        self.presets.iter()
+    }
+
+    /// Finds a preset by name, ignoring case, spaces and punctuation, so
+    /// "u-skate world" and "USkateWorld" both find U-Skate World.
+    pub fn position_by_name(&self, name: &str) -> Option<usize> {
+        let name = normalize_name(name);
+        self.presets
+            .iter()
+            .position(|p| normalize_name(&p.name) == name)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Preset> {
+        self.position_by_name(name)
+            .map(|index| &self.presets[index])
+    }
+
+    /// Adds the presets from `path`, which is either a preset file or a
+    /// directory whose `.toml` and `.json` files are read in name order. A
+    /// preset named like one already registered replaces it, otherwise it goes
+    /// after the rest. Each file loads completely or not at all, and the
+    /// errors of any that failed are returned.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Vec<io::Error> {
+        let path = path.as_ref();
+        let files = if path.is_dir() {
+            match preset_files_in(path) {
+                Ok(files) => files,
+                Err(e) => return vec![e],
+            }
+        } else {
+            vec![path.to_path_buf()]
+        };
+
+        let mut errors = Vec::new();
+        for file in files {
+            match load_presets(&file) {
+                Ok(presets) => {
+                    for preset in presets {
+                        self.add(preset);
+                    }
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        errors
+    }
+}
+
+impl Index<usize> for PresetRegistry {
+    type Output = Preset;
+
+    fn index(&self, index: usize) -> &Preset {
+        &self.presets[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a PresetRegistry {
+    type Item = &'a Preset;
+    type IntoIter = std::slice::Iter<'a, Preset>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Where the app looks for user presets, under the platform's config directory.
+pub fn user_preset_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("gray_scott_reaction_diffusion").join("presets"))
+}
+
+/// The file in the user preset directory that presets saved from the app go to.
+pub fn saved_presets_path() -> Option<PathBuf> {
+    user_preset_dir().map(|dir| dir.join("saved.toml"))
+}
+
+/// Reads the presets in a TOML file, or a JSON one if the extension is
+/// `.json`. Both hold a list of presets under `preset`:
+///
+/// ```toml
+/// [[preset]]
+/// name = "Pulsing Spots"
+/// description = "Spots that swell and shrink."
+/// feed_rate = 0.025
+/// kill_rate = 0.06
+/// # The rest are optional
+/// delta_u = 1.0
+/// delta_v = 0.5
+/// seeder = "random-circles"
+/// nutrient_pattern = "radial-gradient"
+/// nutrient_pattern_reversed = false
+/// lut = "MATPLOTLIB_bone_r"
+/// ```
+///
+/// Every preset is validated, names must differ within the file, and errors
+/// name the file and the preset.
+pub fn load_presets(path: impl AsRef<Path>) -> io::Result<Vec<Preset>> {
+    let path = path.as_ref();
+    let invalid = |message: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), message),
+        )
+    };
+
+    let text = fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let file: PresetFile = if is_json(path) {
+        serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))?
+    } else {
+        toml::from_str(&text).map_err(|e| invalid(e.to_string()))?
+    };
+
+    let presets: Vec<Preset> = file
+        .presets
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let label = format!("preset {} \"{}\"", index + 1, entry.name);
+            entry
+                .into_preset()
+                .map_err(|problem| invalid(format!("{}: {}", label, problem)))
+        })
+        .collect::<io::Result<_>>()?;
+
+    // Names are matched like `PresetRegistry::position_by_name` does, so a
+    // later preset would silently replace an earlier one
+    for (index, preset) in presets.iter().enumerate() {
+        let name = normalize_name(&preset.name);
+        if let Some(first) = presets[..index]
+            .iter()
+            .position(|p| normalize_name(&p.name) == name)
+        {
+            return Err(invalid(format!(
+                "preset {} \"{}\" has the same name as preset {} \"{}\"",
+                index + 1,
+                preset.name,
+                first + 1,
+                presets[first].name
+            )));
+        }
+    }
+    Ok(presets)
+}
+
+/// Adds `preset` to the preset file at `path`, replacing any preset there with
+/// the same name, and creating the file and its directory if needed. The file
+/// is written as JSON if its extension is `.json` and TOML otherwise. The
+/// preset is validated first, so the file can always be loaded back.
+pub fn save_preset(path: impl AsRef<Path>, preset: &Preset) -> io::Result<()> {
+    let path = path.as_ref();
+    PresetEntry::from(preset).into_preset().map_err(|problem| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("preset \"{}\": {}", preset.name, problem),
+        )
+    })?;
+
+    // An unreadable file is left alone rather than overwritten
+    let mut presets = if path.exists() {
+        load_presets(path)?
+    } else {
+        Vec::new()
+    };
+    let name = normalize_name(&preset.name);
+    match presets.iter().position(|p| normalize_name(&p.name) == name) {
+        Some(index) => presets[index] = preset.clone(),
+        None => presets.push(preset.clone()),
+    }
+
+    let file = PresetFile {
+        presets: presets.iter().map(PresetEntry::from).collect(),
+    };
+    let text = if is_json(path) {
+        serde_json::to_string_pretty(&file).map_err(io::Error::other)?
+    } else {
+        toml::to_string_pretty(&file).map_err(io::Error::other)?
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text)
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+/// The preset files directly inside `dir`, sorted by name.
+fn preset_files_in(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_preset_file = path.extension().is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("toml") || extension.eq_ignore_ascii_case("json")
+        });
+        if path.is_file() && is_preset_file {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The layout of a preset file.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct PresetFile {
+    #[serde(default, rename = "preset")]
+    presets: Vec<PresetEntry>,
+}
+
+/// A preset as written in a file, before validation.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct PresetEntry {
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    description: String,
+    feed_rate: f32,
+    kill_rate: f32,
+    delta_u: Option<f32>,
+    delta_v: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seeder: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nutrient_pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    nutrient_pattern_reversed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lut: Option<String>,
+}
+
+impl From<&Preset> for PresetEntry {
+    fn from(preset: &Preset) -> Self {
+        Self {
+            name: preset.name.to_string(),
+            description: preset.description.to_string(),
+            feed_rate: preset.feed_rate,
+            kill_rate: preset.kill_rate,
+            delta_u: Some(preset.delta_u),
+            delta_v: Some(preset.delta_v),
+            seeder: preset.seeder.as_ref().map(|name| name.to_string()),
+            nutrient_pattern: preset.nutrient_pattern.map(|p| p.name().to_string()),
+            nutrient_pattern_reversed: preset.is_nutrient_pattern_reversed,
+            lut: preset.lut.as_ref().map(|name| name.to_string()),
+        }
+    }
+}
+
+impl PresetEntry {
+    fn into_preset(self) -> Result<Preset, String> {
+        if self.name.trim().is_empty() {
+            return Err("name is empty".to_string());
+        }
+        for (field, rate) in [("feed_rate", self.feed_rate), ("kill_rate", self.kill_rate)] {
+            if !(0.0..=MAX_RATE).contains(&rate) {
+                return Err(format!(
+                    "{} is {}, but must be between 0 and {}",
+                    field, rate, MAX_RATE
+                ));
+            }
+        }
+        let delta_u = self.delta_u.unwrap_or(DEFAULT_DELTA_U);
+        let delta_v = self.delta_v.unwrap_or(DEFAULT_DELTA_V);
+        for (field, rate) in [("delta_u", delta_u), ("delta_v", delta_v)] {
+            if !(rate > 0.0 && rate <= MAX_DIFFUSION_RATE) {
+                return Err(format!(
+                    "{} is {}, but must be above 0 and at most {}",
+                    field, rate, MAX_DIFFUSION_RATE
+                ));
+            }
+        }
+
+        if let Some(seeder) = &self.seeder {
+            let builtin = seeders::builtin();
+            if seeders::position_by_name(&builtin, seeder).is_none() {
+                let names: Vec<&str> = builtin.iter().map(|s| s.name()).collect();
+                return Err(format!(
+                    "unknown seeder \"{}\", expected one of: {}",
+                    seeder,
+                    names.join(", ")
+                ));
+            }
+        }
+        let nutrient_pattern = match &self.nutrient_pattern {
+            Some(name) => Some(NutrientPattern::from_name(name).ok_or_else(|| {
+                let names: Vec<&str> = NutrientPattern::all().iter().map(|p| p.name()).collect();
+                format!(
+                    "unknown nutrient pattern \"{}\", expected one of: {}",
+                    name,
+                    names.join(", ")
+                )
+            })?),
+            None => None,
+        };
+        if let Some(lut) = &self.lut
+            && !LutManager::new().get_available_luts().contains(lut)
+        {
+            return Err(format!("unknown LUT \"{}\"", lut));
+        }
+
+        Ok(Preset {
+            name: Cow::Owned(self.name),
+            description: Cow::Owned(self.description),
+            feed_rate: self.feed_rate,
+            kill_rate: self.kill_rate,
+            delta_u,
+            delta_v,
+            seeder: self.seeder.map(Cow::Owned),
+            nutrient_pattern,
+            is_nutrient_pattern_reversed: self.nutrient_pattern_reversed,
+            lut: self.lut.map(Cow::Owned),
+        })
+    }
+}
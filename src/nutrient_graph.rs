@@ -0,0 +1,481 @@
+//! Nutrient patterns combined into a single field of feed rate multipliers.
+//!
+//! A [`NutrientGraph`] is a tree of patterns joined by operators. On the GPU
+//! it runs as a short postfix program held in a uniform buffer, which
+//! reaction_diffusion.wgsl evaluates with a small stack for every cell.
+
+use crate::nutrient_map::NutrientMap;
+use crate::nutrient_presets::{NutrientParams, NutrientPattern};
+use bytemuck::{Pod, Zeroable};
+use std::fmt;
+use std::ops::{Add, Mul};
+use std::sync::Arc;
+
+/// Most nodes a graph can have and still fit in the GPU's uniform buffer.
+/// Must match `MAX_NUTRIENT_NODES` in reaction_diffusion.wgsl.
+pub const MAX_NODES: usize = 16;
+/// Most values the GPU's evaluation stack can hold at once. Must match
+/// `NUTRIENT_STACK_SIZE` in reaction_diffusion.wgsl.
+pub const MAX_STACK_DEPTH: usize = 8;
+
+/// Program node that reads the graph's image, as opposed to a pattern (0) or
+/// an [`Operator`]. Must match `OP_IMAGE` in reaction_diffusion.wgsl.
+pub(crate) const IMAGE_OP: u32 = 6;
+
+/// A nutrient pattern, or patterns combined with operators.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NutrientGraph {
+    Pattern(NutrientPattern, NutrientParams),
+    /// An image laid out like a pattern. A graph can use only one image,
+    /// though it can appear more than once.
+    Image(Arc<NutrientMap>, NutrientParams),
+    Add(Box<NutrientGraph>, Box<NutrientGraph>),
+    Multiply(Box<NutrientGraph>, Box<NutrientGraph>),
+    Min(Box<NutrientGraph>, Box<NutrientGraph>),
+    Max(Box<NutrientGraph>, Box<NutrientGraph>),
+    /// `a` where `mask` is 0, `b` where it is 1 and a mix in between. The mask
+    /// is clamped between 0 and 1, so masks usually span that range.
+    Blend {
+        a: Box<NutrientGraph>,
+        b: Box<NutrientGraph>,
+        mask: Box<NutrientGraph>,
+    },
+}
+
+impl Default for NutrientGraph {
+    fn default() -> Self {
+        Self::pattern(NutrientPattern::Uniform)
+    }
+}
+
+impl From<NutrientPattern> for NutrientGraph {
+    fn from(pattern: NutrientPattern) -> Self {
+        Self::pattern(pattern)
+    }
+}
+
+impl NutrientGraph {
+    /// `pattern` with its default parameters.
+    pub fn pattern(pattern: NutrientPattern) -> Self {
+        Self::Pattern(pattern, pattern.default_params())
+    }
+
+    pub fn with_params(pattern: NutrientPattern, params: NutrientParams) -> Self {
+        Self::Pattern(pattern, params)
+    }
+
+    /// `map` stretched over the grid, mapped between 0.5 and 1 like most
+    /// patterns.
+    pub fn image(map: impl Into<Arc<NutrientMap>>) -> Self {
+        Self::Image(map.into(), NutrientParams::default())
+    }
+
+    pub fn image_with_params(map: impl Into<Arc<NutrientMap>>, params: NutrientParams) -> Self {
+        Self::Image(map.into(), params)
+    }
+
+    pub fn min(self, other: impl Into<NutrientGraph>) -> Self {
+        Self::Min(Box::new(self), Box::new(other.into()))
+    }
+
+    pub fn max(self, other: impl Into<NutrientGraph>) -> Self {
+        Self::Max(Box::new(self), Box::new(other.into()))
+    }
+
+    /// Mixes from `self` to `other` as `mask` goes from 0 to 1.
+    pub fn blend(self, other: impl Into<NutrientGraph>, mask: impl Into<NutrientGraph>) -> Self {
+        Self::Blend {
+            a: Box::new(self),
+            b: Box::new(other.into()),
+            mask: Box::new(mask.into()),
+        }
+    }
+
+    /// The single pattern this graph consists of, if it isn't a combination.
+    pub fn as_pattern(&self) -> Option<(NutrientPattern, &NutrientParams)> {
+        match self {
+            Self::Pattern(pattern, params) => Some((*pattern, params)),
+            _ => None,
+        }
+    }
+
+    /// The image the graph reads, if any.
+    pub fn image_map(&self) -> Option<&Arc<NutrientMap>> {
+        match self {
+            Self::Pattern(..) => None,
+            Self::Image(map, _) => Some(map),
+            Self::Add(a, b) | Self::Multiply(a, b) | Self::Min(a, b) | Self::Max(a, b) => {
+                a.image_map().or_else(|| b.image_map())
+            }
+            Self::Blend { a, b, mask } => a
+                .image_map()
+                .or_else(|| b.image_map())
+                .or_else(|| mask.image_map()),
+        }
+    }
+
+    /// Reverses every pattern in the graph.
+    pub fn toggle_reversal(&mut self) {
+        self.for_each_params(&mut |params| params.is_reversed = !params.is_reversed);
+    }
+
+    fn for_each_params(&mut self, f: &mut impl FnMut(&mut NutrientParams)) {
+        match self {
+            Self::Pattern(_, params) | Self::Image(_, params) => f(params),
+            Self::Add(a, b) | Self::Multiply(a, b) | Self::Min(a, b) | Self::Max(a, b) => {
+                a.for_each_params(f);
+                b.for_each_params(f);
+            }
+            Self::Blend { a, b, mask } => {
+                a.for_each_params(f);
+                b.for_each_params(f);
+                mask.for_each_params(f);
+            }
+        }
+    }
+
+    /// Whether any pattern in the graph changes over time.
+    pub fn is_animated(&self) -> bool {
+        match self {
+            Self::Pattern(pattern, _) => pattern.is_animated(),
+            Self::Image(..) => false,
+            Self::Add(a, b) | Self::Multiply(a, b) | Self::Min(a, b) | Self::Max(a, b) => {
+                a.is_animated() || b.is_animated()
+            }
+            Self::Blend { a, b, mask } => a.is_animated() || b.is_animated() || mask.is_animated(),
+        }
+    }
+
+    /// The feed rate multiplier at cell `(x, y)` of a `width` by `height` grid
+    /// at simulated `time`. Mirrors `get_nutrient_factor` in
+    /// reaction_diffusion.wgsl.
+    pub fn factor(&self, x: usize, y: usize, width: usize, height: usize, time: f32) -> f32 {
+        let factor = |graph: &NutrientGraph| graph.factor(x, y, width, height, time);
+        match self {
+            Self::Pattern(pattern, params) => pattern.factor(x, y, width, height, params, time),
+            Self::Image(map, params) => map.factor(x, y, width, height, params),
+            Self::Add(a, b) => factor(a) + factor(b),
+            Self::Multiply(a, b) => factor(a) * factor(b),
+            Self::Min(a, b) => factor(a).min(factor(b)),
+            Self::Max(a, b) => factor(a).max(factor(b)),
+            Self::Blend { a, b, mask } => {
+                let (a, b) = (factor(a), factor(b));
+                a + (b - a) * factor(mask).clamp(0.0, 1.0)
+            }
+        }
+    }
+
+    /// The factor of every cell of a `width` by `height` grid at simulated
+    /// `time`, row by row.
+    pub fn factors(&self, width: usize, height: usize, time: f32) -> Vec<f32> {
+        (0..width * height)
+            .map(|i| self.factor(i % width, i / width, width, height, time))
+            .collect()
+    }
+
+    /// Checks that the graph fits on the GPU and its parameters are usable.
+    pub fn validate(&self) -> Result<(), String> {
+        let program = self.program();
+        if program.len() > MAX_NODES {
+            return Err(format!(
+                "nutrient graph has {} nodes, more than the {} allowed",
+                program.len(),
+                MAX_NODES
+            ));
+        }
+        let depth = stack_depth(&program);
+        if depth > MAX_STACK_DEPTH {
+            return Err(format!(
+                "nutrient graph needs a stack {} deep, more than the {} allowed",
+                depth, MAX_STACK_DEPTH
+            ));
+        }
+        if let Some(map) = self.image_map() {
+            let mut maps = program.iter().filter_map(|node| match node {
+                ProgramNode::Image(other, _) => Some(other),
+                _ => None,
+            });
+            if !maps.all(|other| Arc::ptr_eq(map, other) || map == other) {
+                return Err("nutrient graph uses more than one image".to_string());
+            }
+        }
+        for node in &program {
+            let (name, params) = match node {
+                ProgramNode::Pattern(pattern, params) => (pattern.name(), params),
+                ProgramNode::Image(_, params) => ("Image", params),
+                ProgramNode::Operator(_) => continue,
+            };
+            let values = [
+                params.scale,
+                params.orientation,
+                params.center.0,
+                params.center.1,
+                params.contrast,
+                params.min,
+                params.max,
+                params.speed,
+            ];
+            if values.iter().any(|value| !value.is_finite()) {
+                return Err(format!("{} has a parameter that isn't finite", name));
+            }
+            if params.scale <= 0.0 {
+                return Err(format!("{} has a scale that isn't above 0", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// The graph in the order the GPU evaluates it, operands before operators.
+    pub(crate) fn program(&self) -> Vec<ProgramNode> {
+        let mut program = Vec::new();
+        self.push_program(&mut program);
+        program
+    }
+
+    fn push_program(&self, program: &mut Vec<ProgramNode>) {
+        let operator = match self {
+            Self::Pattern(pattern, params) => {
+                program.push(ProgramNode::Pattern(*pattern, *params));
+                return;
+            }
+            Self::Image(map, params) => {
+                program.push(ProgramNode::Image(map.clone(), *params));
+                return;
+            }
+            Self::Add(a, b) | Self::Multiply(a, b) | Self::Min(a, b) | Self::Max(a, b) => {
+                a.push_program(program);
+                b.push_program(program);
+                match self {
+                    Self::Add(..) => Operator::Add,
+                    Self::Multiply(..) => Operator::Multiply,
+                    Self::Min(..) => Operator::Min,
+                    _ => Operator::Max,
+                }
+            }
+            Self::Blend { a, b, mask } => {
+                a.push_program(program);
+                b.push_program(program);
+                mask.push_program(program);
+                Operator::Blend
+            }
+        };
+        program.push(ProgramNode::Operator(operator));
+    }
+
+    /// Rebuilds a graph from the nodes of [`Self::program`], or `None` if
+    /// they don't form exactly one graph.
+    pub(crate) fn from_program(program: &[ProgramNode]) -> Option<Self> {
+        let mut stack: Vec<NutrientGraph> = Vec::new();
+        for node in program {
+            let graph = match node {
+                ProgramNode::Pattern(pattern, params) => Self::Pattern(*pattern, *params),
+                ProgramNode::Image(map, params) => Self::Image(map.clone(), *params),
+                ProgramNode::Operator(Operator::Blend) => {
+                    let mask = stack.pop()?;
+                    let b = stack.pop()?;
+                    let a = stack.pop()?;
+                    a.blend(b, mask)
+                }
+                ProgramNode::Operator(operator) => {
+                    let b = Box::new(stack.pop()?);
+                    let a = Box::new(stack.pop()?);
+                    match *operator {
+                        Operator::Add => Self::Add(a, b),
+                        Operator::Multiply => Self::Multiply(a, b),
+                        Operator::Min => Self::Min(a, b),
+                        Operator::Max => Self::Max(a, b),
+                        Operator::Blend => unreachable!("Blend is handled above"),
+                    }
+                }
+            };
+            stack.push(graph);
+        }
+        match (stack.pop(), stack.is_empty()) {
+            (Some(graph), true) => Some(graph),
+            _ => None,
+        }
+    }
+
+    /// The uniform buffer contents for reaction_diffusion.wgsl.
+    pub(crate) fn to_gpu(&self) -> GpuNutrientGraph {
+        let program = self.program();
+        assert!(
+            program.len() <= MAX_NODES,
+            "Nutrient graph has {} nodes, more than the {} allowed",
+            program.len(),
+            MAX_NODES
+        );
+        let mut nodes = [GpuNutrientNode::zeroed(); MAX_NODES];
+        for (gpu_node, node) in nodes.iter_mut().zip(&program) {
+            *gpu_node = GpuNutrientNode::from(node);
+        }
+        GpuNutrientGraph {
+            node_count: program.len() as u32,
+            _padding: [0; 3],
+            nodes,
+        }
+    }
+}
+
+/// The most values on the stack at once while evaluating `program`.
+fn stack_depth(program: &[ProgramNode]) -> usize {
+    let mut depth: usize = 0;
+    let mut deepest = 0;
+    for node in program {
+        depth = match node {
+            ProgramNode::Pattern(..) | ProgramNode::Image(..) => depth + 1,
+            ProgramNode::Operator(Operator::Blend) => depth.saturating_sub(2),
+            ProgramNode::Operator(_) => depth.saturating_sub(1),
+        };
+        deepest = deepest.max(depth);
+    }
+    deepest
+}
+
+impl Add for NutrientGraph {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::Add(Box::new(self), Box::new(other))
+    }
+}
+
+impl Mul for NutrientGraph {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::Multiply(Box::new(self), Box::new(other))
+    }
+}
+
+impl fmt::Display for NutrientGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pattern(pattern, params) => {
+                write!(f, "{}", pattern.name())?;
+                if params.is_reversed {
+                    write!(f, " (Reversed)")?;
+                }
+                Ok(())
+            }
+            Self::Image(_, params) => {
+                write!(f, "Image")?;
+                if params.is_reversed {
+                    write!(f, " (Reversed)")?;
+                }
+                Ok(())
+            }
+            Self::Add(a, b) => write!(f, "({} + {})", a, b),
+            Self::Multiply(a, b) => write!(f, "({} x {})", a, b),
+            Self::Min(a, b) => write!(f, "min({}, {})", a, b),
+            Self::Max(a, b) => write!(f, "max({}, {})", a, b),
+            Self::Blend { a, b, mask } => write!(f, "blend({}, {} by {})", a, b, mask),
+        }
+    }
+}
+
+/// The nutrient factor of every cell of a grid, as evaluated on the GPU after
+/// `step_count` steps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NutrientField {
+    pub width: usize,
+    pub height: usize,
+    pub step_count: u64,
+    /// Row by row, row 0 at the bottom like the grid.
+    pub factors: Vec<f32>,
+}
+
+impl NutrientField {
+    /// The lowest and highest factor.
+    pub fn range(&self) -> (f32, f32) {
+        self.factors.iter().fold(
+            (f32::INFINITY, f32::NEG_INFINITY),
+            |(low, high), &factor| (low.min(factor), high.max(factor)),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operator {
+    Add = 1,
+    Multiply = 2,
+    Min = 3,
+    Max = 4,
+    Blend = 5,
+}
+
+impl Operator {
+    pub(crate) fn from_u32(value: u32) -> Option<Self> {
+        [
+            Operator::Add,
+            Operator::Multiply,
+            Operator::Min,
+            Operator::Max,
+            Operator::Blend,
+        ]
+        .into_iter()
+        .find(|op| *op as u32 == value)
+    }
+}
+
+/// One step of a graph's postfix program.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ProgramNode {
+    Pattern(NutrientPattern, NutrientParams),
+    Image(Arc<NutrientMap>, NutrientParams),
+    Operator(Operator),
+}
+
+/// Matches `NutrientNode` in reaction_diffusion.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub(crate) struct GpuNutrientNode {
+    /// 0 for a pattern, [`IMAGE_OP`] for the image, otherwise an
+    /// [`Operator`].
+    op: u32,
+    pattern: u32,
+    is_reversed: u32,
+    _padding: u32,
+    center: [f32; 2],
+    scale: f32,
+    orientation: f32,
+    contrast: f32,
+    min: f32,
+    max: f32,
+    speed: f32,
+}
+
+impl From<&ProgramNode> for GpuNutrientNode {
+    fn from(node: &ProgramNode) -> Self {
+        let leaf = |op: u32, pattern: u32, params: &NutrientParams| Self {
+            op,
+            pattern,
+            is_reversed: params.is_reversed as u32,
+            _padding: 0,
+            center: [params.center.0, params.center.1],
+            scale: params.scale,
+            orientation: params.orientation,
+            contrast: params.contrast,
+            min: params.min,
+            max: params.max,
+            speed: params.speed,
+        };
+        match *node {
+            ProgramNode::Pattern(pattern, ref params) => leaf(0, pattern.as_u32(), params),
+            ProgramNode::Image(_, ref params) => leaf(IMAGE_OP, 0, params),
+            ProgramNode::Operator(operator) => Self {
+                op: operator as u32,
+                ..Self::zeroed()
+            },
+        }
+    }
+}
+
+/// Matches `NutrientGraph` in reaction_diffusion.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub(crate) struct GpuNutrientGraph {
+    node_count: u32,
+    _padding: [u32; 3],
+    nodes: [GpuNutrientNode; MAX_NODES],
+}
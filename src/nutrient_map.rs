@@ -0,0 +1,151 @@
+//! Nutrient fields read from images, so that patterns grow over photographs
+//! and logos.
+
+use crate::nutrient_presets::NutrientParams;
+use image::DynamicImage;
+use std::path::Path;
+
+/// The part of an image's colour read as nutrient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Channel {
+    /// Brightness.
+    #[default]
+    Luma,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+/// One channel of an image, between 0 and 1, stretched over the grid as a
+/// nutrient pattern by [`crate::NutrientGraph::image`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NutrientMap {
+    width: usize,
+    height: usize,
+    /// Row by row from the top, as in the image.
+    values: Vec<f32>,
+}
+
+impl NutrientMap {
+    pub fn open(path: impl AsRef<Path>, channel: Channel) -> image::ImageResult<Self> {
+        Ok(Self::from_image(&image::open(path)?, channel))
+    }
+
+    pub fn from_image(image: &DynamicImage, channel: Channel) -> Self {
+        let values = match channel {
+            Channel::Luma => image.to_luma32f().into_raw(),
+            _ => {
+                let index = match channel {
+                    Channel::Red => 0,
+                    Channel::Green => 1,
+                    Channel::Blue => 2,
+                    _ => 3,
+                };
+                image
+                    .to_rgba32f()
+                    .pixels()
+                    .map(|pixel| pixel.0[index])
+                    .collect()
+            }
+        };
+        Self {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            values,
+        }
+    }
+
+    /// A map from values between 0 and 1, row by row from the top, or `None`
+    /// if there aren't `width` times `height` of them.
+    pub fn from_values(width: usize, height: usize, values: Vec<f32>) -> Option<Self> {
+        (width.checked_mul(height) == Some(values.len())).then_some(Self {
+            width,
+            height,
+            values,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The map's values, row by row from the top.
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> f32 {
+        self.values[y * self.width + x]
+    }
+
+    /// The map's value over cell `(x, y)` when it is stretched over a `width`
+    /// by `height` grid. Shrinking averages the pixels under the cell and
+    /// enlarging interpolates between the nearest ones. An empty map is 1
+    /// everywhere.
+    pub fn sample(&self, x: usize, y: usize, width: usize, height: usize) -> f32 {
+        if self.values.is_empty() {
+            return 1.0;
+        }
+        let scale_x = self.width as f32 / width as f32;
+        let scale_y = self.height as f32 / height as f32;
+        // Image rows start at the top, grid rows at the bottom
+        let y = height - 1 - y;
+
+        if scale_x > 1.0 || scale_y > 1.0 {
+            let span = |cell: usize, scale: f32, size: usize| {
+                let start = ((cell as f32 * scale) as usize).min(size - 1);
+                let end = (((cell + 1) as f32 * scale).ceil() as usize).clamp(start + 1, size);
+                start..end
+            };
+            let columns = span(x, scale_x, self.width);
+            let rows = span(y, scale_y, self.height);
+            let count = columns.len() * rows.len();
+            let sum: f32 = rows
+                .flat_map(|row| columns.clone().map(move |column| (column, row)))
+                .map(|(column, row)| self.pixel(column, row))
+                .sum();
+            return sum / count as f32;
+        }
+
+        let position = |cell: usize, scale: f32, size: usize| {
+            let position = ((cell as f32 + 0.5) * scale - 0.5).clamp(0.0, (size - 1) as f32);
+            let start = position.floor() as usize;
+            (start, (start + 1).min(size - 1), position.fract())
+        };
+        let (x0, x1, tx) = position(x, scale_x, self.width);
+        let (y0, y1, ty) = position(y, scale_y, self.height);
+        let top = self.pixel(x0, y0) + (self.pixel(x1, y0) - self.pixel(x0, y0)) * tx;
+        let bottom = self.pixel(x0, y1) + (self.pixel(x1, y1) - self.pixel(x0, y1)) * tx;
+        top + (bottom - top) * ty
+    }
+
+    /// The map stretched over a `width` by `height` grid, row by row from the
+    /// bottom, as the GPU reads it.
+    pub fn resampled(&self, width: usize, height: usize) -> Vec<f32> {
+        (0..width * height)
+            .map(|i| self.sample(i % width, i / width, width, height))
+            .collect()
+    }
+
+    /// The feed rate multiplier at cell `(x, y)` of a `width` by `height`
+    /// grid, where the map repeats beyond the grid when scaled down or moved.
+    /// Mirrors `image_factor` in reaction_diffusion.wgsl.
+    pub fn factor(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        params: &NutrientParams,
+    ) -> f32 {
+        let ((px, py), _) = params.transform(x, y, width, height);
+        let cell_x = (px.floor() as i32).rem_euclid(width as i32) as usize;
+        let cell_y = (py.floor() as i32).rem_euclid(height as i32) as usize;
+        params.apply_range(self.sample(cell_x, cell_y, width, height))
+    }
+}
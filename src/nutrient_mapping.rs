@@ -0,0 +1,150 @@
+//! Which rates the nutrient field scales, and how strongly.
+
+use crate::seeders::normalize_name;
+use std::fmt;
+
+/// How strongly the nutrient factor scales each rate. A strength of 1 scales
+/// the rate by the factor, 0 leaves it alone and anything between mixes the
+/// two, so a mapping can vary the feed rate fully while nudging the kill rate
+/// just enough to turn spots into stripes.
+///
+/// Each rate is scaled by `1 + strength * (factor - 1)`, which is never taken
+/// below 0. Strengths large enough to push the diffusion rates past about 1.25
+/// make the simulation unstable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NutrientMapping {
+    pub feed: f32,
+    pub kill: f32,
+    pub delta_u: f32,
+    pub delta_v: f32,
+}
+
+impl NutrientMapping {
+    /// The nutrient factor scales the feed rate alone.
+    pub const FEED: Self = Self {
+        feed: 1.0,
+        kill: 0.0,
+        delta_u: 0.0,
+        delta_v: 0.0,
+    };
+
+    /// The nutrient factor nudges the kill rate, by 5% over the usual range
+    /// of 0.5 to 1, which is enough to move between spots and mazes.
+    pub const KILL: Self = Self {
+        feed: 0.0,
+        kill: 0.1,
+        delta_u: 0.0,
+        delta_v: 0.0,
+    };
+
+    pub const FEED_AND_KILL: Self = Self {
+        feed: 1.0,
+        kill: 0.1,
+        delta_u: 0.0,
+        delta_v: 0.0,
+    };
+
+    /// The nutrient factor slows diffusion, making the pattern finer where
+    /// it is low.
+    pub const DIFFUSION: Self = Self {
+        feed: 0.0,
+        kill: 0.0,
+        delta_u: 0.5,
+        delta_v: 0.5,
+    };
+
+    /// The named mappings, in the order the app cycles through them.
+    pub fn named() -> [(&'static str, Self); 4] {
+        [
+            ("Feed", Self::FEED),
+            ("Kill", Self::KILL),
+            ("Feed and Kill", Self::FEED_AND_KILL),
+            ("Diffusion", Self::DIFFUSION),
+        ]
+    }
+
+    /// The name of this mapping, if it is one of [`Self::named`].
+    pub fn name(&self) -> Option<&'static str> {
+        Self::named()
+            .into_iter()
+            .find(|(_, mapping)| mapping == self)
+            .map(|(name, _)| name)
+    }
+
+    /// Parses a named mapping, ignoring case, spaces and punctuation, or
+    /// strengths such as `feed=1,kill=0.1`. Rates left out aren't scaled.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Some((_, mapping)) = Self::named()
+            .into_iter()
+            .find(|(name, _)| normalize_name(name) == normalize_name(text))
+        {
+            return Ok(mapping);
+        }
+
+        let mut mapping = Self {
+            feed: 0.0,
+            kill: 0.0,
+            delta_u: 0.0,
+            delta_v: 0.0,
+        };
+        for part in text.split(',') {
+            let (target, strength) = part
+                .split_once('=')
+                .ok_or_else(|| format!("`{}` is not a mapping name or target=strength", part))?;
+            let strength: f32 = match strength.trim().parse() {
+                Ok(strength) if f32::is_finite(strength) => strength,
+                _ => return Err(format!("`{}` is not a number", strength.trim())),
+            };
+            match normalize_name(target).as_str() {
+                "feed" => mapping.feed = strength,
+                "kill" => mapping.kill = strength,
+                "du" | "deltau" => mapping.delta_u = strength,
+                "dv" | "deltav" => mapping.delta_v = strength,
+                _ => {
+                    return Err(format!(
+                        "unknown target `{}`, expected feed, kill, du or dv",
+                        target.trim()
+                    ));
+                }
+            }
+        }
+        Ok(mapping)
+    }
+
+    /// Whether every strength is a finite number.
+    pub fn is_finite(&self) -> bool {
+        self.as_array().iter().all(|strength| strength.is_finite())
+    }
+
+    /// The strengths for feed, kill, delta U and delta V in that order, as the
+    /// shader takes them.
+    pub fn as_array(&self) -> [f32; 4] {
+        [self.feed, self.kill, self.delta_u, self.delta_v]
+    }
+
+    /// What to multiply the feed, kill, delta U and delta V rates by where the
+    /// nutrient factor is `factor`. Mirrors `main` in reaction_diffusion.wgsl.
+    pub fn scales(&self, factor: f32) -> [f32; 4] {
+        self.as_array()
+            .map(|strength| (1.0 + strength * (factor - 1.0)).max(0.0))
+    }
+}
+
+impl Default for NutrientMapping {
+    fn default() -> Self {
+        Self::FEED
+    }
+}
+
+impl fmt::Display for NutrientMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(
+                f,
+                "feed={}, kill={}, du={}, dv={}",
+                self.feed, self.kill, self.delta_u, self.delta_v
+            ),
+        }
+    }
+}
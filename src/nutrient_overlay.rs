@@ -0,0 +1,118 @@
+use gray_scott_reaction_diffusion::NutrientField;
+use gray_scott_reaction_diffusion::renderer::OverlayRect;
+
+/// Spacing of the contour lines, in nutrient factor.
+const CONTOUR_SPACING: f32 = 0.1;
+
+/// Opacity of the field when blended over the simulation.
+const BLENDED_ALPHA: u8 = 140;
+
+/// Colours from the lowest nutrient factor to the highest.
+const RAMP: [[u8; 3]; 3] = [[68, 1, 84], [33, 145, 140], [253, 231, 37]];
+
+/// How the nutrient factor field is shown alongside the simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NutrientView {
+    #[default]
+    Off,
+    /// Tints the simulation with the field.
+    Blended,
+    /// Draws lines of equal nutrient factor over the simulation.
+    Contours,
+    /// Squeezes the simulation into the left half of the window and shows the
+    /// field in the right half.
+    SideBySide,
+}
+
+impl NutrientView {
+    const ALL: [Self; 4] = [Self::Off, Self::Blended, Self::Contours, Self::SideBySide];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Blended => "Blended",
+            Self::Contours => "Contours",
+            Self::SideBySide => "Side by Side",
+        }
+    }
+
+    /// The view after this one, or before it if `reverse`.
+    pub fn cycled(&self, reverse: bool) -> Self {
+        let len = Self::ALL.len();
+        let index = Self::ALL.iter().position(|view| view == self).unwrap_or(0);
+        Self::ALL[if reverse {
+            (index + len - 1) % len
+        } else {
+            (index + 1) % len
+        }]
+    }
+
+    /// Where the simulation is drawn in this view.
+    pub fn simulation_rect(&self) -> OverlayRect {
+        match self {
+            Self::SideBySide => OverlayRect {
+                width: 0.5,
+                ..OverlayRect::FULL
+            },
+            _ => OverlayRect::FULL,
+        }
+    }
+
+    /// Where the field is drawn in this view.
+    pub fn field_rect(&self) -> OverlayRect {
+        match self {
+            Self::SideBySide => OverlayRect {
+                x: 0.5,
+                width: 0.5,
+                ..OverlayRect::FULL
+            },
+            _ => OverlayRect::FULL,
+        }
+    }
+}
+
+/// Draws `field` for `view`. Factors are coloured from 0 up to the larger of
+/// 1 and the highest factor, so that a field of all ones always looks the
+/// same. Returns RGBA pixels with rows running bottom to top.
+pub fn draw(field: &NutrientField, view: NutrientView) -> Vec<u8> {
+    let (factors, width, height) = (&field.factors, field.width, field.height);
+    let scale = field.range().1.max(1.0);
+    let mut pixels = vec![0; width * height * 4];
+
+    for (index, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+        let factor = factors[index];
+        let alpha = match view {
+            NutrientView::Off => continue,
+            NutrientView::Blended => BLENDED_ALPHA,
+            NutrientView::SideBySide => 255,
+            NutrientView::Contours => {
+                // A cell is on a contour where its band differs from that of
+                // the cell to its right or above it
+                let (x, y) = (index % width, index / width);
+                let band = (factor / CONTOUR_SPACING).floor();
+                let crosses = |neighbour: f32| (neighbour / CONTOUR_SPACING).floor() != band;
+                let on_contour = (x + 1 < width && crosses(factors[index + 1]))
+                    || (y + 1 < height && crosses(factors[index + width]));
+                if !on_contour {
+                    continue;
+                }
+                255
+            }
+        };
+        let [r, g, b] = ramp((factor / scale).clamp(0.0, 1.0));
+        pixel.copy_from_slice(&[r, g, b, alpha]);
+    }
+    pixels
+}
+
+/// The colour `t` of the way along the ramp.
+fn ramp(t: f32) -> [u8; 3] {
+    let position = t * (RAMP.len() - 1) as f32;
+    let start = (position.floor() as usize).min(RAMP.len() - 2);
+    let fraction = position - start as f32;
+    [0, 1, 2].map(|channel| {
+        let low = RAMP[start][channel] as f32;
+        let high = RAMP[start + 1][channel] as f32;
+        (low + (high - low) * fraction).round() as u8
+    })
+}
@@ -1,3 +1,11 @@
+use crate::seeders::normalize_name;
+use std::f32::consts::TAU;
+
+/// Simulated time an animated pattern takes to go through one cycle at speed
+/// 1, such as a full turn or moving along by one stripe. Must match
+/// `NUTRIENT_CYCLE_TIME` in reaction_diffusion.wgsl.
+pub const CYCLE_TIME: f32 = 2000.0;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum NutrientPattern {
     Uniform = 0,
@@ -9,6 +17,10 @@ pub enum NutrientPattern {
     Noise = 6,
     WaveFunction = 7,
     CosineGrid = 8,
+    RotatingRadialGradient = 9,
+    MovingStripes = 10,
+    ScrollingNoise = 11,
+    PulsingRings = 12,
 }
 
 impl NutrientPattern {
@@ -16,6 +28,19 @@ impl NutrientPattern {
         self as u32
     }
 
+    pub fn from_u32(value: u32) -> Option<Self> {
+        Self::all().into_iter().find(|p| p.as_u32() == value)
+    }
+
+    /// Finds a pattern by name, ignoring case, spaces and punctuation so that
+    /// `radial-gradient` matches "Radial Gradient".
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = normalize_name(name);
+        Self::all()
+            .into_iter()
+            .find(|p| normalize_name(p.name()) == name)
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             NutrientPattern::Uniform => "Uniform",
@@ -27,9 +52,133 @@ impl NutrientPattern {
             NutrientPattern::Noise => "Noise",
             NutrientPattern::WaveFunction => "Wave Function",
             NutrientPattern::CosineGrid => "Cosine Grid",
+            NutrientPattern::RotatingRadialGradient => "Rotating Radial Gradient",
+            NutrientPattern::MovingStripes => "Moving Stripes",
+            NutrientPattern::ScrollingNoise => "Scrolling Noise",
+            NutrientPattern::PulsingRings => "Pulsing Rings",
+        }
+    }
+
+    /// Whether the pattern changes over time.
+    pub fn is_animated(self) -> bool {
+        matches!(
+            self,
+            NutrientPattern::RotatingRadialGradient
+                | NutrientPattern::MovingStripes
+                | NutrientPattern::ScrollingNoise
+                | NutrientPattern::PulsingRings
+        )
+    }
+
+    /// The parameters that reproduce the pattern as it was before patterns
+    /// had any. Gradients and the cosine grid span 0 to 1, the rest 0.5 to 1.
+    /// Animated patterns follow the static ones they resemble.
+    pub fn default_params(self) -> NutrientParams {
+        match self {
+            NutrientPattern::DiagonalGradient
+            | NutrientPattern::RadialGradient
+            | NutrientPattern::CosineGrid
+            | NutrientPattern::RotatingRadialGradient => NutrientParams {
+                min: 0.0,
+                max: 1.0,
+                ..NutrientParams::default()
+            },
+            _ => NutrientParams::default(),
         }
     }
 
+    /// The feed rate multiplier at cell `(x, y)` of a `width` by `height`
+    /// grid at simulated `time`. Mirrors `pattern_factor` in
+    /// reaction_diffusion.wgsl.
+    pub fn factor(
+        self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        params: &NutrientParams,
+        time: f32,
+    ) -> f32 {
+        let (w, h) = (width as f32, height as f32);
+        let ((px, py), (dx, dy)) = params.transform(x, y, width, height);
+        let (nx, ny) = (px / w, py / h);
+        let (ox, oy) = (dx / w, dy / h);
+        // Cycles completed by an animated pattern, and how far it is through
+        // the current one, which keeps precision on long runs
+        let cycles = time * params.speed / CYCLE_TIME;
+        let phase = cycles.rem_euclid(1.0);
+
+        // Each pattern is between 0 and 1 before contrast and range apply
+        let raw = match self {
+            NutrientPattern::Uniform => 1.0,
+            NutrientPattern::Checkerboard => {
+                let block_size = 200.0;
+                let bx = (px / block_size).floor() as i32;
+                let by = (py / block_size).floor() as i32;
+                if (bx + by) & 1 == 0 { 1.0 } else { 0.0 }
+            }
+            NutrientPattern::DiagonalGradient => (nx + ny) / 2.0,
+            NutrientPattern::RadialGradient => 1.0 - (ox * ox + oy * oy).sqrt(),
+            NutrientPattern::VerticalStripes => {
+                if (nx / 0.1).rem_euclid(2.0) < 1.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            NutrientPattern::HorizontalStripes => {
+                if (ny / 0.1).rem_euclid(2.0) < 1.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            NutrientPattern::Noise => fbm_noise(px, py),
+            NutrientPattern::ScrollingNoise => {
+                // Drifts diagonally by a tenth of the grid's width per cycle,
+                // never repeating
+                let offset = cycles * w * 0.1;
+                fbm_noise(px - offset, py - offset * 0.5)
+            }
+            NutrientPattern::WaveFunction => {
+                let x_norm = ox * 4.0;
+                let y_norm = oy * 4.0;
+                let wave = x_norm * (-(x_norm * x_norm + y_norm * y_norm)).exp();
+                (wave + 0.43) / 0.86
+            }
+            NutrientPattern::CosineGrid => {
+                let x_scaled = nx * 18.85;
+                let y_scaled = ny * 12.566;
+                let pattern1 = (x_scaled + (y_scaled * 0.5).cos()).cos();
+                let pattern2 = (y_scaled + (x_scaled * 0.3).sin()).cos();
+                let interference = pattern1 * pattern2;
+                let raw = -(interference * interference) * (x_scaled * 0.5).cos();
+                0.5 + raw.tanh() * 0.5
+            }
+            NutrientPattern::RotatingRadialGradient => {
+                // The radial gradient, its peak circling the centre once a cycle
+                let (sin, cos) = (phase * TAU).sin_cos();
+                let (ox, oy) = (ox - 0.25 * cos, oy - 0.25 * sin);
+                1.0 - (ox * ox + oy * oy).sqrt()
+            }
+            NutrientPattern::MovingStripes => {
+                // Vertical stripes moving right by a pair of stripes a cycle
+                if (nx / 0.1 - phase * 2.0).rem_euclid(2.0) < 1.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            NutrientPattern::PulsingRings => {
+                // Rings a tenth of the grid apart moving outwards by one a cycle
+                let distance = (ox * ox + oy * oy).sqrt();
+                0.5 + 0.5 * ((distance / 0.1 - phase) * TAU).cos()
+            }
+        };
+
+        params.apply_range(raw)
+    }
+
     pub fn all() -> Vec<NutrientPattern> {
         use NutrientPattern::*;
         vec![
@@ -42,10 +191,125 @@ impl NutrientPattern {
             Noise,
             WaveFunction,
             CosineGrid,
+            RotatingRadialGradient,
+            MovingStripes,
+            ScrollingNoise,
+            PulsingRings,
         ]
     }
 }
 
+/// A PCG hash of `n` between 0 and 1. Integer arithmetic gives the same bits
+/// on every GPU, unlike the usual `fract(sin(n) * 43758.5453)`, whose `sin` of
+/// large arguments varies between drivers. Mirrors `hash` in
+/// reaction_diffusion.wgsl.
+fn hash(n: u32) -> f32 {
+    let state = n.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    // The top 24 bits fit in an f32 exactly
+    (((word >> 22) ^ word) >> 8) as f32 / 16777216.0
+}
+
+/// Fractal noise with some periodic variation, between 0 and 1.
+fn fbm_noise(px: f32, py: f32) -> f32 {
+    let value_noise = |x: u32, y: u32, seed: u32| {
+        hash(
+            x.wrapping_mul(73856093)
+                .wrapping_add(y.wrapping_mul(19349663))
+                .wrapping_add(seed),
+        )
+    };
+
+    let mut fbm = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+    for octave in 0..4 {
+        let scaled_x = (px * frequency).floor() as i32 as u32;
+        let scaled_y = (py * frequency).floor() as i32 as u32;
+        fbm += value_noise(scaled_x, scaled_y, octave) * amplitude;
+        frequency *= 2.0;
+        amplitude *= 0.5;
+    }
+    let periodic = (px * 0.02).sin() * (py * 0.02).cos() * 0.2;
+    let value = fbm + periodic;
+    (value * value).clamp(0.0, 1.0)
+}
+
+/// How a nutrient pattern is laid out over the grid and which factors it
+/// spans. The defaults reproduce the patterns as they were before they had
+/// parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NutrientParams {
+    /// Size of the pattern's features, where 2 makes them twice as large.
+    pub scale: f32,
+    /// Anticlockwise rotation about `center`, in radians.
+    pub orientation: f32,
+    /// The point the pattern is scaled and rotated about, and the middle of
+    /// the radial gradient and wave function, as fractions of the grid.
+    pub center: (f32, f32),
+    /// Stretches the pattern about its midpoint before it is mapped between
+    /// `min` and `max`, where 0 flattens it.
+    pub contrast: f32,
+    /// Factor where the pattern is lowest.
+    pub min: f32,
+    /// Factor where the pattern is highest.
+    pub max: f32,
+    /// Swaps `min` and `max`, so the pattern is highest where it was lowest.
+    pub is_reversed: bool,
+    /// How fast an animated pattern moves, where 2 is twice as fast and a
+    /// negative speed runs it backwards. Static patterns ignore it.
+    pub speed: f32,
+}
+
+impl Default for NutrientParams {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            orientation: 0.0,
+            center: (0.5, 0.5),
+            contrast: 1.0,
+            min: 0.5,
+            max: 1.0,
+            is_reversed: false,
+            speed: 1.0,
+        }
+    }
+}
+
+impl NutrientParams {
+    /// Rotates and scales cell `(x, y)` of a `width` by `height` grid about
+    /// the centre, in cells so that rotations stay square on grids that
+    /// aren't. Returns the position the pattern is read at and its offset
+    /// from the centre. Mirrors `transform` in reaction_diffusion.wgsl.
+    pub(crate) fn transform(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> ((f32, f32), (f32, f32)) {
+        let (center_x, center_y) = (self.center.0 * width as f32, self.center.1 * height as f32);
+        let (sin, cos) = self.orientation.sin_cos();
+        let (rx, ry) = (x as f32 - center_x, y as f32 - center_y);
+        let dx = (rx * cos + ry * sin) / self.scale;
+        let dy = (ry * cos - rx * sin) / self.scale;
+        ((center_x + dx, center_y + dy), (dx, dy))
+    }
+
+    /// Maps a pattern value between 0 and 1 to a factor, applying contrast,
+    /// the range and reversal. Mirrors `apply_range` in reaction_diffusion.wgsl.
+    pub(crate) fn apply_range(&self, raw: f32) -> f32 {
+        let stretched = 0.5 + (raw - 0.5) * self.contrast;
+        let factor = (self.min + (self.max - self.min) * stretched)
+            .clamp(self.min.min(self.max), self.min.max(self.max));
+        if self.is_reversed {
+            self.min + self.max - factor
+        } else {
+            factor
+        }
+    }
+}
+
 pub fn uniform() -> NutrientPattern {
     NutrientPattern::Uniform
 }
@@ -0,0 +1,538 @@
+//! Maps what the model does across a grid of feed and kill rates by running a
+//! small simulation at each point on the CPU and classifying how it ends up.
+
+use crate::analysis::{PatternClass, Segmentation, SegmentationOptions};
+use crate::convergence::{ConvergenceEvent, ConvergenceMonitor};
+use crate::cpu_model::CpuReactionDiffusionSystem;
+use crate::field::Field;
+use crate::seeders::Seeder;
+use fontdue::Font;
+use image::{Rgba, RgbaImage};
+use rayon::prelude::*;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Smallest spread of V below which a surviving field counts as uniform.
+const UNIFORM_SPREAD: f32 = 0.05;
+
+/// The sampled region of the f-k plane and how each point is simulated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseDiagramConfig {
+    pub feed_range: (f32, f32),
+    pub kill_range: (f32, f32),
+    pub feed_samples: usize,
+    pub kill_samples: usize,
+    /// Width and height of each simulation, in cells.
+    pub grid_size: usize,
+    /// Steps each simulation runs for unless it settles sooner.
+    pub max_steps: u64,
+    /// Seed for the seeder, shared by every point so they start out the same.
+    pub seed: u64,
+    pub delta_u: f32,
+    pub delta_v: f32,
+}
+
+impl Default for PhaseDiagramConfig {
+    fn default() -> Self {
+        Self {
+            feed_range: (0.01, 0.09),
+            kill_range: (0.03, 0.07),
+            feed_samples: 32,
+            kill_samples: 32,
+            grid_size: 64,
+            max_steps: 10_000,
+            seed: 1,
+            delta_u: 1.0,
+            delta_v: 0.5,
+        }
+    }
+}
+
+impl PhaseDiagramConfig {
+    /// The feed rate of row `index`, lowest first.
+    pub fn feed_rate(&self, index: usize) -> f32 {
+        lerp_range(self.feed_range, index, self.feed_samples)
+    }
+
+    /// The kill rate of column `index`, lowest first.
+    pub fn kill_rate(&self, index: usize) -> f32 {
+        lerp_range(self.kill_range, index, self.kill_samples)
+    }
+}
+
+fn lerp_range((min, max): (f32, f32), index: usize, samples: usize) -> f32 {
+    if samples <= 1 {
+        return min;
+    }
+    min + (max - min) * index as f32 / (samples - 1) as f32
+}
+
+/// The kind of state a simulation ended up in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseOutcome {
+    DiedOut,
+    /// Alive, but with V the same everywhere.
+    Uniform,
+    Spots,
+    Stripes,
+    Labyrinth,
+}
+
+impl PhaseOutcome {
+    pub fn all() -> [PhaseOutcome; 5] {
+        use PhaseOutcome::*;
+        [DiedOut, Uniform, Spots, Stripes, Labyrinth]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PhaseOutcome::DiedOut => "Died Out",
+            PhaseOutcome::Uniform => "Uniform",
+            PhaseOutcome::Spots => "Spots",
+            PhaseOutcome::Stripes => "Stripes",
+            PhaseOutcome::Labyrinth => "Labyrinth",
+        }
+    }
+
+    /// The color the outcome is drawn in on the diagram.
+    pub fn color(&self) -> [u8; 3] {
+        match self {
+            PhaseOutcome::DiedOut => [35, 35, 40],
+            PhaseOutcome::Uniform => [110, 90, 150],
+            PhaseOutcome::Spots => [235, 130, 45],
+            PhaseOutcome::Stripes => [70, 175, 95],
+            PhaseOutcome::Labyrinth => [65, 130, 225],
+        }
+    }
+}
+
+/// Whether a simulation had stopped changing when it was classified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dynamics {
+    Steady,
+    /// Mean V repeats every `period` steps.
+    Oscillating {
+        period: u64,
+    },
+    /// Still changing after the maximum number of steps.
+    Evolving,
+}
+
+impl Dynamics {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dynamics::Steady => "Steady",
+            Dynamics::Oscillating { .. } => "Oscillating",
+            Dynamics::Evolving => "Evolving",
+        }
+    }
+}
+
+/// How the simulation at one point of the diagram turned out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseSample {
+    pub feed_rate: f32,
+    pub kill_rate: f32,
+    pub outcome: PhaseOutcome,
+    pub dynamics: Dynamics,
+    /// Connected regions of high V at the end.
+    pub component_count: usize,
+    /// Steps run before the simulation settled or ran out of time.
+    pub steps: u64,
+}
+
+/// A labelled point drawn on top of the diagram, such as a named preset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub name: String,
+    pub feed_rate: f32,
+    pub kill_rate: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseDiagram {
+    pub config: PhaseDiagramConfig,
+    /// One sample per point, row by row from the lowest feed rate, each row
+    /// running from the lowest kill rate.
+    pub samples: Vec<PhaseSample>,
+}
+
+impl PhaseDiagram {
+    /// Runs every point of the diagram in parallel, each from the state
+    /// `seeder` generates for the configured seed.
+    pub fn compute(config: PhaseDiagramConfig, seeder: &dyn Seeder) -> Self {
+        Self::compute_with_progress(config, seeder, |_, _| {})
+    }
+
+    /// Like [`Self::compute`], calling `progress` with the number of points
+    /// finished and the total after each one.
+    pub fn compute_with_progress(
+        config: PhaseDiagramConfig,
+        seeder: &dyn Seeder,
+        progress: impl Fn(usize, usize) + Sync,
+    ) -> Self {
+        let size = config.grid_size;
+        let initial = seeder.generate(size, size, config.seed);
+        let total = config.feed_samples * config.kill_samples;
+        let finished = AtomicUsize::new(0);
+
+        let samples = (0..total)
+            .into_par_iter()
+            .map(|index| {
+                let feed_rate = config.feed_rate(index / config.kill_samples);
+                let kill_rate = config.kill_rate(index % config.kill_samples);
+                let sample = simulate(&config, &initial, feed_rate, kill_rate);
+                progress(finished.fetch_add(1, Ordering::Relaxed) + 1, total);
+                sample
+            })
+            .collect();
+
+        Self { config, samples }
+    }
+
+    pub fn sample(&self, feed_index: usize, kill_index: usize) -> &PhaseSample {
+        &self.samples[feed_index * self.config.kill_samples + kill_index]
+    }
+
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "feed_rate,kill_rate,outcome,dynamics,period,components,steps"
+        )?;
+        for sample in &self.samples {
+            let period = match sample.dynamics {
+                Dynamics::Oscillating { period } => period.to_string(),
+                _ => String::new(),
+            };
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                sample.feed_rate,
+                sample.kill_rate,
+                sample.outcome.name(),
+                sample.dynamics.name(),
+                period,
+                sample.component_count,
+                sample.steps
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draws the diagram with kill rate across and feed rate up, axes, a legend
+    /// and `markers` at their rates. Oscillating points get a white dot.
+    pub fn render(&self, font: &Font, markers: &[Marker]) -> RgbaImage {
+        let config = &self.config;
+        let cell = (640 / config.feed_samples.max(config.kill_samples).max(1)).clamp(4, 32) as u32;
+        let plot_width = cell * config.kill_samples as u32;
+        let plot_height = cell * config.feed_samples as u32;
+        let (left, right, top, bottom) = (80, 190, 50, 60);
+        let title = format!(
+            "Gray-Scott phase diagram: {0}x{0} cells, up to {1} steps, Du {2}, Dv {3}",
+            config.grid_size, config.max_steps, config.delta_u, config.delta_v
+        );
+        let title_width = text_width(font, &title, 18.0).ceil() as u32;
+        let mut image = RgbaImage::from_pixel(
+            (left + plot_width + right).max(left + title_width + 10),
+            top + plot_height + bottom,
+            Rgba([250, 250, 250, 255]),
+        );
+        let text_color = [20, 20, 20];
+
+        for feed_index in 0..config.feed_samples {
+            for kill_index in 0..config.kill_samples {
+                let sample = self.sample(feed_index, kill_index);
+                let x0 = left + kill_index as u32 * cell;
+                // Highest feed rate at the top
+                let y0 = top + (config.feed_samples - 1 - feed_index) as u32 * cell;
+                fill_rect(&mut image, x0, y0, cell, cell, sample.outcome.color());
+                if matches!(sample.dynamics, Dynamics::Oscillating { .. }) {
+                    let center = (x0 as f32 + cell as f32 / 2.0, y0 as f32 + cell as f32 / 2.0);
+                    fill_circle(&mut image, center, cell as f32 / 5.0, [255, 255, 255]);
+                }
+            }
+        }
+
+        // Axes with five ticks each
+        let ticks = 5;
+        for i in 0..ticks {
+            let t = i as f32 / (ticks - 1) as f32;
+            let kill_rate = config.kill_range.0 + (config.kill_range.1 - config.kill_range.0) * t;
+            let x = left as f32 + (t * (plot_width - cell) as f32) + cell as f32 / 2.0;
+            fill_rect(&mut image, x as u32, top + plot_height, 1, 6, text_color);
+            let label = format!("{:.3}", kill_rate);
+            let label_width = text_width(font, &label, 14.0);
+            draw_text(
+                &mut image,
+                font,
+                &label,
+                x - label_width / 2.0,
+                (top + plot_height + 22) as f32,
+                14.0,
+                text_color,
+            );
+
+            let feed_rate = config.feed_range.0 + (config.feed_range.1 - config.feed_range.0) * t;
+            let y =
+                (top + plot_height) as f32 - (t * (plot_height - cell) as f32) - cell as f32 / 2.0;
+            fill_rect(&mut image, left - 6, y as u32, 6, 1, text_color);
+            let label = format!("{:.3}", feed_rate);
+            let label_width = text_width(font, &label, 14.0);
+            draw_text(
+                &mut image,
+                font,
+                &label,
+                left as f32 - 10.0 - label_width,
+                y + 5.0,
+                14.0,
+                text_color,
+            );
+        }
+        draw_text(
+            &mut image,
+            font,
+            "kill rate k",
+            left as f32 + plot_width as f32 / 2.0 - 30.0,
+            (top + plot_height + 48) as f32,
+            16.0,
+            text_color,
+        );
+        draw_text(
+            &mut image,
+            font,
+            "feed rate f",
+            8.0,
+            top as f32 - 10.0,
+            16.0,
+            text_color,
+        );
+        draw_text(
+            &mut image,
+            font,
+            &title,
+            left as f32,
+            24.0,
+            18.0,
+            text_color,
+        );
+
+        // Legend
+        let legend_x = left + plot_width + 20;
+        for (i, outcome) in PhaseOutcome::all().iter().enumerate() {
+            let y = top + i as u32 * 24;
+            fill_rect(&mut image, legend_x, y, 16, 16, outcome.color());
+            draw_text(
+                &mut image,
+                font,
+                outcome.name(),
+                (legend_x + 24) as f32,
+                (y + 13) as f32,
+                15.0,
+                text_color,
+            );
+        }
+        let y = top + PhaseOutcome::all().len() as u32 * 24;
+        fill_rect(&mut image, legend_x, y, 16, 16, [120, 120, 120]);
+        fill_circle(
+            &mut image,
+            ((legend_x + 8) as f32, (y + 8) as f32),
+            3.5,
+            [255, 255, 255],
+        );
+        draw_text(
+            &mut image,
+            font,
+            "Oscillating",
+            (legend_x + 24) as f32,
+            (y + 13) as f32,
+            15.0,
+            text_color,
+        );
+
+        // Markers at the same rates share a single label
+        let mut groups: Vec<(f32, f32, Vec<&str>)> = Vec::new();
+        for marker in markers {
+            match groups
+                .iter_mut()
+                .find(|(f, k, _)| *f == marker.feed_rate && *k == marker.kill_rate)
+            {
+                Some(group) => group.2.push(&marker.name),
+                None => groups.push((marker.feed_rate, marker.kill_rate, vec![&marker.name])),
+            }
+        }
+        let to_fraction = |value: f32, (min, max): (f32, f32)| (value - min) / (max - min);
+        for (feed_rate, kill_rate, names) in groups {
+            let fx = to_fraction(kill_rate, config.kill_range);
+            let fy = to_fraction(feed_rate, config.feed_range);
+            if !(0.0..=1.0).contains(&fx) || !(0.0..=1.0).contains(&fy) {
+                continue;
+            }
+            let x = left as f32 + cell as f32 / 2.0 + fx * (plot_width - cell) as f32;
+            let y =
+                (top + plot_height) as f32 - cell as f32 / 2.0 - fy * (plot_height - cell) as f32;
+            fill_circle(&mut image, (x, y), 6.0, [0, 0, 0]);
+            fill_circle(&mut image, (x, y), 4.0, [255, 255, 255]);
+            let label = names.join(" / ");
+            // Shadowed so it reads over any outcome color
+            draw_text(&mut image, font, &label, x + 9.0, y + 5.0, 14.0, [0, 0, 0]);
+            draw_text(
+                &mut image,
+                font,
+                &label,
+                x + 8.0,
+                y + 4.0,
+                14.0,
+                [255, 255, 255],
+            );
+        }
+
+        image
+    }
+}
+
+/// Runs one point of the diagram and classifies where it ends up.
+fn simulate(
+    config: &PhaseDiagramConfig,
+    initial: &[(f32, f32)],
+    feed_rate: f32,
+    kill_rate: f32,
+) -> PhaseSample {
+    let size = config.grid_size;
+    let mut system = CpuReactionDiffusionSystem::new(
+        size,
+        size,
+        feed_rate,
+        kill_rate,
+        config.delta_u,
+        config.delta_v,
+    );
+    system.set_all(initial);
+    let mut monitor = ConvergenceMonitor::new();
+    let event = system.run_until_converged(config.max_steps, &mut monitor);
+    let (outcome, component_count) = classify(&system.field(), event, &monitor);
+    let dynamics = match event {
+        Some(ConvergenceEvent::SteadyState { .. }) | Some(ConvergenceEvent::DiedOut { .. }) => {
+            Dynamics::Steady
+        }
+        Some(ConvergenceEvent::Oscillating { period, .. }) => Dynamics::Oscillating { period },
+        None => Dynamics::Evolving,
+    };
+
+    PhaseSample {
+        feed_rate,
+        kill_rate,
+        outcome,
+        dynamics,
+        component_count,
+        steps: system.step_count(),
+    }
+}
+
+/// Classifies the state a simulation reached after a run watched by
+/// `monitor` that ended with `event`, returning the outcome and the number
+/// of connected regions of high V.
+pub fn classify(
+    field: &Field,
+    event: Option<ConvergenceEvent>,
+    monitor: &ConvergenceMonitor,
+) -> (PhaseOutcome, usize) {
+    let (min_v, max_v) = field
+        .v_values()
+        .fold((f32::MAX, f32::MIN), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+    let died_out = matches!(event, Some(ConvergenceEvent::DiedOut { .. }))
+        || max_v < monitor.died_out_threshold;
+
+    // Thresholding halfway up the range of V copes with weak patterns that
+    // never reach the usual threshold
+    let segmentation = Segmentation::of_v(
+        field,
+        SegmentationOptions {
+            threshold: (min_v + max_v) / 2.0,
+            periodic: true,
+        },
+    );
+    let outcome = if died_out {
+        PhaseOutcome::DiedOut
+    } else if max_v - min_v < UNIFORM_SPREAD {
+        PhaseOutcome::Uniform
+    } else {
+        match segmentation.classify() {
+            PatternClass::Empty | PatternClass::Uniform => PhaseOutcome::Uniform,
+            PatternClass::Spots => PhaseOutcome::Spots,
+            PatternClass::Stripes => PhaseOutcome::Stripes,
+            PatternClass::Labyrinth => PhaseOutcome::Labyrinth,
+        }
+    };
+    (outcome, if died_out { 0 } else { segmentation.count() })
+}
+
+fn fill_rect(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: [u8; 3]) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, Rgba([color[0], color[1], color[2], 255]));
+        }
+    }
+}
+
+fn fill_circle(image: &mut RgbaImage, (cx, cy): (f32, f32), radius: f32, color: [u8; 3]) {
+    let (x0, x1) = ((cx - radius).floor() as i64, (cx + radius).ceil() as i64);
+    let (y0, y1) = ((cy - radius).floor() as i64, (cy + radius).ceil() as i64);
+    for py in y0.max(0)..=y1.min(image.height() as i64 - 1) {
+        for px in x0.max(0)..=x1.min(image.width() as i64 - 1) {
+            let (dx, dy) = (px as f32 + 0.5 - cx, py as f32 + 0.5 - cy);
+            if dx * dx + dy * dy <= radius * radius {
+                image.put_pixel(
+                    px as u32,
+                    py as u32,
+                    Rgba([color[0], color[1], color[2], 255]),
+                );
+            }
+        }
+    }
+}
+
+fn text_width(font: &Font, text: &str, size: f32) -> f32 {
+    text.chars()
+        .map(|c| font.metrics(c, size).advance_width)
+        .sum()
+}
+
+/// Blends `text` into the image with its baseline at `baseline_y`.
+fn draw_text(
+    image: &mut RgbaImage,
+    font: &Font,
+    text: &str,
+    x: f32,
+    baseline_y: f32,
+    size: f32,
+    color: [u8; 3],
+) {
+    let mut pen_x = x;
+    for c in text.chars() {
+        let (metrics, coverage) = font.rasterize(c, size);
+        let glyph_left = (pen_x + metrics.xmin as f32).round() as i64;
+        let glyph_top = (baseline_y - metrics.height as f32 - metrics.ymin as f32).round() as i64;
+        for gy in 0..metrics.height {
+            for gx in 0..metrics.width {
+                let alpha = coverage[gy * metrics.width + gx] as f32 / 255.0;
+                let (px, py) = (glyph_left + gx as i64, glyph_top + gy as i64);
+                if alpha <= 0.0
+                    || px < 0
+                    || py < 0
+                    || px >= image.width() as i64
+                    || py >= image.height() as i64
+                {
+                    continue;
+                }
+                let pixel = image.get_pixel_mut(px as u32, py as u32);
+                for (channel, &target) in pixel.0.iter_mut().zip(&color) {
+                    *channel =
+                        (*channel as f32 * (1.0 - alpha) + target as f32 * alpha).round() as u8;
+                }
+            }
+        }
+        pen_x += metrics.advance_width;
+    }
+}
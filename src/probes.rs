@@ -0,0 +1,338 @@
+use crate::field::UVPair;
+use bytemuck::{Pod, Zeroable};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use wgpu::util::DeviceExt;
+
+/// Default number of steps of samples kept on the GPU between reads.
+pub const DEFAULT_PROBE_CAPACITY: usize = 4096;
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct ProbeParams {
+    probe_count: u32,
+    row: u32,
+    _padding0: u32,
+    _padding1: u32,
+}
+
+/// A named cell sampled after every simulation step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Probe {
+    pub name: String,
+    pub x: usize,
+    pub y: usize,
+}
+
+/// The values a probe saw over a run of steps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeSeries {
+    pub probe: Probe,
+    /// The step each sample was taken after.
+    pub steps: Vec<u64>,
+    pub u: Vec<f32>,
+    pub v: Vec<f32>,
+}
+
+/// Writes probe series as CSV with one row per step and a `u` and `v` column
+/// per probe.
+pub fn write_csv(series: &[ProbeSeries], mut writer: impl Write) -> io::Result<()> {
+    write!(writer, "step")?;
+    for s in series {
+        let name = &s.probe.name;
+        write!(
+            writer,
+            ",{},{}",
+            csv_field(&format!("{}_u", name)),
+            csv_field(&format!("{}_v", name))
+        )?;
+    }
+    writeln!(writer)?;
+
+    let steps = series.first().map(|s| s.steps.as_slice()).unwrap_or(&[]);
+    for (i, step) in steps.iter().enumerate() {
+        write!(writer, "{}", step)?;
+        for s in series {
+            write!(writer, ",{},{}", s.u[i], s.v[i])?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Quotes `text` as RFC 4180 requires when it holds a comma, quote or line
+/// break, doubling any quotes inside it.
+fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Samples a set of probes on the GPU into a ring of rows, one row per step.
+pub(crate) struct ProbeSampler {
+    probes: Vec<Probe>,
+    capacity: usize,
+    /// The step recorded in each occupied row, oldest first.
+    sampled_steps: VecDeque<u64>,
+    next_row: usize,
+    params_buffer: wgpu::Buffer,
+    samples_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_groups: Option<[wgpu::BindGroup; 2]>,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl ProbeSampler {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Probe Bind Group Layout"),
+            entries: &[
+                storage_entry(0, true),
+                storage_entry(1, true),
+                storage_entry(2, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Probe Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Probe Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/probes.wgsl").into()),
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Probe Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Probe Params Buffer"),
+            size: std::mem::size_of::<ProbeParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            probes: Vec::new(),
+            capacity: DEFAULT_PROBE_CAPACITY,
+            sampled_steps: VecDeque::new(),
+            next_row: 0,
+            params_buffer,
+            samples_buffer: create_samples_buffer(device, 1),
+            bind_group_layout,
+            bind_groups: None,
+            pipeline,
+        }
+    }
+
+    pub(crate) fn probes(&self) -> &[Probe] {
+        &self.probes
+    }
+
+    pub(crate) fn set_capacity(
+        &mut self,
+        device: &wgpu::Device,
+        uvs_buffers: &[wgpu::Buffer; 2],
+        grid_width: usize,
+        capacity: usize,
+    ) {
+        self.capacity = capacity.max(1);
+        let probes = std::mem::take(&mut self.probes);
+        self.set_probes(device, uvs_buffers, grid_width, probes);
+    }
+
+    /// Replaces the probe set, discarding any samples not yet taken.
+    pub(crate) fn set_probes(
+        &mut self,
+        device: &wgpu::Device,
+        uvs_buffers: &[wgpu::Buffer; 2],
+        grid_width: usize,
+        probes: Vec<Probe>,
+    ) {
+        self.probes = probes;
+        self.sampled_steps.clear();
+        self.next_row = 0;
+
+        if self.probes.is_empty() {
+            self.bind_groups = None;
+            return;
+        }
+
+        let indices: Vec<u32> = self
+            .probes
+            .iter()
+            .map(|p| (p.y * grid_width + p.x) as u32)
+            .collect();
+        let indices_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Probe Indices Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        self.samples_buffer = create_samples_buffer(device, self.probes.len() * self.capacity);
+
+        self.bind_groups = Some([0, 1].map(|i| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&format!("Probe Bind Group {}", i)),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uvs_buffers[i].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: indices_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: self.samples_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: self.params_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        }));
+    }
+
+    /// Records the probed cells of `uvs_buffers[buffer_index]` as the state
+    /// after `step_count`.
+    pub(crate) fn sample(
+        &mut self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        buffer_index: usize,
+        step_count: u64,
+    ) {
+        let Some(bind_groups) = &self.bind_groups else {
+            return;
+        };
+
+        let params = ProbeParams {
+            probe_count: self.probes.len() as u32,
+            row: self.next_row as u32,
+            _padding0: 0,
+            _padding1: 0,
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Probe Pass"),
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &bind_groups[buffer_index], &[]);
+            compute_pass.dispatch_workgroups((self.probes.len() as u32).div_ceil(64), 1, 1);
+        }
+
+        self.next_row = (self.next_row + 1) % self.capacity;
+        if self.sampled_steps.len() == self.capacity {
+            self.sampled_steps.pop_front();
+        }
+        self.sampled_steps.push_back(step_count);
+    }
+
+    /// Reads back every sample taken since the last call and starts afresh.
+    pub(crate) fn take_series(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Vec<ProbeSeries> {
+        let probe_count = self.probes.len();
+        let row_count = self.sampled_steps.len();
+        if probe_count == 0 || row_count == 0 {
+            return Vec::new();
+        }
+
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Probe Staging Buffer"),
+            size: self.samples_buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Probe Readback Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(
+            &self.samples_buffer,
+            0,
+            &staging_buffer,
+            0,
+            staging_buffer.size(),
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = staging_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv().unwrap().unwrap();
+
+        let samples: Vec<UVPair> = bytemuck::cast_slice(&buffer_slice.get_mapped_range()).to_vec();
+        staging_buffer.unmap();
+
+        // The oldest row sits just after the newest once the ring has wrapped
+        let first_row = (self.next_row + self.capacity - row_count) % self.capacity;
+        let series = self
+            .probes
+            .iter()
+            .enumerate()
+            .map(|(i, probe)| {
+                let values: Vec<UVPair> = (0..row_count)
+                    .map(|r| samples[((first_row + r) % self.capacity) * probe_count + i])
+                    .collect();
+                ProbeSeries {
+                    probe: probe.clone(),
+                    steps: self.sampled_steps.iter().copied().collect(),
+                    u: values.iter().map(|uv| uv.u).collect(),
+                    v: values.iter().map(|uv| uv.v).collect(),
+                }
+            })
+            .collect();
+
+        self.sampled_steps.clear();
+        self.next_row = 0;
+        series
+    }
+}
+
+fn create_samples_buffer(device: &wgpu::Device, sample_count: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Probe Samples Buffer"),
+        size: (sample_count.max(1) * std::mem::size_of::<UVPair>()) as u64,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    })
+}
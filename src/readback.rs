@@ -0,0 +1,364 @@
+use crate::field::{Field, UVPair};
+use crate::nutrient_graph::NutrientField;
+use bytemuck::{Pod, Zeroable};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+/// How many readbacks may be in flight at once through the non-blocking API.
+pub const MAX_PENDING_READBACKS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SlotState {
+    Free,
+    Pending,
+    Mapped,
+    Failed(wgpu::BufferAsyncError),
+}
+
+struct StagingSlot {
+    buffer: wgpu::Buffer,
+    state: Arc<Mutex<SlotState>>,
+    width: usize,
+    height: usize,
+    step_count: u64,
+    generation: u64,
+}
+
+/// Identifies a readback started with `ReactionDiffusionSystem::request_readback`,
+/// or of a `T` other than the grid, such as with
+/// `ReactionDiffusionSystem::request_nutrient_field`.
+///
+/// Hand it back to `ReactionDiffusionSystem::poll_readback`, or the matching
+/// poll method, until the data is ready.
+#[derive(Debug)]
+#[must_use = "a pending readback holds a staging buffer until it is polled to completion"]
+pub struct ReadbackHandle<T = Field> {
+    slot: usize,
+    generation: u64,
+    data: PhantomData<fn() -> T>,
+}
+
+/// The outcome of polling a readback.
+#[derive(Debug)]
+pub enum Readback<T = Field> {
+    Ready(T),
+    Pending(ReadbackHandle<T>),
+    Failed(wgpu::BufferAsyncError),
+}
+
+/// A per-cell array that can be copied back from a GPU buffer.
+pub(crate) trait ReadbackData {
+    /// Bytes each cell takes in the buffer.
+    const CELL_SIZE: usize;
+
+    fn from_bytes(width: usize, height: usize, step_count: u64, bytes: &[u8]) -> Self;
+}
+
+impl ReadbackData for Field {
+    const CELL_SIZE: usize = std::mem::size_of::<UVPair>();
+
+    fn from_bytes(width: usize, height: usize, step_count: u64, bytes: &[u8]) -> Self {
+        Field::new(
+            width,
+            height,
+            step_count,
+            bytemuck::cast_slice(bytes).to_vec(),
+        )
+    }
+}
+
+impl ReadbackData for NutrientField {
+    const CELL_SIZE: usize = std::mem::size_of::<f32>();
+
+    fn from_bytes(width: usize, height: usize, step_count: u64, bytes: &[u8]) -> Self {
+        NutrientField {
+            width,
+            height,
+            step_count,
+            factors: bytemuck::cast_slice(bytes).to_vec(),
+        }
+    }
+}
+
+fn create_staging_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Staging Buffer"),
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// A reusable set of staging buffers for copying the grid, or another
+/// per-cell array, back to the CPU.
+pub(crate) struct ReadbackRing<T = Field> {
+    slots: Vec<StagingSlot>,
+    next_generation: u64,
+    data: PhantomData<fn() -> T>,
+}
+
+impl<T: ReadbackData> ReadbackRing<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            next_generation: 0,
+            data: PhantomData,
+        }
+    }
+
+    /// Whether `limit` readbacks are already in flight.
+    pub(crate) fn is_full(&self, limit: usize) -> bool {
+        self.pending_count() >= limit
+    }
+
+    fn pending_count(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| *slot.state.lock().unwrap() != SlotState::Free)
+            .count()
+    }
+
+    /// Copies `source` into a free staging buffer and starts mapping it.
+    ///
+    /// Returns `None` if `limit` readbacks are already in flight.
+    pub(crate) fn start(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        source: &wgpu::Buffer,
+        (width, height): (usize, usize),
+        step_count: u64,
+        limit: Option<usize>,
+    ) -> Option<ReadbackHandle<T>> {
+        if limit.is_some_and(|limit| self.is_full(limit)) {
+            return None;
+        }
+
+        let size = (width * height * T::CELL_SIZE) as u64;
+        let free_slot = self
+            .slots
+            .iter()
+            .position(|slot| *slot.state.lock().unwrap() == SlotState::Free);
+        let index = match free_slot {
+            Some(index) => index,
+            None => {
+                self.slots.push(StagingSlot {
+                    buffer: create_staging_buffer(device, size),
+                    state: Arc::new(Mutex::new(SlotState::Free)),
+                    width,
+                    height,
+                    step_count,
+                    generation: 0,
+                });
+                self.slots.len() - 1
+            }
+        };
+        // Buffers sized for an old grid can't be reused
+        if self.slots[index].buffer.size() != size {
+            self.slots[index].buffer = create_staging_buffer(device, size);
+        }
+
+        let generation = self.next_generation;
+        self.next_generation += 1;
+
+        let slot = &mut self.slots[index];
+        slot.width = width;
+        slot.height = height;
+        slot.step_count = step_count;
+        slot.generation = generation;
+        *slot.state.lock().unwrap() = SlotState::Pending;
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(source, 0, &slot.buffer, 0, size);
+        queue.submit(Some(encoder.finish()));
+
+        let state = Arc::clone(&slot.state);
+        slot.buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *state.lock().unwrap() = match result {
+                    Ok(()) => SlotState::Mapped,
+                    Err(e) => SlotState::Failed(e),
+                };
+            });
+
+        Some(ReadbackHandle {
+            slot: index,
+            generation,
+            data: PhantomData,
+        })
+    }
+
+    /// Takes the data for `handle` if its buffer has finished mapping. The
+    /// device must have been polled for mapping to make progress.
+    pub(crate) fn take(&mut self, handle: ReadbackHandle<T>) -> Readback<T> {
+        let Some(slot) = self
+            .slots
+            .get(handle.slot)
+            .filter(|slot| slot.generation == handle.generation)
+        else {
+            panic!("Readback handle does not belong to this simulation");
+        };
+
+        let state = slot.state.lock().unwrap().clone();
+        match state {
+            SlotState::Pending => Readback::Pending(handle),
+            SlotState::Failed(e) => {
+                *slot.state.lock().unwrap() = SlotState::Free;
+                Readback::Failed(e)
+            }
+            SlotState::Mapped => {
+                let data = T::from_bytes(
+                    slot.width,
+                    slot.height,
+                    slot.step_count,
+                    &slot.buffer.slice(..).get_mapped_range(),
+                );
+                slot.buffer.unmap();
+                *slot.state.lock().unwrap() = SlotState::Free;
+                Readback::Ready(data)
+            }
+            SlotState::Free => unreachable!("A handle is only issued for a busy slot"),
+        }
+    }
+}
+
+/// Number of workgroups the GPU reductions are split across. Each one leaves a
+/// partial result that is combined on the CPU.
+pub(crate) const REDUCTION_WORKGROUP_COUNT: usize = 64;
+/// Matches `@workgroup_size` in the reduction shaders.
+pub(crate) const REDUCTION_WORKGROUP_SIZE: usize = 256;
+
+/// Mirrors the params uniform of the reduction shaders.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub(crate) struct ReductionParams {
+    cell_count: u32,
+    thread_count: u32,
+    _padding0: u32,
+    _padding1: u32,
+}
+
+impl ReductionParams {
+    pub(crate) fn new(cell_count: usize) -> Self {
+        Self {
+            cell_count: cell_count as u32,
+            thread_count: (REDUCTION_WORKGROUP_COUNT * REDUCTION_WORKGROUP_SIZE) as u32,
+            _padding0: 0,
+            _padding1: 0,
+        }
+    }
+}
+
+/// How many workgroups of a reduction see any of `cell_count` cells. Partials
+/// past these are left untouched.
+pub(crate) fn active_workgroups(cell_count: usize) -> usize {
+    cell_count
+        .div_ceil(REDUCTION_WORKGROUP_SIZE)
+        .min(REDUCTION_WORKGROUP_COUNT)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StagingState {
+    Idle,
+    /// Copied into by a command buffer that has not been submitted yet.
+    Encoded,
+    Mapping,
+    Mapped,
+    Failed(wgpu::BufferAsyncError),
+}
+
+/// A staging buffer that a GPU reduction copies its results into, mapped
+/// without stalling the simulation. Holds one reduction at a time.
+pub(crate) struct StagingBuffer {
+    buffer: wgpu::Buffer,
+    state: Arc<Mutex<StagingState>>,
+    /// The step whose results are being staged.
+    step_count: u64,
+}
+
+impl StagingBuffer {
+    pub(crate) fn new(device: &wgpu::Device, label: &str, size: u64) -> Self {
+        Self {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            state: Arc::new(Mutex::new(StagingState::Idle)),
+            step_count: 0,
+        }
+    }
+
+    /// The buffer to copy results into.
+    pub(crate) fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    /// Whether a reduction can be encoded now.
+    pub(crate) fn is_idle(&self) -> bool {
+        *self.state.lock().unwrap() == StagingState::Idle
+    }
+
+    /// Whether a reduction has been submitted and not yet collected, whether
+    /// or not it has finished mapping.
+    pub(crate) fn is_submitted(&self) -> bool {
+        !matches!(
+            *self.state.lock().unwrap(),
+            StagingState::Idle | StagingState::Encoded
+        )
+    }
+
+    /// Records that copies of the results after `step_count` have been
+    /// encoded into the buffer.
+    pub(crate) fn mark_encoded(&mut self, step_count: u64) {
+        self.step_count = step_count;
+        *self.state.lock().unwrap() = StagingState::Encoded;
+    }
+
+    /// Starts mapping the results once the copies have been submitted.
+    pub(crate) fn start_mapping(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        if *state != StagingState::Encoded {
+            return;
+        }
+        *state = StagingState::Mapping;
+        drop(state);
+
+        let state = Arc::clone(&self.state);
+        self.buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *state.lock().unwrap() = match result {
+                    Ok(()) => StagingState::Mapped,
+                    Err(e) => StagingState::Failed(e),
+                };
+            });
+    }
+
+    /// Hands the mapped bytes and their step to `read` if the results have
+    /// arrived, freeing the buffer. The device must have been polled for
+    /// mapping to make progress.
+    pub(crate) fn collect<R>(
+        &mut self,
+        read: impl FnOnce(&[u8], u64) -> R,
+    ) -> Result<Option<R>, wgpu::BufferAsyncError> {
+        let state = self.state.lock().unwrap().clone();
+        match state {
+            StagingState::Mapped => {
+                let result = read(&self.buffer.slice(..).get_mapped_range(), self.step_count);
+                self.buffer.unmap();
+                *self.state.lock().unwrap() = StagingState::Idle;
+                Ok(Some(result))
+            }
+            StagingState::Failed(e) => {
+                *self.state.lock().unwrap() = StagingState::Idle;
+                Err(e)
+            }
+            _ => Ok(None),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::field::UVPair;
 use crate::lut_manager::LutData;
 use bytemuck::{Pod, Zeroable};
 use fontdue::Font;
@@ -10,6 +11,49 @@ struct Uniforms {
     window_aspect_ratio: f32,
     simulation_aspect_ratio: f32,
     is_lut_reversed: u32,
+    upscale_filter: u32,
+    simulation_rect: OverlayRect,
+}
+
+/// How the simulation texture is stretched to fill the window when the grid
+/// is smaller or larger than the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpscaleFilter {
+    /// Shows each cell as a hard-edged block.
+    #[default]
+    Nearest = 0,
+    /// Blends between neighbouring cells, wrapping at the edges like the
+    /// simulation does.
+    Bilinear = 1,
+}
+
+/// Where an overlay is drawn, as fractions of the window measured from the
+/// bottom left corner.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Pod, Zeroable)]
+pub struct OverlayRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl OverlayRect {
+    /// The whole window.
+    pub const FULL: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+}
+
+/// An image drawn over the simulation, such as a graph.
+struct Overlay {
+    name: String,
+    texture: wgpu::Texture,
+    rect_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
 }
 
 pub struct Renderer {
@@ -22,6 +66,8 @@ pub struct Renderer {
     uniforms: Uniforms,
     uniform_buffer: wgpu::Buffer,
     diffusion_texture: wgpu::Texture,
+    diffusion_sampler: wgpu::Sampler,
+    diffusion_bind_group_layout: wgpu::BindGroupLayout,
     diffusion_bind_group: wgpu::BindGroup,
     simulation_texture_width: u32,
     simulation_texture_height: u32,
@@ -31,6 +77,10 @@ pub struct Renderer {
     text_bind_group_layout: wgpu::BindGroupLayout,
     text_sampler: wgpu::Sampler,
     text_size: f32,
+    overlay_pipeline: wgpu::RenderPipeline,
+    overlay_bind_group_layout: wgpu::BindGroupLayout,
+    overlay_sampler: wgpu::Sampler,
+    overlays: Vec<Overlay>,
 }
 
 impl Renderer {
@@ -90,6 +140,8 @@ impl Renderer {
             window_aspect_ratio: size.width as f32 / size.height as f32,
             simulation_aspect_ratio: width as f32 / height as f32,
             is_lut_reversed: 0,
+            upscale_filter: UpscaleFilter::default() as u32,
+            simulation_rect: OverlayRect::FULL,
         };
 
         let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -105,20 +157,7 @@ impl Renderer {
             mapped_at_creation: false,
         });
 
-        let diffusion_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Diffusion Texture"),
-            size: wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rg32Float,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
+        let diffusion_texture = create_diffusion_texture(&device, width, height);
 
         let diffusion_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Diffusion Sampler"),
@@ -233,30 +272,14 @@ impl Renderer {
                 ],
             });
 
-        let diffusion_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Diffusion Bind Group"),
-            layout: &diffusion_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: uniform_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(
-                        &diffusion_texture.create_view(&wgpu::TextureViewDescriptor::default()),
-                    ),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::Sampler(&diffusion_sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: lut_buffer.as_entire_binding(),
-                },
-            ],
-        });
+        let diffusion_bind_group = create_diffusion_bind_group(
+            &device,
+            &diffusion_bind_group_layout,
+            &uniform_buffer,
+            &diffusion_texture,
+            &diffusion_sampler,
+            &lut_buffer,
+        );
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Pipeline Layout"),
@@ -275,7 +298,7 @@ impl Renderer {
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader,
-                entry_point: "vs_main",
+                entry_point: "vs_simulation",
                 buffers: &[],
             },
             fragment: Some(wgpu::FragmentState {
@@ -351,6 +374,96 @@ impl Renderer {
             multiview: None,
         });
 
+        let overlay_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Overlay Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/overlay.wgsl").into()),
+        });
+
+        let overlay_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Overlay Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let overlay_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Overlay Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let overlay_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Overlay Pipeline Layout"),
+                bind_group_layouts: &[&overlay_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let overlay_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Overlay Pipeline"),
+            layout: Some(&overlay_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &overlay_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &overlay_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
         Self {
             surface,
             device,
@@ -361,6 +474,8 @@ impl Renderer {
             uniforms,
             uniform_buffer,
             diffusion_texture,
+            diffusion_sampler,
+            diffusion_bind_group_layout,
             diffusion_bind_group,
             simulation_texture_width: width,
             simulation_texture_height: height,
@@ -370,6 +485,10 @@ impl Renderer {
             text_bind_group_layout,
             text_sampler,
             text_size,
+            overlay_pipeline,
+            overlay_bind_group_layout,
+            overlay_sampler,
+            overlays: Vec::new(),
         }
     }
 
@@ -394,8 +513,40 @@ impl Renderer {
         }
     }
 
-    pub fn update_texture(&mut self, uvs: &[(f32, f32)]) {
-        let data: Vec<f32> = uvs.iter().flat_map(|&(u, v)| [u, v]).collect();
+    /// Recreates the simulation texture after the grid has been resized.
+    pub fn set_simulation_size(&mut self, width: u32, height: u32) {
+        if width == self.simulation_texture_width && height == self.simulation_texture_height {
+            return;
+        }
+
+        self.diffusion_texture = create_diffusion_texture(&self.device, width, height);
+        self.diffusion_bind_group = create_diffusion_bind_group(
+            &self.device,
+            &self.diffusion_bind_group_layout,
+            &self.uniform_buffer,
+            &self.diffusion_texture,
+            &self.diffusion_sampler,
+            &self.lut_buffer,
+        );
+        self.simulation_texture_width = width;
+        self.simulation_texture_height = height;
+
+        self.uniforms.simulation_aspect_ratio = width as f32 / height as f32;
+        self.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniforms]),
+        );
+    }
+
+    pub fn simulation_size(&self) -> (u32, u32) {
+        (
+            self.simulation_texture_width,
+            self.simulation_texture_height,
+        )
+    }
+
+    pub fn update_texture(&mut self, uvs: &[UVPair]) {
         self.queue.write_texture(
             wgpu::ImageCopyTexture {
                 texture: &self.diffusion_texture,
@@ -403,7 +554,7 @@ impl Renderer {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            bytemuck::cast_slice(&data),
+            bytemuck::cast_slice(uvs),
             wgpu::ImageDataLayout {
                 offset: 0,
                 bytes_per_row: Some(self.simulation_texture_width * 8), // 2 f32s per pixel
@@ -628,6 +779,13 @@ impl Renderer {
             render_pass.set_bind_group(0, &self.diffusion_bind_group, &[]);
             render_pass.draw(0..4, 0..1);
 
+            // Render overlays beneath the help text
+            render_pass.set_pipeline(&self.overlay_pipeline);
+            for overlay in &self.overlays {
+                render_pass.set_bind_group(0, &overlay.bind_group, &[]);
+                render_pass.draw(0..4, 0..1);
+            }
+
             // Render text if available and text bind group exists
             if let Some(text_bind_group) = &self.text_bind_group {
                 render_pass.set_pipeline(&self.text_pipeline);
@@ -641,6 +799,126 @@ impl Renderer {
         Ok(())
     }
 
+    /// Shows an RGBA image over the simulation in `rect`, replacing any overlay
+    /// with the same name. Rows run bottom to top, like the simulation grid.
+    pub fn set_overlay(
+        &mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+        rect: OverlayRect,
+    ) {
+        assert_eq!(
+            rgba.len(),
+            (width * height * 4) as usize,
+            "Overlay data length must match its dimensions"
+        );
+
+        let index = match self.overlays.iter().position(|o| o.name == name) {
+            Some(index) => {
+                let size = self.overlays[index].texture.size();
+                if size.width != width || size.height != height {
+                    self.overlays[index] = self.create_overlay(name, width, height);
+                }
+                index
+            }
+            None => {
+                let overlay = self.create_overlay(name, width, height);
+                self.overlays.push(overlay);
+                self.overlays.len() - 1
+            }
+        };
+
+        let overlay = &self.overlays[index];
+        self.queue
+            .write_buffer(&overlay.rect_buffer, 0, bytemuck::cast_slice(&[rect]));
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &overlay.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * 4),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    pub fn clear_overlay(&mut self, name: &str) {
+        self.overlays.retain(|o| o.name != name);
+    }
+
+    pub fn has_overlay(&self, name: &str) -> bool {
+        self.overlays.iter().any(|o| o.name == name)
+    }
+
+    /// Draws the named overlay beneath all the others.
+    pub fn send_overlay_to_back(&mut self, name: &str) {
+        if let Some(index) = self.overlays.iter().position(|o| o.name == name) {
+            let overlay = self.overlays.remove(index);
+            self.overlays.insert(0, overlay);
+        }
+    }
+
+    fn create_overlay(&self, name: &str, width: u32, height: u32) -> Overlay {
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Overlay Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let rect_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Overlay Rect Buffer"),
+            size: std::mem::size_of::<OverlayRect>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Overlay Bind Group"),
+            layout: &self.overlay_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: rect_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.overlay_sampler),
+                },
+            ],
+        });
+        Overlay {
+            name: name.to_string(),
+            texture,
+            rect_buffer,
+            bind_group,
+        }
+    }
+
     pub fn set_lut_reversed(&mut self, reversed: bool) {
         self.uniforms.is_lut_reversed = if reversed { 1 } else { 0 };
         self.queue.write_buffer(
@@ -653,4 +931,86 @@ impl Renderer {
     pub fn is_lut_reversed(&self) -> bool {
         self.uniforms.is_lut_reversed == 1
     }
+
+    pub fn set_upscale_filter(&mut self, filter: UpscaleFilter) {
+        self.uniforms.upscale_filter = filter as u32;
+        self.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniforms]),
+        );
+    }
+
+    /// Draws the simulation in `rect` rather than over the whole window,
+    /// leaving the rest of the window for overlays.
+    pub fn set_simulation_rect(&mut self, rect: OverlayRect) {
+        self.uniforms.simulation_rect = rect;
+        self.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniforms]),
+        );
+    }
+
+    pub fn simulation_rect(&self) -> OverlayRect {
+        self.uniforms.simulation_rect
+    }
+
+    pub fn upscale_filter(&self) -> UpscaleFilter {
+        match self.uniforms.upscale_filter {
+            1 => UpscaleFilter::Bilinear,
+            _ => UpscaleFilter::Nearest,
+        }
+    }
+}
+
+fn create_diffusion_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Diffusion Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rg32Float,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    })
+}
+
+fn create_diffusion_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uniform_buffer: &wgpu::Buffer,
+    diffusion_texture: &wgpu::Texture,
+    diffusion_sampler: &wgpu::Sampler,
+    lut_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Diffusion Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(
+                    &diffusion_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                ),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(diffusion_sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: lut_buffer.as_entire_binding(),
+            },
+        ],
+    })
 }
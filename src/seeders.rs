@@ -0,0 +1,832 @@
+use fontdue::Font;
+use noise::core::worley::ReturnType;
+use noise::{NoiseFn, Perlin, Worley};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::PI;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The resting state of the reaction: all U, no V.
+pub const EMPTY: (f32, f32) = (1.0, 0.0);
+/// The state written into seeded cells.
+pub const SEEDED: (f32, f32) = (0.5, 0.25);
+/// A state rich in V that holds up on its own at high feed rates.
+pub const SHEET: (f32, f32) = (0.4, 0.4);
+
+/// Produces an initial state for a `width * height` grid.
+///
+/// Row 0 of the returned grid is the bottom of the window, matching the layout
+/// `ReactionDiffusionSystem::set_all` expects.
+pub trait Seeder {
+    fn name(&self) -> &str;
+
+    /// Generates the initial `(u, v)` values. `seed` drives any randomness so a
+    /// run can be reproduced from the same seed.
+    fn generate(&self, width: usize, height: usize, seed: u64) -> Vec<(f32, f32)>;
+
+    /// Names of the parameters [`Seeder::set_parameter`] accepts.
+    fn parameters(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Sets the parameter `name`, matched like seeder names, from `value`.
+    fn set_parameter(&mut self, name: &str, _value: &str) -> Result<(), String> {
+        Err(unknown_parameter(self.name(), self.parameters(), name))
+    }
+}
+
+/// A seeder name with parameters to set on it, written like
+/// `random-circles:count=10,max-radius=8`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeederSpec {
+    pub name: String,
+    pub parameters: Vec<(String, String)>,
+}
+
+impl SeederSpec {
+    /// Parses a seeder name, optionally followed by a colon and
+    /// comma-separated `name=value` parameters.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (name, parameters) = match text.split_once(':') {
+            Some((name, parameters)) => (name.trim(), Some(parameters)),
+            None => (text.trim(), None),
+        };
+        if name.is_empty() {
+            return Err("seeder name is empty".to_string());
+        }
+        let parameters = match parameters {
+            Some(parameters) => parameters
+                .split(',')
+                .map(|part| {
+                    let (name, value) = part
+                        .split_once('=')
+                        .ok_or_else(|| format!("`{}` is not name=value", part))?;
+                    Ok((name.trim().to_string(), value.trim().to_string()))
+                })
+                .collect::<Result<_, String>>()?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            parameters,
+        })
+    }
+
+    /// Finds the seeder named by the spec in `seeders` and sets its
+    /// parameters, returning its index.
+    pub fn apply(&self, seeders: &mut [Box<dyn Seeder>]) -> Result<usize, String> {
+        let index = position_by_name(seeders, &self.name)
+            .ok_or_else(|| format!("unknown seeder `{}`", self.name))?;
+        for (name, value) in &self.parameters {
+            seeders[index].set_parameter(name, value)?;
+        }
+        Ok(index)
+    }
+}
+
+fn unknown_parameter(seeder: &str, parameters: &[&str], name: &str) -> String {
+    if parameters.is_empty() {
+        format!("{} has no parameters, so `{}` can't be set", seeder, name)
+    } else {
+        format!(
+            "{} has no parameter `{}`, expected {}",
+            seeder,
+            name,
+            parameters.join(", ")
+        )
+    }
+}
+
+/// Parses a parameter value, rejecting infinities and NaN.
+fn parse_number<T: FromStr + Into<f64> + Copy>(value: &str) -> Result<T, String> {
+    match value.parse::<T>() {
+        Ok(number) if number.into().is_finite() => Ok(number),
+        _ => Err(format!("`{}` is not a number", value)),
+    }
+}
+
+/// Parses a length or a frequency, which can't be negative.
+fn parse_size<T: FromStr + Into<f64> + Copy>(value: &str) -> Result<T, String> {
+    let size = parse_number::<T>(value)?;
+    if size.into() < 0.0 {
+        return Err(format!("`{}` is negative", value));
+    }
+    Ok(size)
+}
+
+/// Parses a fraction between 0 and 1.
+fn parse_fraction(value: &str) -> Result<f32, String> {
+    let fraction = parse_number::<f32>(value)?;
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(format!("`{}` is not between 0 and 1", value));
+    }
+    Ok(fraction)
+}
+
+fn parse_count(value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{}` is not a whole number", value))
+}
+
+fn parse_flag(value: &str) -> Result<bool, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{}` is not true or false", value))
+}
+
+/// Builds a grid by evaluating `is_seeded` at every cell.
+fn seed_where(
+    width: usize,
+    height: usize,
+    mut is_seeded: impl FnMut(usize, usize) -> bool,
+) -> Vec<(f32, f32)> {
+    let mut values = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            values.push(if is_seeded(x, y) { SEEDED } else { EMPTY });
+        }
+    }
+    values
+}
+
+/// Squared distance between two cells on the wrapping grid.
+fn wrapped_distance_squared(
+    (ax, ay): (f32, f32),
+    (bx, by): (f32, f32),
+    width: usize,
+    height: usize,
+) -> f32 {
+    let mut dx = (ax - bx).abs();
+    let mut dy = (ay - by).abs();
+    dx = dx.min(width as f32 - dx);
+    dy = dy.min(height as f32 - dy);
+    dx * dx + dy * dy
+}
+
+/// Leaves the whole grid in the resting state.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Clear;
+
+impl Seeder for Clear {
+    fn name(&self) -> &str {
+        "Clear"
+    }
+
+    fn generate(&self, width: usize, height: usize, _seed: u64) -> Vec<(f32, f32)> {
+        vec![EMPTY; width * height]
+    }
+}
+
+/// Sprinkles random values over a fraction of the cells.
+#[derive(Debug, Clone, Copy)]
+pub struct RandomNoise {
+    /// Fraction of cells that receive noise.
+    pub density: f32,
+}
+
+impl Default for RandomNoise {
+    fn default() -> Self {
+        Self { density: 0.05 }
+    }
+}
+
+impl Seeder for RandomNoise {
+    fn name(&self) -> &str {
+        "Noise"
+    }
+
+    fn generate(&self, width: usize, height: usize, seed: u64) -> Vec<(f32, f32)> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        (0..width * height)
+            .map(|_| {
+                if rng.r#gen::<f32>() < self.density {
+                    let u = 0.5 + rng.r#gen::<f32>() * 0.5; // Random U between 0.5 and 1.0
+                    let v = 0.2 + rng.r#gen::<f32>() * 0.6; // Random V between 0.2 and 0.8
+                    (u, v)
+                } else {
+                    EMPTY
+                }
+            })
+            .collect()
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["density"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "density" => self.density = parse_fraction(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// A filled square in the middle of the grid.
+#[derive(Debug, Clone, Copy)]
+pub struct CenterSquare {
+    /// Side length as a fraction of the shorter grid dimension.
+    pub size: f32,
+}
+
+impl Default for CenterSquare {
+    fn default() -> Self {
+        Self { size: 0.1 }
+    }
+}
+
+impl Seeder for CenterSquare {
+    fn name(&self) -> &str {
+        "Center Square"
+    }
+
+    fn generate(&self, width: usize, height: usize, _seed: u64) -> Vec<(f32, f32)> {
+        let half_side = self.size * width.min(height) as f32 / 2.0;
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        seed_where(width, height, |x, y| {
+            (x as f32 - cx).abs() <= half_side && (y as f32 - cy).abs() <= half_side
+        })
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["size"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "size" => self.size = parse_size(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// A filled disc in the middle of the grid.
+#[derive(Debug, Clone, Copy)]
+pub struct CenterDisc {
+    /// Radius as a fraction of the shorter grid dimension.
+    pub radius: f32,
+}
+
+impl Default for CenterDisc {
+    fn default() -> Self {
+        Self { radius: 0.05 }
+    }
+}
+
+impl Seeder for CenterDisc {
+    fn name(&self) -> &str {
+        "Center Disc"
+    }
+
+    fn generate(&self, width: usize, height: usize, _seed: u64) -> Vec<(f32, f32)> {
+        let radius = self.radius * width.min(height) as f32;
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        seed_where(width, height, |x, y| {
+            let dx = x as f32 - cx;
+            let dy = y as f32 - cy;
+            dx * dx + dy * dy <= radius * radius
+        })
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["radius"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "radius" => self.radius = parse_size(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// Discs of random size scattered over the grid.
+#[derive(Debug, Clone, Copy)]
+pub struct RandomCircles {
+    pub count: usize,
+    /// Smallest radius in cells.
+    pub min_radius: f32,
+    /// Largest radius in cells.
+    pub max_radius: f32,
+}
+
+impl Default for RandomCircles {
+    fn default() -> Self {
+        Self {
+            count: 40,
+            min_radius: 4.0,
+            max_radius: 20.0,
+        }
+    }
+}
+
+impl Seeder for RandomCircles {
+    fn name(&self) -> &str {
+        "Random Circles"
+    }
+
+    fn generate(&self, width: usize, height: usize, seed: u64) -> Vec<(f32, f32)> {
+        let circles = random_circles(
+            width,
+            height,
+            seed,
+            self.count,
+            (self.min_radius, self.max_radius),
+        );
+        seed_where(width, height, |x, y| {
+            in_any_circle(&circles, x, y, width, height)
+        })
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["count", "min-radius", "max-radius"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "count" => self.count = parse_count(value)?,
+            "minradius" => self.min_radius = parse_size(value)?,
+            "maxradius" => self.max_radius = parse_size(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// A sheet of V over the whole grid, pierced by discs of random size left in
+/// the resting state. Suits rates where V survives on its own but can't
+/// spread from small seeds, such as Pearson's rho and sigma.
+#[derive(Debug, Clone, Copy)]
+pub struct PerforatedSheet {
+    pub count: usize,
+    /// Smallest radius in cells.
+    pub min_radius: f32,
+    /// Largest radius in cells.
+    pub max_radius: f32,
+}
+
+impl Default for PerforatedSheet {
+    fn default() -> Self {
+        Self {
+            count: 40,
+            min_radius: 2.0,
+            max_radius: 6.0,
+        }
+    }
+}
+
+impl Seeder for PerforatedSheet {
+    fn name(&self) -> &str {
+        "Perforated Sheet"
+    }
+
+    fn generate(&self, width: usize, height: usize, seed: u64) -> Vec<(f32, f32)> {
+        let holes = random_circles(
+            width,
+            height,
+            seed,
+            self.count,
+            (self.min_radius, self.max_radius),
+        );
+        let mut values = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let is_hole = in_any_circle(&holes, x, y, width, height);
+                values.push(if is_hole { EMPTY } else { SHEET });
+            }
+        }
+        values
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["count", "min-radius", "max-radius"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "count" => self.count = parse_count(value)?,
+            "minradius" => self.min_radius = parse_size(value)?,
+            "maxradius" => self.max_radius = parse_size(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// `count` circles with random centres and radii between `min_radius` and
+/// `max_radius`. An empty grid has no room for any.
+fn random_circles(
+    width: usize,
+    height: usize,
+    seed: u64,
+    count: usize,
+    (min_radius, max_radius): (f32, f32),
+) -> Vec<((f32, f32), f32)> {
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let mut rng = SmallRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let center = (
+                rng.gen_range(0.0..width as f32),
+                rng.gen_range(0.0..height as f32),
+            );
+            let radius = rng.gen_range(min_radius..=max_radius.max(min_radius));
+            (center, radius)
+        })
+        .collect()
+}
+
+/// Whether cell `(x, y)` lies inside any of `circles` on the wrapping grid.
+fn in_any_circle(
+    circles: &[((f32, f32), f32)],
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) -> bool {
+    circles.iter().any(|&(center, radius)| {
+        wrapped_distance_squared((x as f32, y as f32), center, width, height) <= radius * radius
+    })
+}
+
+/// Thresholded Perlin noise, giving blobby connected regions.
+#[derive(Debug, Clone, Copy)]
+pub struct PerlinNoise {
+    /// Noise frequency in cycles per cell.
+    pub frequency: f64,
+    /// Cells whose noise value exceeds this threshold are seeded. Perlin noise
+    /// lies roughly in [-1, 1].
+    pub threshold: f64,
+}
+
+impl Default for PerlinNoise {
+    fn default() -> Self {
+        Self {
+            frequency: 0.02,
+            threshold: 0.3,
+        }
+    }
+}
+
+impl Seeder for PerlinNoise {
+    fn name(&self) -> &str {
+        "Perlin Noise"
+    }
+
+    fn generate(&self, width: usize, height: usize, seed: u64) -> Vec<(f32, f32)> {
+        let perlin = Perlin::new(seed as u32);
+        seed_where(width, height, |x, y| {
+            perlin.get([x as f64 * self.frequency, y as f64 * self.frequency]) > self.threshold
+        })
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["frequency", "threshold"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "frequency" => self.frequency = parse_size(value)?,
+            "threshold" => self.threshold = parse_number(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// Spots at the feature points of Worley (cellular) noise.
+#[derive(Debug, Clone, Copy)]
+pub struct WorleyNoise {
+    /// Feature point frequency in points per cell along each axis.
+    pub frequency: f64,
+    /// Cells whose distance to the nearest feature point is below this
+    /// threshold are seeded. Distances are in units of the feature spacing.
+    pub threshold: f64,
+}
+
+impl Default for WorleyNoise {
+    fn default() -> Self {
+        Self {
+            frequency: 0.03,
+            threshold: 0.25,
+        }
+    }
+}
+
+impl Seeder for WorleyNoise {
+    fn name(&self) -> &str {
+        "Worley Noise"
+    }
+
+    fn generate(&self, width: usize, height: usize, seed: u64) -> Vec<(f32, f32)> {
+        let worley = Worley::new(seed as u32)
+            .set_return_type(ReturnType::Distance)
+            .set_frequency(self.frequency);
+        // The distance return type is remapped to [-1, 1], so undo that first
+        seed_where(width, height, |x, y| {
+            (worley.get([x as f64, y as f64]) + 1.0) / 2.0 < self.threshold
+        })
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["frequency", "threshold"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "frequency" => self.frequency = parse_size(value)?,
+            "threshold" => self.threshold = parse_number(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// Parallel straight lines across the grid.
+#[derive(Debug, Clone, Copy)]
+pub struct Lines {
+    pub count: usize,
+    /// Line orientation in radians, 0 being horizontal.
+    pub angle: f32,
+    /// Line thickness in cells.
+    pub thickness: f32,
+}
+
+impl Default for Lines {
+    fn default() -> Self {
+        Self {
+            count: 8,
+            angle: PI / 4.0,
+            thickness: 3.0,
+        }
+    }
+}
+
+impl Seeder for Lines {
+    fn name(&self) -> &str {
+        "Lines"
+    }
+
+    fn generate(&self, width: usize, height: usize, _seed: u64) -> Vec<(f32, f32)> {
+        let (sin, cos) = self.angle.sin_cos();
+        // Distance across the grid perpendicular to the lines
+        let extent = width as f32 * sin.abs() + height as f32 * cos.abs();
+        let spacing = extent / self.count.max(1) as f32;
+        let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+        seed_where(width, height, |x, y| {
+            let offset = -(x as f32 - cx) * sin + (y as f32 - cy) * cos + spacing / 2.0;
+            let distance_to_line = (offset.rem_euclid(spacing) - spacing / 2.0).abs();
+            distance_to_line <= self.thickness / 2.0
+        })
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["count", "angle", "thickness"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "count" => self.count = parse_count(value)?,
+            "angle" => self.angle = parse_number(value)?,
+            "thickness" => self.thickness = parse_size(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// A regular grid of small dots.
+#[derive(Debug, Clone, Copy)]
+pub struct DotGrid {
+    /// Distance between dot centres in cells.
+    pub spacing: f32,
+    /// Dot radius in cells.
+    pub radius: f32,
+}
+
+impl Default for DotGrid {
+    fn default() -> Self {
+        Self {
+            spacing: 40.0,
+            radius: 3.0,
+        }
+    }
+}
+
+impl Seeder for DotGrid {
+    fn name(&self) -> &str {
+        "Dot Grid"
+    }
+
+    fn generate(&self, width: usize, height: usize, _seed: u64) -> Vec<(f32, f32)> {
+        let spacing = self.spacing.max(1.0);
+        seed_where(width, height, |x, y| {
+            let dx = (x as f32 + spacing / 2.0).rem_euclid(spacing) - spacing / 2.0;
+            let dy = (y as f32 + spacing / 2.0).rem_euclid(spacing) - spacing / 2.0;
+            dx * dx + dy * dy <= self.radius * self.radius
+        })
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["spacing", "radius"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "spacing" => self.spacing = parse_size(value)?,
+            "radius" => self.radius = parse_size(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// Seeds the dark (or, when inverted, light) areas of an image, stretched to
+/// fill the grid.
+#[derive(Debug, Clone)]
+pub struct ImageSeeder {
+    luma: image::ImageBuffer<image::Luma<f32>, Vec<f32>>,
+    /// Brightness in [0, 1] below which a pixel is seeded.
+    pub threshold: f32,
+    /// Seed the bright areas instead of the dark ones.
+    pub invert: bool,
+}
+
+impl ImageSeeder {
+    pub fn open(path: impl AsRef<Path>) -> image::ImageResult<Self> {
+        Ok(Self {
+            luma: image::open(path)?.to_luma32f(),
+            threshold: 0.5,
+            invert: false,
+        })
+    }
+}
+
+impl Seeder for ImageSeeder {
+    fn name(&self) -> &str {
+        "Image"
+    }
+
+    fn generate(&self, width: usize, height: usize, _seed: u64) -> Vec<(f32, f32)> {
+        let (image_width, image_height) = self.luma.dimensions();
+        if image_width == 0 || image_height == 0 {
+            return Clear.generate(width, height, 0);
+        }
+
+        seed_where(width, height, |x, y| {
+            let px = (x * image_width as usize / width) as u32;
+            // Image rows start at the top, grid rows at the bottom
+            let py = ((height - 1 - y) * image_height as usize / height) as u32;
+            let is_dark = self.luma.get_pixel(px, py).0[0] < self.threshold;
+            is_dark != self.invert
+        })
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["threshold", "invert"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "threshold" => self.threshold = parse_fraction(value)?,
+            "invert" => self.invert = parse_flag(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// Seeds the shape of some text rendered in the middle of the grid.
+#[derive(Clone)]
+pub struct TextSeeder {
+    pub text: String,
+    font: Font,
+    /// Line height as a fraction of the grid height.
+    pub size: f32,
+}
+
+impl TextSeeder {
+    pub fn new(text: impl Into<String>, font: Font) -> Self {
+        Self {
+            text: text.into(),
+            font,
+            size: 0.15,
+        }
+    }
+}
+
+impl Seeder for TextSeeder {
+    fn name(&self) -> &str {
+        "Text"
+    }
+
+    fn generate(&self, width: usize, height: usize, _seed: u64) -> Vec<(f32, f32)> {
+        let mut values = Clear.generate(width, height, 0);
+        let lines: Vec<&str> = self.text.lines().collect();
+        if lines.is_empty() {
+            return values;
+        }
+
+        // Shrink the text until the widest line fits
+        let line_width = |line: &str, px: f32| -> f32 {
+            line.chars()
+                .map(|ch| self.font.metrics(ch, px).advance_width)
+                .sum()
+        };
+        let mut px = self.size * height as f32;
+        let widest = lines
+            .iter()
+            .map(|line| line_width(line, px))
+            .fold(0.0, f32::max);
+        if widest > width as f32 * 0.9 {
+            px *= width as f32 * 0.9 / widest;
+        }
+
+        let line_height = px * 1.2;
+        let block_top = (height as f32 - line_height * lines.len() as f32) / 2.0;
+
+        for (i, line) in lines.iter().enumerate() {
+            // Work in window space (y down) and flip when writing to the grid
+            let baseline = block_top + i as f32 * line_height + px;
+            let mut pen_x = (width as f32 - line_width(line, px)) / 2.0;
+
+            for ch in line.chars() {
+                let (metrics, bitmap) = self.font.rasterize(ch, px);
+                let glyph_top = baseline - metrics.height as f32 - metrics.ymin as f32;
+                for gy in 0..metrics.height {
+                    for gx in 0..metrics.width {
+                        if bitmap[gy * metrics.width + gx] < 128 {
+                            continue;
+                        }
+                        let x = (pen_x + metrics.xmin as f32) as isize + gx as isize;
+                        let window_y = glyph_top as isize + gy as isize;
+                        if x < 0
+                            || x >= width as isize
+                            || window_y < 0
+                            || window_y >= height as isize
+                        {
+                            continue;
+                        }
+                        let y = height - 1 - window_y as usize;
+                        values[y * width + x as usize] = SEEDED;
+                    }
+                }
+                pen_x += metrics.advance_width;
+            }
+        }
+
+        values
+    }
+
+    fn parameters(&self) -> &'static [&'static str] {
+        &["size"]
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match normalize_name(name).as_str() {
+            "size" => self.size = parse_size(value)?,
+            _ => return Err(unknown_parameter(self.name(), self.parameters(), name)),
+        }
+        Ok(())
+    }
+}
+
+/// The seeders that need no external input, in the order the app cycles them.
+pub fn builtin() -> Vec<Box<dyn Seeder>> {
+    vec![
+        Box::new(RandomNoise::default()),
+        Box::new(CenterSquare::default()),
+        Box::new(CenterDisc::default()),
+        Box::new(RandomCircles::default()),
+        Box::new(PerforatedSheet::default()),
+        Box::new(PerlinNoise::default()),
+        Box::new(WorleyNoise::default()),
+        Box::new(Lines::default()),
+        Box::new(DotGrid::default()),
+    ]
+}
+
+/// Finds a seeder by name, ignoring case, spaces, dashes and underscores so
+/// that `center-disc` matches "Center Disc".
+pub fn position_by_name(seeders: &[Box<dyn Seeder>], name: &str) -> Option<usize> {
+    let name = normalize_name(name);
+    seeders
+        .iter()
+        .position(|s| normalize_name(s.name()) == name)
+}
+
+/// Lowercases `name` and drops everything but letters and digits, so names
+/// can be looked up however they are written.
+pub(crate) fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
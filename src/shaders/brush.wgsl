@@ -0,0 +1,71 @@
+struct BrushParams {
+    center_x: f32,
+    center_y: f32,
+    radius: f32,
+    falloff: u32,
+    target_u: f32,
+    target_v: f32,
+    strength: f32,
+    width: u32,
+    height: u32,
+    origin_x: i32,
+    origin_y: i32,
+    extent_x: u32,
+    extent_y: u32,
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+}
+
+struct UVPair {
+    u: f32,
+    v: f32,
+}
+
+@group(0) @binding(0) var<storage, read_write> uvs: array<UVPair>;
+@group(0) @binding(1) var<uniform> brush: BrushParams;
+
+fn get_falloff(distance: f32) -> f32 {
+    switch (brush.falloff) {
+        case 0u: { // Constant
+            return 1.0;
+        }
+        case 1u: { // Linear
+            return 1.0 - distance;
+        }
+        default: { // Smooth
+            let t = 1.0 - distance * distance;
+            return t * t;
+        }
+    }
+}
+
+// Dispatched over the brush's bounding box only, starting at (origin_x, origin_y)
+// inside the grid. The box is at most one grid in size, so each cell has one
+// invocation at most
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if (global_id.x >= brush.extent_x || global_id.y >= brush.extent_y) {
+        return;
+    }
+
+    let x = brush.origin_x + i32(global_id.x);
+    let y = brush.origin_y + i32(global_id.y);
+
+    let dx = f32(x) - brush.center_x;
+    let dy = f32(y) - brush.center_y;
+    let distance = sqrt(dx * dx + dy * dy) / max(brush.radius, 0.0001);
+    if (distance >= 1.0) {
+        return;
+    }
+
+    // The grid wraps, so the brush does too
+    let wrapped_x = u32(x) % brush.width;
+    let wrapped_y = u32(y) % brush.height;
+    let idx = wrapped_y * brush.width + wrapped_x;
+
+    let factor = clamp(get_falloff(distance) * brush.strength, 0.0, 1.0);
+    let uv = uvs[idx];
+    let painted = mix(vec2<f32>(uv.u, uv.v), vec2<f32>(brush.target_u, brush.target_v), factor);
+    uvs[idx] = UVPair(painted.x, painted.y);
+}
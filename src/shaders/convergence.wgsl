@@ -0,0 +1,72 @@
+struct ConvergenceParams {
+    cell_count: u32,
+    thread_count: u32,
+    _padding0: u32,
+    _padding1: u32,
+}
+
+struct UVPair {
+    u: f32,
+    v: f32,
+}
+
+// One workgroup's share of the reduction
+struct Partial {
+    // Squared change of U in x and of V in y
+    sum_of_squares: vec2<f32>,
+    sum_v: f32,
+    max_v: f32,
+}
+
+const WORKGROUP_SIZE: u32 = 256u;
+
+@group(0) @binding(0) var<storage, read> current: array<UVPair>;
+@group(0) @binding(1) var<storage, read> previous: array<UVPair>;
+@group(0) @binding(2) var<storage, read_write> partials: array<Partial>;
+@group(0) @binding(3) var<uniform> params: ConvergenceParams;
+
+var<workgroup> shared_sum_of_squares: array<vec2<f32>, WORKGROUP_SIZE>;
+var<workgroup> shared_sum_v: array<f32, WORKGROUP_SIZE>;
+var<workgroup> shared_max_v: array<f32, WORKGROUP_SIZE>;
+
+// Each thread strides over the grid, then the workgroup reduces its threads'
+// totals in shared memory. The per-workgroup partials are combined on the CPU.
+@compute @workgroup_size(256)
+fn main(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+) {
+    var sum_of_squares = vec2<f32>(0.0);
+    var sum_v = 0.0;
+    var max_v = 0.0;
+    for (var i = global_id.x; i < params.cell_count; i += params.thread_count) {
+        let change = vec2<f32>(current[i].u - previous[i].u, current[i].v - previous[i].v);
+        sum_of_squares += change * change;
+        sum_v += current[i].v;
+        max_v = max(max_v, current[i].v);
+    }
+
+    shared_sum_of_squares[local_index] = sum_of_squares;
+    shared_sum_v[local_index] = sum_v;
+    shared_max_v[local_index] = max_v;
+    workgroupBarrier();
+
+    for (var stride = WORKGROUP_SIZE / 2u; stride > 0u; stride = stride >> 1u) {
+        if (local_index < stride) {
+            let other = local_index + stride;
+            shared_sum_of_squares[local_index] += shared_sum_of_squares[other];
+            shared_sum_v[local_index] += shared_sum_v[other];
+            shared_max_v[local_index] = max(shared_max_v[local_index], shared_max_v[other]);
+        }
+        workgroupBarrier();
+    }
+
+    if (local_index == 0u) {
+        partials[workgroup_id.x] = Partial(
+            shared_sum_of_squares[0],
+            shared_sum_v[0],
+            shared_max_v[0],
+        );
+    }
+}
@@ -0,0 +1,33 @@
+struct OverlayRect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> rect: OverlayRect;
+@group(0) @binding(1) var t_overlay: texture_2d<f32>;
+@group(0) @binding(2) var s_overlay: sampler;
+
+// A quad covering the overlay's rectangle, given as fractions of the window
+// measured from the bottom left
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let corner = vec2<f32>(f32(vertex_index & 1u), f32((vertex_index >> 1u) & 1u));
+    let window_position = vec2<f32>(rect.x, rect.y) + corner * vec2<f32>(rect.width, rect.height);
+
+    out.position = vec4<f32>(window_position * 2.0 - 1.0, 0.0, 1.0);
+    out.tex_coords = corner;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_overlay, s_overlay, in.tex_coords);
+}
@@ -0,0 +1,27 @@
+struct ProbeParams {
+    probe_count: u32,
+    row: u32,
+    _padding0: u32,
+    _padding1: u32,
+}
+
+struct UVPair {
+    u: f32,
+    v: f32,
+}
+
+@group(0) @binding(0) var<storage, read> uvs: array<UVPair>;
+@group(0) @binding(1) var<storage, read> probe_indices: array<u32>;
+@group(0) @binding(2) var<storage, read_write> samples: array<UVPair>;
+@group(0) @binding(3) var<uniform> params: ProbeParams;
+
+// Copies each probed cell into this step's row of the sample ring
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if (i >= params.probe_count) {
+        return;
+    }
+
+    samples[params.row * params.probe_count + i] = uvs[probe_indices[i]];
+}
@@ -5,8 +5,54 @@ struct SimulationParams {
     delta_v: f32,
     width: u32,
     height: u32,
-    nutrient_pattern: u32,
-    is_nutrient_pattern_reversed: u32,
+    // Simulated time, which drives animated nutrient patterns
+    time: f32,
+    _padding: u32,
+    // Strengths with which the nutrient factor scales feed, kill, delta U and
+    // delta V
+    nutrient_mapping: vec4<f32>,
+}
+
+// Must match MAX_NODES and MAX_STACK_DEPTH in nutrient_graph.rs
+const MAX_NUTRIENT_NODES: u32 = 16u;
+const NUTRIENT_STACK_SIZE: u32 = 8u;
+
+const OP_PATTERN: u32 = 0u;
+const OP_ADD: u32 = 1u;
+const OP_MULTIPLY: u32 = 2u;
+const OP_MIN: u32 = 3u;
+const OP_MAX: u32 = 4u;
+const OP_BLEND: u32 = 5u;
+// Must match IMAGE_OP in nutrient_graph.rs
+const OP_IMAGE: u32 = 6u;
+
+// Must match CYCLE_TIME in nutrient_presets.rs
+const NUTRIENT_CYCLE_TIME: f32 = 2000.0;
+const TAU: f32 = 6.283185307;
+
+// One step of a nutrient graph's postfix program: a pattern or the image,
+// which push their factor, or an operator, which pops its operands and pushes
+// the result
+struct NutrientNode {
+    op: u32,
+    pattern: u32,
+    is_reversed: u32,
+    _padding: u32,
+    center: vec2<f32>,
+    scale: f32,
+    orientation: f32,
+    contrast: f32,
+    min_value: f32,
+    max_value: f32,
+    speed: f32,
+}
+
+struct NutrientGraph {
+    node_count: u32,
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+    nodes: array<NutrientNode, MAX_NUTRIENT_NODES>,
 }
 
 struct UVPair {
@@ -17,6 +63,11 @@ struct UVPair {
 @group(0) @binding(0) var<storage, read> uvs_in: array<UVPair>;
 @group(0) @binding(1) var<storage, read_write> uvs_out: array<UVPair>;
 @group(0) @binding(2) var<uniform> params: SimulationParams;
+@group(0) @binding(3) var<uniform> nutrient_graph: NutrientGraph;
+// The nutrient graph's image resampled to the grid, row 0 at the bottom
+@group(0) @binding(4) var<storage, read> nutrient_map: array<f32>;
+// Every cell's nutrient factor, written by nutrient_field for display
+@group(0) @binding(5) var<storage, read_write> nutrient_field_out: array<f32>;
 
 fn get_index(x: i32, y: i32) -> u32 {
     let width = i32(params.width);
@@ -58,83 +109,131 @@ fn get_laplacian(x: i32, y: i32) -> vec2<f32> {
     return laplacian;
 }
 
+// A PCG hash of n between 0 and 1, in integers so that every GPU agrees.
+// Mirrors hash in nutrient_presets.rs
 fn hash(n: u32) -> f32 {
-    return fract(sin(f32(n)) * 43758.5453);
+    let state = n * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return f32(((word >> 22u) ^ word) >> 8u) / 16777216.0;
 }
 
 fn noise2D(x: u32, y: u32, seed: u32) -> f32 {
     return hash(x * 73856093u + y * 19349663u + seed);
 }
 
-fn get_nutrient_factor(x: i32, y: i32) -> f32 {
-    // Calculate normalized coordinates
-    let nx = f32(x) / f32(params.width);
-    let ny = f32(y) / f32(params.height);
+// Maps a pattern value between 0 and 1 to a factor, applying contrast, the
+// range and reversal. Mirrors NutrientParams::apply_range
+fn apply_range(node: NutrientNode, raw: f32) -> f32 {
+    let stretched = 0.5 + (raw - 0.5) * node.contrast;
+    let factor = clamp(
+        node.min_value + (node.max_value - node.min_value) * stretched,
+        min(node.min_value, node.max_value),
+        max(node.min_value, node.max_value)
+    );
+    if (node.is_reversed != 0u) {
+        return node.min_value + node.max_value - factor;
+    }
+    return factor;
+}
+
+// Rotates and scales a cell about the node's centre, in cells so that
+// rotations stay square on grids that aren't. Returns the offset from the
+// centre, to which the centre is added to get the position the node is read
+// at. Mirrors NutrientParams::transform
+fn transform(node: NutrientNode, x: i32, y: i32) -> vec2<f32> {
+    let center = node.center * vec2<f32>(f32(params.width), f32(params.height));
+    let s = sin(node.orientation);
+    let c = cos(node.orientation);
+    let r = vec2<f32>(f32(x), f32(y)) - center;
+    return vec2<f32>(r.x * c + r.y * s, r.y * c - r.x * s) / node.scale;
+}
+
+// Mirrors NutrientMap::factor
+fn image_factor(node: NutrientNode, x: i32, y: i32) -> f32 {
+    let width = i32(params.width);
+    let height = i32(params.height);
+    let p = node.center * vec2<f32>(f32(width), f32(height)) + transform(node, x, y);
+    // The image repeats beyond the grid
+    let cell_x = ((i32(floor(p.x)) % width) + width) % width;
+    let cell_y = ((i32(floor(p.y)) % height) + height) % height;
+    return apply_range(node, nutrient_map[u32(cell_y * width + cell_x)]);
+}
+
+// Fractal noise with some periodic variation, between 0 and 1. Mirrors fbm_noise
+// in nutrient_presets.rs
+fn fbm_noise(p: vec2<f32>) -> f32 {
+    var fBm = 0.0;
+    var amplitude = 0.5;
+    var frequency = 1.0;
     
-    var result = 0.0;
+    // Add multiple octaves of noise
+    for (var i = 0u; i < 4u; i = i + 1u) {
+        let scaled_x = u32(i32(floor(p.x * frequency)));
+        let scaled_y = u32(i32(floor(p.y * frequency)));
+        fBm += noise2D(scaled_x, scaled_y, i) * amplitude;
+        frequency *= 2.0;
+        amplitude *= 0.5;
+    }
     
-    switch (params.nutrient_pattern) {
+    // Add some periodic variation
+    let periodic = sin(p.x * 0.02) * cos(p.y * 0.02) * 0.2;
+    
+    // Combine and adjust contrast. pow is undefined for negative values
+    let value = fBm + periodic;
+    return clamp(value * value, 0.0, 1.0);
+}
+
+// Mirrors NutrientPattern::factor
+fn pattern_factor(node: NutrientNode, x: i32, y: i32) -> f32 {
+    let w = f32(params.width);
+    let h = f32(params.height);
+
+    let d = transform(node, x, y);
+    let p = node.center * vec2<f32>(w, h) + d;
+    let nx = p.x / w;
+    let ny = p.y / h;
+    let o = d / vec2<f32>(w, h);
+    // Cycles completed by an animated pattern, and how far it is through the
+    // current one, which keeps precision on long runs
+    let cycles = params.time * node.speed / NUTRIENT_CYCLE_TIME;
+    let phase = cycles - floor(cycles);
+
+    // Each pattern is between 0 and 1 before contrast and range apply
+    var raw = 1.0;
+
+    switch (node.pattern) {
         case 0u: { // Uniform
-            result = 1.0;
+            raw = 1.0;
         }
         case 1u: { // Checkerboard
-            let block_size = 200u;
-            let bx = u32(x) / block_size;
-            let by = u32(y) / block_size;
-            let is_checker = ((bx + by) % 2u) == 0u;
-            result = select(0.5, 1.0, is_checker);
+            let block_size = 200.0;
+            let bx = i32(floor(p.x / block_size));
+            let by = i32(floor(p.y / block_size));
+            raw = select(0.0, 1.0, ((bx + by) & 1) == 0);
         }
         case 2u: { // Diagonal gradient
-            result = (nx + ny) / 2.0;
+            raw = (nx + ny) / 2.0;
         }
         case 3u: { // Radial gradient
-            let center_x = 0.5;
-            let center_y = 0.5;
-            let dx = nx - center_x;
-            let dy = ny - center_y;
-            let distance = sqrt(dx * dx + dy * dy);
-            result = 1.0 - distance;
+            raw = 1.0 - sqrt(o.x * o.x + o.y * o.y);
         }
         case 4u: { // Vertical stripes
-            let stripe_width = 0.1;
-            let is_stripe = (nx / stripe_width) % 2.0 < 1.0;
-            result = select(0.5, 1.0, is_stripe);
+            let stripes = nx / 0.1;
+            raw = select(0.0, 1.0, stripes - 2.0 * floor(stripes / 2.0) < 1.0);
         }
         case 5u: { // Horizontal stripes
-            let stripe_width = 0.1;
-            let is_stripe = (ny / stripe_width) % 2.0 < 1.0;
-            result = select(0.5, 1.0, is_stripe);
+            let stripes = ny / 0.1;
+            raw = select(0.0, 1.0, stripes - 2.0 * floor(stripes / 2.0) < 1.0);
         }
         case 6u: { // Enhanced Noise with fBm
-            let x_u = u32(x);
-            let y_u = u32(y);
-            
-            var fBm = 0.0;
-            var amplitude = 0.5;
-            var frequency = 1.0;
-            
-            // Add multiple octaves of noise
-            for (var i = 0u; i < 4u; i = i + 1u) {
-                let scaled_x = u32(f32(x_u) * frequency);
-                let scaled_y = u32(f32(y_u) * frequency);
-                fBm += noise2D(scaled_x, scaled_y, i) * amplitude;
-                frequency *= 2.0;
-                amplitude *= 0.5;
-            }
-            
-            // Add some periodic variation
-            let periodic = sin(f32(x_u) * 0.02) * cos(f32(y_u) * 0.02) * 0.2;
-            
-            // Combine and adjust contrast
-            result = 0.5 + pow(fBm + periodic, 2.0) * 0.5;
-            result = clamp(result, 0.5, 1.0);
+            raw = fbm_noise(p);
         }
         case 7u: { // Wave function f(x,y) = xe^(-(x² + y²))
-            let x_norm = (nx * 4.0) - 2.0;
-            let y_norm = (ny * 4.0) - 2.0;
+            let x_norm = o.x * 4.0;
+            let y_norm = o.y * 4.0;
             let squared_dist = x_norm * x_norm + y_norm * y_norm;
             let wave = x_norm * exp(-squared_dist);
-            result = 0.5 + ((wave + 0.43) / 0.86) * 0.5;
+            raw = (wave + 0.43) / 0.86;
         }
         case 8u: { // Enhanced cosine grid with phase and frequency variations
             // Scale coordinates with different frequencies
@@ -149,22 +248,80 @@ fn get_nutrient_factor(x: i32, y: i32) -> f32 {
             let interference = pattern1 * pattern2;
             
             // Add non-linear transformation
-            let raw = -(interference * interference) * cos(x_scaled * 0.5);
+            let wave = -(interference * interference) * cos(x_scaled * 0.5);
             
-            // Normalize to [0.5, 1.0] with smoother transition
-            result = 0.5 + (tanh(raw) * 0.5);
+            // Normalize to [0, 1] with smoother transition
+            raw = 0.5 + (tanh(wave) * 0.5);
+        }
+        case 9u: { // Rotating radial gradient, its peak circling the centre
+            let peak = 0.25 * vec2<f32>(cos(phase * TAU), sin(phase * TAU));
+            let offset = o - peak;
+            raw = 1.0 - sqrt(offset.x * offset.x + offset.y * offset.y);
+        }
+        case 10u: { // Moving vertical stripes
+            let stripes = nx / 0.1 - phase * 2.0;
+            raw = select(0.0, 1.0, stripes - 2.0 * floor(stripes / 2.0) < 1.0);
+        }
+        case 11u: { // Scrolling noise, never repeating
+            let offset = cycles * w * 0.1;
+            raw = fbm_noise(p - vec2<f32>(offset, offset * 0.5));
+        }
+        case 12u: { // Pulsing rings moving outwards
+            let distance = sqrt(o.x * o.x + o.y * o.y);
+            raw = 0.5 + 0.5 * cos((distance / 0.1 - phase) * TAU);
         }
         default: {
-            result = 1.0;
+            raw = 1.0;
         }
     }
-    
-    // If reversed, invert the pattern (but keep it in the 0.5 to 1.0 range)
-    if (params.is_nutrient_pattern_reversed != 0u) {
-        result = 1.5 - result;
+
+    return apply_range(node, raw);
+}
+
+// Evaluates the nutrient graph's postfix program for one cell. Mirrors
+// NutrientGraph::factor
+fn get_nutrient_factor(x: i32, y: i32) -> f32 {
+    var stack: array<f32, NUTRIENT_STACK_SIZE>;
+    var top = 0u;
+
+    let node_count = min(nutrient_graph.node_count, MAX_NUTRIENT_NODES);
+    for (var i = 0u; i < node_count; i = i + 1u) {
+        let node = nutrient_graph.nodes[i];
+        if (node.op == OP_PATTERN) {
+            stack[top] = pattern_factor(node, x, y);
+            top += 1u;
+        } else if (node.op == OP_IMAGE) {
+            stack[top] = image_factor(node, x, y);
+            top += 1u;
+        } else if (node.op == OP_BLEND) {
+            let a = stack[top - 3u];
+            let b = stack[top - 2u];
+            let mask = clamp(stack[top - 1u], 0.0, 1.0);
+            top -= 2u;
+            stack[top - 1u] = a + (b - a) * mask;
+        } else {
+            let a = stack[top - 2u];
+            let b = stack[top - 1u];
+            top -= 1u;
+            var result = a;
+            if (node.op == OP_ADD) {
+                result = a + b;
+            } else if (node.op == OP_MULTIPLY) {
+                result = a * b;
+            } else if (node.op == OP_MIN) {
+                result = min(a, b);
+            } else if (node.op == OP_MAX) {
+                result = max(a, b);
+            }
+            stack[top - 1u] = result;
+        }
     }
-    
-    return result;
+
+    // An empty graph leaves the feed rate alone
+    if (top == 0u) {
+        return 1.0;
+    }
+    return stack[0];
 }
 
 @compute @workgroup_size(8, 8)
@@ -183,14 +340,31 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let laplacian = get_laplacian(x, y);
     let nutrient_factor = get_nutrient_factor(x, y);
     
-    // Incorporate nutrient factor into the feed rate
-    let effective_feed_rate = params.feed_rate * nutrient_factor;
+    // Scale each rate by the nutrient factor as strongly as the mapping says.
+    // Mirrors NutrientMapping::scales
+    let scales = max(vec4<f32>(1.0) + params.nutrient_mapping * (nutrient_factor - 1.0), vec4<f32>(0.0));
+    let effective_feed_rate = params.feed_rate * scales.x;
+    let effective_kill_rate = params.kill_rate * scales.y;
     
-    let delta_u = params.delta_u * laplacian.x - reaction_rate + effective_feed_rate * (1.0 - uv.u);
-    let delta_v = params.delta_v * laplacian.y + reaction_rate - (params.kill_rate + effective_feed_rate) * uv.v;
+    let delta_u = params.delta_u * scales.z * laplacian.x - reaction_rate + effective_feed_rate * (1.0 - uv.u);
+    let delta_v = params.delta_v * scales.w * laplacian.y + reaction_rate - (effective_kill_rate + effective_feed_rate) * uv.v;
     
     let new_u = clamp(uv.u + delta_u, 0.0, 1.0);
     let new_v = clamp(uv.v + delta_v, 0.0, 1.0);
     
     uvs_out[idx] = UVPair(new_u, new_v);
 } 
+
+// Writes every cell's nutrient factor, so that what is shown is exactly what
+// main uses
+@compute @workgroup_size(8, 8)
+fn nutrient_field(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let x = i32(global_id.x);
+    let y = i32(global_id.y);
+
+    if (x >= i32(params.width) || y >= i32(params.height)) {
+        return;
+    }
+
+    nutrient_field_out[get_index(x, y)] = get_nutrient_factor(x, y);
+}
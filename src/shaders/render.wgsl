@@ -7,6 +7,10 @@ struct Uniforms {
     window_aspect_ratio: f32,
     simulation_aspect_ratio: f32,
     is_lut_reversed: u32,
+    upscale_filter: u32, // 0 = nearest, 1 = bilinear
+    // Where the simulation is drawn, as x, y, width and height fractions of
+    // the window measured from the bottom left
+    simulation_rect: vec4<f32>,
 }
 
 // Bind groups
@@ -32,23 +36,53 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     return out;
 }
 
+// A quad covering the simulation's rectangle of the window
+@vertex
+fn vs_simulation(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let corner = vec2<f32>(f32(vertex_index & 1u), f32((vertex_index >> 1u) & 1u));
+    let window_position = uniforms.simulation_rect.xy + corner * uniforms.simulation_rect.zw;
+
+    out.position = vec4<f32>(window_position * 2.0 - 1.0, 0.0, 1.0);
+    out.tex_coords = corner;
+    return out;
+}
+
+// Loads a cell, wrapping around the edges like the simulation does
+fn load_wrapped(px: vec2<i32>, dims: vec2<i32>) -> vec2<f32> {
+    let wrapped = ((px % dims) + dims) % dims;
+    return textureLoad(t_texture, wrapped, 0).xy;
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let dims = textureDimensions(t_texture);
-    let px = vec2<i32>(
-        i32(in.tex_coords.x * f32(dims.x)),
-        i32(in.tex_coords.y * f32(dims.y))
-    );
-    
-    // Clamp pixel coordinates to valid range
-    let px_clamped = clamp(
-        px,
-        vec2<i32>(0),
-        vec2<i32>(i32(dims.x) - 1, i32(dims.y) - 1)
-    );
-    
-    let uv = textureLoad(t_texture, px_clamped, 0);
-    
+    let dims = vec2<i32>(textureDimensions(t_texture));
+    let position = in.tex_coords * vec2<f32>(dims);
+
+    // Rg32Float isn't filterable, so bilinear upscaling is done by hand
+    var uv: vec2<f32>;
+    if uniforms.upscale_filter == 1u {
+        let centered = position - 0.5;
+        let base = floor(centered);
+        let t = centered - base;
+        let px = vec2<i32>(base);
+        let bottom = mix(
+            load_wrapped(px, dims),
+            load_wrapped(px + vec2<i32>(1, 0), dims),
+            t.x
+        );
+        let top = mix(
+            load_wrapped(px + vec2<i32>(0, 1), dims),
+            load_wrapped(px + vec2<i32>(1, 1), dims),
+            t.x
+        );
+        uv = mix(bottom, top, t.y);
+    } else {
+        // Clamp pixel coordinates to valid range
+        let px_clamped = clamp(vec2<i32>(position), vec2<i32>(0), dims - 1);
+        uv = textureLoad(t_texture, px_clamped, 0).xy;
+    }
+
     // Map the v component (concentration) to LUT index
     let v = clamp(255.0 * uv.y, 0.0, 255.0);
     let lut_index = select(u32(v), u32(255.0 - v), uniforms.is_lut_reversed == 1u);
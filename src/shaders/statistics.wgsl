@@ -0,0 +1,97 @@
+struct StatisticsParams {
+    cell_count: u32,
+    thread_count: u32,
+    _padding0: u32,
+    _padding1: u32,
+}
+
+struct UVPair {
+    u: f32,
+    v: f32,
+}
+
+// One workgroup's share of the reduction, with U in x and V in y
+struct Partial {
+    sum: vec2<f32>,
+    sum_of_squares: vec2<f32>,
+    minimum: vec2<f32>,
+    maximum: vec2<f32>,
+}
+
+const WORKGROUP_SIZE: u32 = 256u;
+const HISTOGRAM_BINS: u32 = 256u;
+
+@group(0) @binding(0) var<storage, read> uvs: array<UVPair>;
+@group(0) @binding(1) var<storage, read_write> partials: array<Partial>;
+// U bins first, then V bins
+@group(0) @binding(2) var<storage, read_write> histogram: array<atomic<u32>>;
+@group(0) @binding(3) var<uniform> params: StatisticsParams;
+
+var<workgroup> shared_sum: array<vec2<f32>, WORKGROUP_SIZE>;
+var<workgroup> shared_sum_of_squares: array<vec2<f32>, WORKGROUP_SIZE>;
+var<workgroup> shared_minimum: array<vec2<f32>, WORKGROUP_SIZE>;
+var<workgroup> shared_maximum: array<vec2<f32>, WORKGROUP_SIZE>;
+var<workgroup> local_histogram: array<atomic<u32>, 512>;
+
+fn histogram_bin(value: f32) -> u32 {
+    return min(u32(clamp(value, 0.0, 1.0) * f32(HISTOGRAM_BINS)), HISTOGRAM_BINS - 1u);
+}
+
+// Each thread strides over the grid, then the workgroup reduces its threads'
+// totals in shared memory. The per-workgroup partials are combined on the CPU.
+@compute @workgroup_size(256)
+fn main(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) workgroup_id: vec3<u32>,
+) {
+    atomicStore(&local_histogram[local_index], 0u);
+    atomicStore(&local_histogram[local_index + HISTOGRAM_BINS], 0u);
+    workgroupBarrier();
+
+    var sum = vec2<f32>(0.0);
+    var sum_of_squares = vec2<f32>(0.0);
+    var minimum = vec2<f32>(3.4e38);
+    var maximum = vec2<f32>(-3.4e38);
+    for (var i = global_id.x; i < params.cell_count; i += params.thread_count) {
+        let uv = vec2<f32>(uvs[i].u, uvs[i].v);
+        sum += uv;
+        sum_of_squares += uv * uv;
+        minimum = min(minimum, uv);
+        maximum = max(maximum, uv);
+        atomicAdd(&local_histogram[histogram_bin(uv.x)], 1u);
+        atomicAdd(&local_histogram[HISTOGRAM_BINS + histogram_bin(uv.y)], 1u);
+    }
+
+    shared_sum[local_index] = sum;
+    shared_sum_of_squares[local_index] = sum_of_squares;
+    shared_minimum[local_index] = minimum;
+    shared_maximum[local_index] = maximum;
+    workgroupBarrier();
+
+    for (var stride = WORKGROUP_SIZE / 2u; stride > 0u; stride = stride >> 1u) {
+        if (local_index < stride) {
+            let other = local_index + stride;
+            shared_sum[local_index] += shared_sum[other];
+            shared_sum_of_squares[local_index] += shared_sum_of_squares[other];
+            shared_minimum[local_index] = min(shared_minimum[local_index], shared_minimum[other]);
+            shared_maximum[local_index] = max(shared_maximum[local_index], shared_maximum[other]);
+        }
+        workgroupBarrier();
+    }
+
+    if (local_index == 0u) {
+        partials[workgroup_id.x] = Partial(
+            shared_sum[0],
+            shared_sum_of_squares[0],
+            shared_minimum[0],
+            shared_maximum[0],
+        );
+    }
+
+    atomicAdd(&histogram[local_index], atomicLoad(&local_histogram[local_index]));
+    atomicAdd(
+        &histogram[local_index + HISTOGRAM_BINS],
+        atomicLoad(&local_histogram[local_index + HISTOGRAM_BINS]),
+    );
+}
@@ -0,0 +1,485 @@
+use crate::nutrient_graph::{IMAGE_OP, MAX_NODES, NutrientGraph, Operator, ProgramNode};
+use crate::nutrient_map::NutrientMap;
+use crate::nutrient_mapping::NutrientMapping;
+use crate::nutrient_presets::{NutrientParams, NutrientPattern};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Identifies a simulation state file.
+pub const MAGIC: &[u8; 4] = b"GSRD";
+/// Version of the state file layout written by this build.
+pub const VERSION: u32 = 1;
+/// Most bytes a grid or nutrient image in a state file may take. Larger ones
+/// wouldn't fit in a GPU storage buffer under the default limits, and a
+/// corrupt header shouldn't be able to ask for gigabytes.
+pub const MAX_ARRAY_BYTES: usize = 128 << 20;
+
+/// A complete, self-describing copy of a simulation's state.
+///
+/// The binary layout is little-endian throughout:
+///
+/// | field                          | type            |
+/// |--------------------------------|-----------------|
+/// | magic `GSRD`                   | `[u8; 4]`       |
+/// | version                        | `u32`           |
+/// | width, height                  | `u32`, `u32`    |
+/// | feed, kill, delta U, delta V   | `f32` x 4       |
+/// | nutrient graph node count      | `u32`           |
+/// | nutrient graph nodes           | see below       |
+/// | nutrient mapping feed, kill,   | `f32` x 4       |
+/// | delta U, delta V strengths     |                 |
+/// | step count                     | `u64`           |
+/// | seed                           | `u64`           |
+/// | U/V data, row 0 at the bottom  | `f32` pairs     |
+///
+/// The nutrient graph is stored operands first, as the GPU evaluates it. Each
+/// node is an operator (`u32`, 0 for a pattern, then add, multiply, min, max,
+/// blend and 6 for an image). A pattern follows this with the pattern
+/// (`u32`), and a pattern or image with whether it is reversed (`u32`), then
+/// its centre, scale, orientation, contrast, min, max and speed (`f32` x 8). An
+/// image ends with its width and height (`u32` x 2) and its values row by row
+/// from the top (`f32`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub width: usize,
+    pub height: usize,
+    pub feed_rate: f32,
+    pub kill_rate: f32,
+    pub delta_u: f32,
+    pub delta_v: f32,
+    pub nutrient: NutrientGraph,
+    pub nutrient_mapping: NutrientMapping,
+    pub step_count: u64,
+    pub seed: u64,
+    pub uvs: Vec<(f32, f32)>,
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    Ok(f32::from_bits(read_u32(reader)?))
+}
+
+/// The number of elements in a `width` by `height` array of `element_size`
+/// byte elements, or an error if it is empty or larger than
+/// [`MAX_ARRAY_BYTES`].
+fn array_len(width: usize, height: usize, element_size: usize, what: &str) -> io::Result<usize> {
+    let len = width
+        .checked_mul(height)
+        .filter(|&len| len > 0 && len <= MAX_ARRAY_BYTES / element_size)
+        .ok_or_else(|| {
+            invalid_data(format!("{} of {}x{} is not supported", what, width, height))
+        })?;
+    Ok(len)
+}
+
+impl Snapshot {
+    /// Checks that the snapshot can be written and read back: the grid must fit
+    /// within [`MAX_ARRAY_BYTES`] with a value for every cell, and the nutrient
+    /// graph and mapping must be usable.
+    pub fn validate(&self) -> Result<(), String> {
+        let cell_count = array_len(
+            self.width,
+            self.height,
+            std::mem::size_of::<(f32, f32)>(),
+            "A grid",
+        )
+        .map_err(|e| e.to_string())?;
+        if self.uvs.len() != cell_count {
+            return Err(format!(
+                "A {}x{} grid needs {} values but has {}",
+                self.width,
+                self.height,
+                cell_count,
+                self.uvs.len()
+            ));
+        }
+        self.nutrient
+            .validate()
+            .map_err(|e| format!("Invalid nutrient graph: {}", e))?;
+        if !self.nutrient_mapping.is_finite() {
+            return Err(format!(
+                "Nutrient mapping {} has strengths that aren't finite",
+                self.nutrient_mapping
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes the snapshot in the layout described above. Fails with
+    /// [`io::ErrorKind::InvalidInput`] for a snapshot that fails
+    /// [`Self::validate`], rather than writing a file that can't be read.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        self.validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.width as u32).to_le_bytes())?;
+        writer.write_all(&(self.height as u32).to_le_bytes())?;
+        for value in [self.feed_rate, self.kill_rate, self.delta_u, self.delta_v] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        write_nutrient_graph(&mut writer, &self.nutrient)?;
+        for strength in self.nutrient_mapping.as_array() {
+            writer.write_all(&strength.to_le_bytes())?;
+        }
+        writer.write_all(&self.step_count.to_le_bytes())?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+        for &(u, v) in &self.uvs {
+            writer.write_all(&u.to_le_bytes())?;
+            writer.write_all(&v.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    pub fn read_from(mut reader: impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("Not a simulation state file"));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported state file version {} (expected {})",
+                version, VERSION
+            )));
+        }
+
+        let width = read_u32(&mut reader)? as usize;
+        let height = read_u32(&mut reader)? as usize;
+        let cell_count = array_len(width, height, std::mem::size_of::<(f32, f32)>(), "A grid")?;
+        let feed_rate = read_f32(&mut reader)?;
+        let kill_rate = read_f32(&mut reader)?;
+        let delta_u = read_f32(&mut reader)?;
+        let delta_v = read_f32(&mut reader)?;
+        let nutrient = read_nutrient_graph(&mut reader)?;
+        let nutrient_mapping = read_nutrient_mapping(&mut reader)?;
+        let step_count = read_u64(&mut reader)?;
+        let seed = read_u64(&mut reader)?;
+
+        // Grown as the data arrives, so that a truncated file fails before
+        // taking all the memory its header asks for
+        let mut uvs = Vec::new();
+        for _ in 0..cell_count {
+            let u = read_f32(&mut reader)?;
+            let v = read_f32(&mut reader)?;
+            uvs.push((u, v));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            feed_rate,
+            kill_rate,
+            delta_u,
+            delta_v,
+            nutrient,
+            nutrient_mapping,
+            step_count,
+            seed,
+            uvs,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the U/V data as a NumPy `.npy` array of shape `(height, width, 2)`.
+    pub fn write_npy(&self, writer: impl Write) -> io::Result<()> {
+        write_npy(
+            writer,
+            &[self.height, self.width, 2],
+            self.uvs.iter().flat_map(|&(u, v)| [u, v]),
+        )
+    }
+
+    /// Writes a NumPy `.npz` archive holding `u` and `v` arrays of shape
+    /// `(height, width)`.
+    pub fn write_npz(&self, mut writer: impl Write) -> io::Result<()> {
+        let shape = [self.height, self.width];
+        let mut u = Vec::new();
+        write_npy(&mut u, &shape, self.uvs.iter().map(|&(u, _)| u))?;
+        let mut v = Vec::new();
+        write_npy(&mut v, &shape, self.uvs.iter().map(|&(_, v)| v))?;
+
+        write_stored_zip(&mut writer, &[("u.npy", &u), ("v.npy", &v)])?;
+        writer.flush()
+    }
+
+    /// Exports to `.npy` or `.npz` depending on the extension of `path`.
+    pub fn export_numpy(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let writer = BufWriter::new(File::create(path)?);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("npz") => self.write_npz(writer),
+            _ => self.write_npy(writer),
+        }
+    }
+}
+
+fn write_nutrient_params(writer: &mut impl Write, params: &NutrientParams) -> io::Result<()> {
+    writer.write_all(&(params.is_reversed as u32).to_le_bytes())?;
+    for value in [
+        params.center.0,
+        params.center.1,
+        params.scale,
+        params.orientation,
+        params.contrast,
+        params.min,
+        params.max,
+        params.speed,
+    ] {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn write_nutrient_graph(writer: &mut impl Write, graph: &NutrientGraph) -> io::Result<()> {
+    let program = graph.program();
+    writer.write_all(&(program.len() as u32).to_le_bytes())?;
+    for node in program {
+        match node {
+            ProgramNode::Pattern(pattern, params) => {
+                writer.write_all(&0u32.to_le_bytes())?;
+                writer.write_all(&pattern.as_u32().to_le_bytes())?;
+                write_nutrient_params(writer, &params)?;
+            }
+            ProgramNode::Image(map, params) => {
+                writer.write_all(&IMAGE_OP.to_le_bytes())?;
+                write_nutrient_params(writer, &params)?;
+                writer.write_all(&(map.width() as u32).to_le_bytes())?;
+                writer.write_all(&(map.height() as u32).to_le_bytes())?;
+                for value in map.values() {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+            }
+            ProgramNode::Operator(operator) => {
+                writer.write_all(&(operator as u32).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_nutrient_params(reader: &mut impl Read) -> io::Result<NutrientParams> {
+    let is_reversed = read_u32(reader)? != 0;
+    let mut values = [0.0; 8];
+    for value in &mut values {
+        *value = read_f32(reader)?;
+    }
+    let [
+        center_x,
+        center_y,
+        scale,
+        orientation,
+        contrast,
+        min,
+        max,
+        speed,
+    ] = values;
+    Ok(NutrientParams {
+        scale,
+        orientation,
+        center: (center_x, center_y),
+        contrast,
+        min,
+        max,
+        is_reversed,
+        speed,
+    })
+}
+
+fn read_nutrient_mapping(reader: &mut impl Read) -> io::Result<NutrientMapping> {
+    let mut strengths = [0.0; 4];
+    for strength in &mut strengths {
+        *strength = read_f32(reader)?;
+    }
+    let [feed, kill, delta_u, delta_v] = strengths;
+    let mapping = NutrientMapping {
+        feed,
+        kill,
+        delta_u,
+        delta_v,
+    };
+    if !mapping.is_finite() {
+        return Err(invalid_data(
+            "Nutrient mapping has a strength that isn't finite",
+        ));
+    }
+    Ok(mapping)
+}
+
+fn read_nutrient_map(reader: &mut impl Read) -> io::Result<NutrientMap> {
+    let width = read_u32(reader)? as usize;
+    let height = read_u32(reader)? as usize;
+    let count = array_len(
+        width,
+        height,
+        std::mem::size_of::<f32>(),
+        "A nutrient image",
+    )?;
+    let mut values = Vec::new();
+    for _ in 0..count {
+        let value = read_f32(reader)?;
+        if !value.is_finite() {
+            return Err(invalid_data("Nutrient image has a value that isn't finite"));
+        }
+        values.push(value);
+    }
+    Ok(NutrientMap::from_values(width, height, values).expect("values match the dimensions"))
+}
+
+fn read_nutrient_graph(reader: &mut impl Read) -> io::Result<NutrientGraph> {
+    let node_count = read_u32(reader)? as usize;
+    if node_count > MAX_NODES {
+        return Err(invalid_data(format!(
+            "Nutrient graph has {} nodes, more than the {} allowed",
+            node_count, MAX_NODES
+        )));
+    }
+
+    let mut program = Vec::with_capacity(node_count);
+    for _ in 0..node_count {
+        let op = read_u32(reader)?;
+        if op == 0 {
+            let pattern = read_u32(reader)?;
+            let pattern = NutrientPattern::from_u32(pattern)
+                .ok_or_else(|| invalid_data(format!("Unknown nutrient pattern {}", pattern)))?;
+            program.push(ProgramNode::Pattern(pattern, read_nutrient_params(reader)?));
+        } else if op == IMAGE_OP {
+            let params = read_nutrient_params(reader)?;
+            let map = read_nutrient_map(reader)?;
+            program.push(ProgramNode::Image(Arc::new(map), params));
+        } else {
+            let operator = Operator::from_u32(op)
+                .ok_or_else(|| invalid_data(format!("Unknown nutrient operator {}", op)))?;
+            program.push(ProgramNode::Operator(operator));
+        }
+    }
+
+    let graph = NutrientGraph::from_program(&program)
+        .ok_or_else(|| invalid_data("Nutrient graph operators are missing operands"))?;
+    graph
+        .validate()
+        .map_err(|e| invalid_data(format!("Invalid nutrient graph: {}", e)))?;
+    Ok(graph)
+}
+
+fn write_npy(
+    mut writer: impl Write,
+    shape: &[usize],
+    values: impl Iterator<Item = f32>,
+) -> io::Result<()> {
+    let shape = shape
+        .iter()
+        .map(|dim| dim.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}), }}",
+        shape
+    );
+    // The magic, version and header length take 10 bytes, and the whole
+    // preamble must be padded with spaces to a multiple of 64 ending in '\n'
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for value in values {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Writes an uncompressed zip archive, which is all `numpy.load` needs to
+/// read an `.npz` file.
+fn write_stored_zip(writer: &mut impl Write, files: &[(&str, &[u8])]) -> io::Result<()> {
+    let mut central_directory = Vec::new();
+    let mut offset = 0u32;
+
+    for &(name, data) in files {
+        let crc = crc32(data);
+        let size = u32::try_from(data.len())
+            .map_err(|_| invalid_data("Array is too large for an .npz archive"))?;
+
+        let mut local_header = Vec::new();
+        local_header.extend_from_slice(&0x0403_4b50u32.to_le_bytes()); // Signature
+        local_header.extend_from_slice(&20u16.to_le_bytes()); // Version needed
+        local_header.extend_from_slice(&0u16.to_le_bytes()); // Flags
+        local_header.extend_from_slice(&0u16.to_le_bytes()); // Stored
+        local_header.extend_from_slice(&0u32.to_le_bytes()); // Modification time and date
+        local_header.extend_from_slice(&crc.to_le_bytes());
+        local_header.extend_from_slice(&size.to_le_bytes()); // Compressed size
+        local_header.extend_from_slice(&size.to_le_bytes()); // Uncompressed size
+        local_header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        local_header.extend_from_slice(&0u16.to_le_bytes()); // Extra field length
+        local_header.extend_from_slice(name.as_bytes());
+
+        central_directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes()); // Signature
+        central_directory.extend_from_slice(&20u16.to_le_bytes()); // Version made by
+        central_directory.extend_from_slice(&local_header[4..30]); // Shared with the local header
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Comment length
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Disk number
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Internal attributes
+        central_directory.extend_from_slice(&0u32.to_le_bytes()); // External attributes
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+
+        writer.write_all(&local_header)?;
+        writer.write_all(data)?;
+        offset = (local_header.len() as u32)
+            .checked_add(size)
+            .and_then(|length| offset.checked_add(length))
+            .ok_or_else(|| invalid_data("Arrays are too large for an .npz archive"))?;
+    }
+
+    writer.write_all(&central_directory)?;
+    writer.write_all(&0x0605_4b50u32.to_le_bytes())?; // End of central directory signature
+    writer.write_all(&0u16.to_le_bytes())?; // Disk number
+    writer.write_all(&0u16.to_le_bytes())?; // Disk with the central directory
+    writer.write_all(&(files.len() as u16).to_le_bytes())?;
+    writer.write_all(&(files.len() as u16).to_le_bytes())?;
+    writer.write_all(&(central_directory.len() as u32).to_le_bytes())?;
+    writer.write_all(&offset.to_le_bytes())?;
+    writer.write_all(&0u16.to_le_bytes()) // Comment length
+}
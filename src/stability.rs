@@ -0,0 +1,265 @@
+//! Linear stability analysis of the homogeneous states of the model.
+//!
+//! A homogeneous steady state is Turing unstable when it is stable on its own
+//! but diffusion makes perturbations of some wavelength grow. The dispersion
+//! relation is computed with the symbol of the same 3x3 Laplacian stencil the
+//! simulation uses, so predicted wavelengths are in cells and match what the
+//! grid shows, rather than those of the continuous model.
+
+use rustfft::num_complex::Complex;
+use std::f64::consts::TAU;
+use std::fmt;
+
+/// Wavenumbers sampled between zero and the Nyquist frequency when searching
+/// for the most unstable mode.
+const DISPERSION_SAMPLES: usize = 1024;
+
+/// Symbol of the simulation's Laplacian stencil for a wave with `kx` and `ky`
+/// cycles per cell. Always between -1.6 and 0.
+pub fn laplacian_symbol(kx: f64, ky: f64) -> f64 {
+    let (cx, cy) = ((TAU * kx).cos(), (TAU * ky).cos());
+    -1.0 + 0.4 * (cx + cy) + 0.2 * cx * cy
+}
+
+/// A spatially uniform state where reaction, feed and kill balance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SteadyState {
+    pub u: f64,
+    pub v: f64,
+    /// Partial derivatives of the reaction terms `[[∂u'/∂u, ∂u'/∂v], [∂v'/∂u, ∂v'/∂v]]`.
+    pub jacobian: [[f64; 2]; 2],
+}
+
+impl SteadyState {
+    fn new(u: f64, v: f64, feed_rate: f64, kill_rate: f64) -> Self {
+        Self {
+            u,
+            v,
+            jacobian: [
+                [-v * v - feed_rate, -2.0 * u * v],
+                [v * v, 2.0 * u * v - (feed_rate + kill_rate)],
+            ],
+        }
+    }
+
+    /// Whether this is the U = 1, V = 0 state every parameter set has.
+    pub fn is_trivial(&self) -> bool {
+        self.v == 0.0
+    }
+
+    /// Growth rates of a perturbation whose wave has Laplacian symbol `symbol`,
+    /// as the eigenvalues of `J + symbol * diag(Du, Dv)`, largest real part first.
+    pub fn eigenvalues(&self, symbol: f64, delta_u: f64, delta_v: f64) -> [Complex<f64>; 2] {
+        let [[a, b], [c, d]] = self.jacobian;
+        let (a, d) = (a + symbol * delta_u, d + symbol * delta_v);
+        let half_trace = (a + d) / 2.0;
+        let determinant = a * d - b * c;
+        let root = Complex::new(half_trace * half_trace - determinant, 0.0).sqrt();
+        [half_trace + root, half_trace - root]
+    }
+}
+
+/// The growth rate of the least stable perturbation at each wavenumber along
+/// the x axis. The stencil is close to isotropic, so other directions differ
+/// only slightly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DispersionRelation {
+    /// Cycles per cell, from 0 to the Nyquist frequency of 0.5.
+    pub wavenumbers: Vec<f64>,
+    /// Largest real part of the growth rates, per step.
+    pub growth_rates: Vec<f64>,
+}
+
+/// The fastest growing perturbation of a Turing unstable state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnstableMode {
+    /// Cycles per cell.
+    pub wavenumber: f64,
+    /// Cells per cycle.
+    pub wavelength: f64,
+    /// Growth rate per step.
+    pub growth_rate: f64,
+}
+
+/// How a homogeneous steady state responds to small perturbations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SteadyStateStability {
+    pub state: SteadyState,
+    /// Eigenvalues for uniform perturbations, largest real part first.
+    pub homogeneous_eigenvalues: [Complex<f64>; 2],
+    pub dispersion: DispersionRelation,
+    /// The fastest growing nonuniform perturbation, if any grow at all.
+    pub most_unstable_mode: Option<UnstableMode>,
+}
+
+impl SteadyStateStability {
+    /// Whether uniform perturbations die away.
+    pub fn is_homogeneously_stable(&self) -> bool {
+        self.homogeneous_eigenvalues[0].re < 0.0
+    }
+
+    /// Whether uniform perturbations spiral rather than decay or grow directly.
+    pub fn is_oscillatory(&self) -> bool {
+        self.homogeneous_eigenvalues[0].im != 0.0
+    }
+
+    /// Stable to uniform perturbations but unstable to some wavelength.
+    pub fn is_turing_unstable(&self) -> bool {
+        self.is_homogeneously_stable() && self.most_unstable_mode.is_some()
+    }
+}
+
+/// The homogeneous steady states of one parameter set and their stability.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StabilityAnalysis {
+    pub feed_rate: f64,
+    pub kill_rate: f64,
+    pub delta_u: f64,
+    pub delta_v: f64,
+    /// The trivial state first, then any others in order of increasing V.
+    pub states: Vec<SteadyStateStability>,
+}
+
+impl StabilityAnalysis {
+    pub fn new(feed_rate: f32, kill_rate: f32, delta_u: f32, delta_v: f32) -> Self {
+        let (f, k) = (feed_rate as f64, kill_rate as f64);
+        let (delta_u, delta_v) = (delta_u as f64, delta_v as f64);
+
+        // V ≠ 0 requires uv = f + k, which leaves
+        // (f + k)v² - fv + f(f + k) = 0
+        let mut states = vec![SteadyState::new(1.0, 0.0, f, k)];
+        let discriminant = f * f - 4.0 * f * (f + k).powi(2);
+        if f > 0.0 && discriminant >= 0.0 {
+            let root = discriminant.sqrt();
+            let mut roots = vec![(f - root) / (2.0 * (f + k))];
+            if root > 0.0 {
+                roots.push((f + root) / (2.0 * (f + k)));
+            }
+            for v in roots {
+                let u = 1.0 - (f + k) * v / f;
+                states.push(SteadyState::new(u, v, f, k));
+            }
+        }
+
+        Self {
+            feed_rate: f,
+            kill_rate: k,
+            delta_u,
+            delta_v,
+            states: states
+                .into_iter()
+                .map(|state| analyse(state, delta_u, delta_v))
+                .collect(),
+        }
+    }
+
+    /// Whether any homogeneous state is Turing unstable.
+    pub fn is_turing_unstable(&self) -> bool {
+        self.states.iter().any(|s| s.is_turing_unstable())
+    }
+
+    /// The fastest growing mode among the Turing unstable states.
+    pub fn most_unstable_mode(&self) -> Option<UnstableMode> {
+        self.states
+            .iter()
+            .filter(|s| s.is_turing_unstable())
+            .filter_map(|s| s.most_unstable_mode)
+            .max_by(|a, b| a.growth_rate.total_cmp(&b.growth_rate))
+    }
+}
+
+fn analyse(state: SteadyState, delta_u: f64, delta_v: f64) -> SteadyStateStability {
+    let growth_rate = |wavenumber: f64| {
+        state.eigenvalues(laplacian_symbol(wavenumber, 0.0), delta_u, delta_v)[0].re
+    };
+
+    let wavenumbers: Vec<f64> = (0..=DISPERSION_SAMPLES)
+        .map(|i| 0.5 * i as f64 / DISPERSION_SAMPLES as f64)
+        .collect();
+    let growth_rates: Vec<f64> = wavenumbers.iter().map(|&k| growth_rate(k)).collect();
+
+    // The peak of the sampled curve, refined with a parabola through its
+    // neighbours
+    let most_unstable_mode = (1..wavenumbers.len())
+        .max_by(|&a, &b| growth_rates[a].total_cmp(&growth_rates[b]))
+        .filter(|&i| growth_rates[i] > 0.0 && growth_rates[i] > growth_rates[0])
+        .map(|i| {
+            let step = wavenumbers[1];
+            let mut wavenumber = wavenumbers[i];
+            if let (Some(&left), Some(&right)) = (growth_rates.get(i - 1), growth_rates.get(i + 1))
+            {
+                let curvature = left - 2.0 * growth_rates[i] + right;
+                if curvature < 0.0 {
+                    wavenumber += (0.5 * (left - right) / curvature).clamp(-0.5, 0.5) * step;
+                }
+            }
+            UnstableMode {
+                wavenumber,
+                wavelength: 1.0 / wavenumber,
+                growth_rate: growth_rate(wavenumber),
+            }
+        });
+
+    SteadyStateStability {
+        state,
+        homogeneous_eigenvalues: state.eigenvalues(0.0, delta_u, delta_v),
+        dispersion: DispersionRelation {
+            wavenumbers,
+            growth_rates,
+        },
+        most_unstable_mode,
+    }
+}
+
+impl fmt::Display for StabilityAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "f = {}, k = {}, Du = {}, Dv = {}",
+            // Shown at the precision they were given in
+            self.feed_rate as f32,
+            self.kill_rate as f32,
+            self.delta_u as f32,
+            self.delta_v as f32
+        )?;
+        for stability in &self.states {
+            let state = &stability.state;
+            write!(f, "Steady state u = {:.4}, v = {:.4}: ", state.u, state.v)?;
+            let [first, second] = stability.homogeneous_eigenvalues;
+            let stability_text = if stability.is_homogeneously_stable() {
+                "stable"
+            } else {
+                "unstable"
+            };
+            if stability.is_oscillatory() {
+                write!(
+                    f,
+                    "{} spiral, eigenvalues {:.5} ± {:.5}i",
+                    stability_text, first.re, first.im
+                )?;
+            } else {
+                write!(
+                    f,
+                    "{}, eigenvalues {:.5} and {:.5}",
+                    stability_text, first.re, second.re
+                )?;
+            }
+            match stability.most_unstable_mode {
+                Some(mode) if stability.is_turing_unstable() => writeln!(
+                    f,
+                    "\n  Turing unstable: wavelength {:.1} cells ({:.4} cycles per cell), growth rate {:.5} per step",
+                    mode.wavelength, mode.wavenumber, mode.growth_rate
+                )?,
+                _ => writeln!(f)?,
+            }
+        }
+        match self.most_unstable_mode() {
+            Some(mode) => write!(
+                f,
+                "Turing unstable, predicted wavelength {:.1} cells",
+                mode.wavelength
+            ),
+            None => write!(f, "Not Turing unstable"),
+        }
+    }
+}
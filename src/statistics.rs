@@ -0,0 +1,398 @@
+use crate::field::{Field, UVPair};
+use crate::readback::{
+    REDUCTION_WORKGROUP_COUNT, ReductionParams, StagingBuffer, active_workgroups,
+};
+use bytemuck::{Pod, Zeroable};
+
+/// Number of equal-width bins covering concentrations from 0 to 1.
+pub const HISTOGRAM_BINS: usize = 256;
+
+/// Summary statistics of one species over the whole grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeciesStatistics {
+    pub mean: f32,
+    pub variance: f32,
+    pub min: f32,
+    pub max: f32,
+    /// Sum of the concentration over every cell.
+    pub total_mass: f32,
+    /// Cell counts per bin, with values outside 0 to 1 counted in the end bins.
+    pub histogram: [u32; HISTOGRAM_BINS],
+}
+
+impl SpeciesStatistics {
+    pub fn std_dev(&self) -> f32 {
+        self.variance.sqrt()
+    }
+
+    fn from_values(values: impl Iterator<Item = f32>) -> Self {
+        let mut accumulator = Accumulator::default();
+        let mut histogram = [0; HISTOGRAM_BINS];
+        for value in values {
+            accumulator.add(value as f64, (value * value) as f64, value, value);
+            histogram[histogram_bin(value)] += 1;
+        }
+        accumulator.finish(histogram)
+    }
+}
+
+/// Statistics of both species at one step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldStatistics {
+    pub step_count: u64,
+    pub cell_count: usize,
+    pub u: SpeciesStatistics,
+    pub v: SpeciesStatistics,
+}
+
+impl FieldStatistics {
+    /// Computes the statistics of a field on the CPU. Gives the same results as
+    /// the GPU pass, up to rounding.
+    pub fn from_field(field: &Field) -> Self {
+        Self {
+            step_count: field.step_count,
+            cell_count: field.as_slice().len(),
+            u: SpeciesStatistics::from_values(field.u_values()),
+            v: SpeciesStatistics::from_values(field.v_values()),
+        }
+    }
+}
+
+/// The histogram bin a concentration falls into.
+pub fn histogram_bin(value: f32) -> usize {
+    ((value.clamp(0.0, 1.0) * HISTOGRAM_BINS as f32) as usize).min(HISTOGRAM_BINS - 1)
+}
+
+#[derive(Default)]
+struct Accumulator {
+    sum: f64,
+    sum_of_squares: f64,
+    min: Option<f32>,
+    max: Option<f32>,
+}
+
+impl Accumulator {
+    fn add(&mut self, sum: f64, sum_of_squares: f64, min: f32, max: f32) {
+        self.sum += sum;
+        self.sum_of_squares += sum_of_squares;
+        self.min = Some(self.min.map_or(min, |m| m.min(min)));
+        self.max = Some(self.max.map_or(max, |m| m.max(max)));
+    }
+
+    fn finish(self, histogram: [u32; HISTOGRAM_BINS]) -> SpeciesStatistics {
+        let cell_count = histogram.iter().map(|&n| n as f64).sum::<f64>().max(1.0);
+        let mean = self.sum / cell_count;
+        SpeciesStatistics {
+            mean: mean as f32,
+            variance: (self.sum_of_squares / cell_count - mean * mean).max(0.0) as f32,
+            min: self.min.unwrap_or(0.0),
+            max: self.max.unwrap_or(0.0),
+            total_mass: self.sum as f32,
+            histogram,
+        }
+    }
+}
+
+/// Mirrors `Partial` in statistics.wgsl, with U in the first component of
+/// each pair and V in the second.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct Partial {
+    sum: UVPair,
+    sum_of_squares: UVPair,
+    min: UVPair,
+    max: UVPair,
+}
+
+const PARTIALS_SIZE: u64 = (REDUCTION_WORKGROUP_COUNT * std::mem::size_of::<Partial>()) as u64;
+const HISTOGRAM_SIZE: u64 = (2 * HISTOGRAM_BINS * std::mem::size_of::<u32>()) as u64;
+
+/// Reduces the grid to [`FieldStatistics`] on the GPU, reading the result back
+/// without stalling the simulation.
+pub(crate) struct StatisticsPass {
+    cell_count: usize,
+    params_buffer: wgpu::Buffer,
+    partials_buffer: wgpu::Buffer,
+    histogram_buffer: wgpu::Buffer,
+    staging: StagingBuffer,
+    latest: Option<FieldStatistics>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_groups: [wgpu::BindGroup; 2],
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl StatisticsPass {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        uvs_buffers: &[wgpu::Buffer; 2],
+        cell_count: usize,
+    ) -> Self {
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Statistics Bind Group Layout"),
+            entries: &[
+                storage_entry(0, true),
+                storage_entry(1, false),
+                storage_entry(2, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Statistics Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Statistics Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/statistics.wgsl").into()),
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Statistics Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Statistics Params Buffer"),
+            size: std::mem::size_of::<ReductionParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let partials_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Statistics Partials Buffer"),
+            size: PARTIALS_SIZE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let histogram_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Statistics Histogram Buffer"),
+            size: HISTOGRAM_SIZE,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging = StagingBuffer::new(
+            device,
+            "Statistics Staging Buffer",
+            PARTIALS_SIZE + HISTOGRAM_SIZE,
+        );
+
+        let bind_groups = create_bind_groups(
+            device,
+            &bind_group_layout,
+            uvs_buffers,
+            &partials_buffer,
+            &histogram_buffer,
+            &params_buffer,
+        );
+
+        let pass = Self {
+            cell_count,
+            params_buffer,
+            partials_buffer,
+            histogram_buffer,
+            staging,
+            latest: None,
+            bind_group_layout,
+            bind_groups,
+            pipeline,
+        };
+        pass.write_params(queue);
+        pass
+    }
+
+    /// Points the pass at a new pair of grid buffers after a resize.
+    pub(crate) fn rebind(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        uvs_buffers: &[wgpu::Buffer; 2],
+        cell_count: usize,
+    ) {
+        self.cell_count = cell_count;
+        self.bind_groups = create_bind_groups(
+            device,
+            &self.bind_group_layout,
+            uvs_buffers,
+            &self.partials_buffer,
+            &self.histogram_buffer,
+            &self.params_buffer,
+        );
+        self.write_params(queue);
+    }
+
+    fn write_params(&self, queue: &wgpu::Queue) {
+        let params = ReductionParams::new(self.cell_count);
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::cast_slice(&[params]));
+    }
+
+    /// Whether a reduction can be encoded now. Only one may be in flight.
+    pub(crate) fn is_idle(&self) -> bool {
+        self.staging.is_idle()
+    }
+
+    /// Encodes a reduction of `uvs_buffers[buffer_index]`, the state after
+    /// `step_count`. Call [`Self::start_mapping`] once the encoder has been
+    /// submitted. Does nothing if a reduction is already in flight.
+    pub(crate) fn encode(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        buffer_index: usize,
+        step_count: u64,
+    ) {
+        if !self.is_idle() {
+            return;
+        }
+
+        encoder.clear_buffer(&self.histogram_buffer, 0, None);
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Statistics Pass"),
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, &self.bind_groups[buffer_index], &[]);
+            compute_pass.dispatch_workgroups(REDUCTION_WORKGROUP_COUNT as u32, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(
+            &self.partials_buffer,
+            0,
+            self.staging.buffer(),
+            0,
+            PARTIALS_SIZE,
+        );
+        encoder.copy_buffer_to_buffer(
+            &self.histogram_buffer,
+            0,
+            self.staging.buffer(),
+            PARTIALS_SIZE,
+            HISTOGRAM_SIZE,
+        );
+
+        self.staging.mark_encoded(step_count);
+    }
+
+    /// Starts mapping the results of a submitted reduction.
+    pub(crate) fn start_mapping(&mut self) {
+        self.staging.start_mapping();
+    }
+
+    /// Whether a reduction has been submitted and not yet collected.
+    pub(crate) fn is_submitted(&self) -> bool {
+        self.staging.is_submitted()
+    }
+
+    /// Collects a finished reduction, if there is one. The device must have
+    /// been polled for mapping to make progress.
+    pub(crate) fn collect(&mut self) -> Result<(), wgpu::BufferAsyncError> {
+        let cell_count = self.cell_count;
+        let statistics = self.staging.collect(|data, step_count| {
+            let (partials, histogram) = data.split_at(PARTIALS_SIZE as usize);
+            combine(
+                bytemuck::cast_slice(partials),
+                bytemuck::cast_slice(histogram),
+                step_count,
+                cell_count,
+            )
+        })?;
+        if statistics.is_some() {
+            self.latest = statistics;
+        }
+        Ok(())
+    }
+
+    /// The most recently collected statistics.
+    pub(crate) fn latest(&self) -> Option<&FieldStatistics> {
+        self.latest.as_ref()
+    }
+}
+
+/// Merges the per-workgroup sums and extremes in double precision.
+fn combine(
+    partials: &[Partial],
+    histogram: &[u32],
+    step_count: u64,
+    cell_count: usize,
+) -> FieldStatistics {
+    let mut u = Accumulator::default();
+    let mut v = Accumulator::default();
+    for partial in &partials[..active_workgroups(cell_count)] {
+        u.add(
+            partial.sum.u as f64,
+            partial.sum_of_squares.u as f64,
+            partial.min.u,
+            partial.max.u,
+        );
+        v.add(
+            partial.sum.v as f64,
+            partial.sum_of_squares.v as f64,
+            partial.min.v,
+            partial.max.v,
+        );
+    }
+
+    let (u_histogram, v_histogram) = histogram.split_at(HISTOGRAM_BINS);
+    FieldStatistics {
+        step_count,
+        cell_count,
+        u: u.finish(u_histogram.try_into().unwrap()),
+        v: v.finish(v_histogram.try_into().unwrap()),
+    }
+}
+
+fn create_bind_groups(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uvs_buffers: &[wgpu::Buffer; 2],
+    partials_buffer: &wgpu::Buffer,
+    histogram_buffer: &wgpu::Buffer,
+    params_buffer: &wgpu::Buffer,
+) -> [wgpu::BindGroup; 2] {
+    [0, 1].map(|i| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("Statistics Bind Group {}", i)),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uvs_buffers[i].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: partials_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: histogram_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    })
+}
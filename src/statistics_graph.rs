@@ -0,0 +1,86 @@
+use gray_scott_reaction_diffusion::statistics::{FieldStatistics, HISTOGRAM_BINS};
+use std::collections::VecDeque;
+
+pub const GRAPH_WIDTH: u32 = HISTOGRAM_BINS as u32;
+pub const GRAPH_HEIGHT: u32 = 160;
+
+/// Height of the histogram strip at the bottom of the graph; the mean V trace
+/// fills the rest.
+const HISTOGRAM_HEIGHT: u32 = 96;
+
+const BACKGROUND: [u8; 4] = [0, 0, 0, 180];
+const U_COLOR: [u8; 4] = [80, 200, 255, 255];
+const V_COLOR: [u8; 4] = [255, 90, 200, 255];
+const OVERLAP_COLOR: [u8; 4] = [255, 255, 255, 255];
+const TRACE_COLOR: [u8; 4] = [255, 220, 60, 255];
+const DIVIDER_COLOR: [u8; 4] = [120, 120, 120, 255];
+
+/// Draws the U and V histograms of `statistics`, log-scaled, beneath a trace
+/// of mean V over `mean_v_history`. Returns RGBA pixels with rows running
+/// bottom to top.
+pub fn draw(statistics: &FieldStatistics, mean_v_history: &VecDeque<f32>) -> Vec<u8> {
+    let mut pixels: Vec<u8> = BACKGROUND
+        .iter()
+        .copied()
+        .cycle()
+        .take((GRAPH_WIDTH * GRAPH_HEIGHT * 4) as usize)
+        .collect();
+    let mut put = |x: u32, y: u32, color: [u8; 4]| {
+        let index = ((y * GRAPH_WIDTH + x) * 4) as usize;
+        pixels[index..index + 4].copy_from_slice(&color);
+    };
+
+    // Log scaling keeps the few cells of a sparse pattern visible next to a
+    // background of thousands
+    let max_count = statistics
+        .u
+        .histogram
+        .iter()
+        .chain(statistics.v.histogram.iter())
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let bar_height = |count: u32| {
+        let fraction = (count as f32).ln_1p() / (max_count as f32).ln_1p();
+        (fraction * (HISTOGRAM_HEIGHT - 1) as f32).round() as u32
+    };
+    for bin in 0..HISTOGRAM_BINS {
+        let u_height = bar_height(statistics.u.histogram[bin]);
+        let v_height = bar_height(statistics.v.histogram[bin]);
+        for y in 0..u_height.max(v_height) {
+            let color = match (y < u_height, y < v_height) {
+                (true, true) => OVERLAP_COLOR,
+                (true, false) => U_COLOR,
+                _ => V_COLOR,
+            };
+            put(bin as u32, y, color);
+        }
+    }
+
+    for x in 0..GRAPH_WIDTH {
+        put(x, HISTOGRAM_HEIGHT, DIVIDER_COLOR);
+    }
+
+    // Newest sample on the right, scaled to the largest mean in view
+    let trace_bottom = HISTOGRAM_HEIGHT + 2;
+    let trace_height = GRAPH_HEIGHT - trace_bottom - 1;
+    let max_mean = mean_v_history
+        .iter()
+        .copied()
+        .fold(0.0f32, f32::max)
+        .max(f32::EPSILON);
+    let offset = GRAPH_WIDTH as usize - mean_v_history.len().min(GRAPH_WIDTH as usize);
+    for (i, &mean) in mean_v_history
+        .iter()
+        .rev()
+        .take(GRAPH_WIDTH as usize)
+        .rev()
+        .enumerate()
+    {
+        let y = trace_bottom + ((mean / max_mean) * trace_height as f32).round() as u32;
+        put((offset + i) as u32, y, TRACE_COLOR);
+    }
+
+    pixels
+}
@@ -0,0 +1,108 @@
+//! Checks that brushes wrap around the grid and that brushes larger than it
+//! paint every cell exactly once.
+
+mod common;
+
+use gray_scott_reaction_diffusion::brush::{BrushFalloff, BrushStroke};
+
+const WIDTH: usize = 24;
+const HEIGHT: usize = 16;
+
+/// Paints a uniform grid with a brush of `radius`, far wider than the grid,
+/// returning the `v` of every cell.
+fn paint_oversized(radius: f32, falloff: BrushFalloff, strength: f32) -> Option<Vec<f32>> {
+    let mut system = common::gpu_system(WIDTH, HEIGHT)?;
+    system.set_all(&vec![(1.0, 0.0); WIDTH * HEIGHT]);
+    system
+        .apply_brush(&BrushStroke {
+            x: 3.5,
+            y: 12.0,
+            radius,
+            falloff,
+            target: (0.0, 1.0),
+            strength,
+        })
+        .unwrap();
+    Some(system.uvs().iter().map(|uv| uv.v).collect())
+}
+
+#[test]
+fn oversized_constant_brush_fills_the_grid() {
+    let Some(vs) = paint_oversized(500.0, BrushFalloff::Constant, 1.0) else {
+        return;
+    };
+    assert!(vs.iter().all(|&v| v == 1.0), "{:?}", vs);
+}
+
+#[test]
+fn oversized_brush_blends_each_cell_once() {
+    let Some(vs) = paint_oversized(500.0, BrushFalloff::Constant, 0.5) else {
+        return;
+    };
+    // A cell painted twice would be pulled three quarters of the way
+    for (i, &v) in vs.iter().enumerate() {
+        assert!(
+            (v - 0.5).abs() < 1e-6,
+            "({}, {}) has v = {}",
+            i % WIDTH,
+            i / WIDTH,
+            v
+        );
+    }
+}
+
+#[test]
+fn brush_wraps_around_the_corner() {
+    let Some(mut system) = common::gpu_system(WIDTH, HEIGHT) else {
+        return;
+    };
+    system.set_all(&vec![(1.0, 0.0); WIDTH * HEIGHT]);
+    let radius = 3.0;
+    system
+        .apply_brush(&BrushStroke {
+            x: 0.0,
+            y: 0.0,
+            radius,
+            falloff: BrushFalloff::Constant,
+            target: (0.0, 1.0),
+            strength: 1.0,
+        })
+        .unwrap();
+    for (i, uv) in system.uvs().iter().enumerate() {
+        // Distance to the nearest image of the corner
+        let (x, y) = ((i % WIDTH) as f32, (i / WIDTH) as f32);
+        let dx = x.min(WIDTH as f32 - x);
+        let dy = y.min(HEIGHT as f32 - y);
+        let expected = if dx.hypot(dy) < radius { 1.0 } else { 0.0 };
+        assert_eq!(uv.v, expected, "({}, {})", x, y);
+    }
+}
+
+#[test]
+fn largest_finite_brush_fills_the_grid() {
+    let Some(vs) = paint_oversized(f32::MAX, BrushFalloff::Constant, 1.0) else {
+        return;
+    };
+    assert!(vs.iter().all(|&v| v == 1.0), "{:?}", vs);
+}
+
+#[test]
+fn rejects_strokes_that_arent_finite() {
+    let Some(mut system) = common::gpu_system(WIDTH, HEIGHT) else {
+        return;
+    };
+    system.set_all(&vec![(1.0, 0.0); WIDTH * HEIGHT]);
+    for stroke in [
+        BrushStroke::seed(3.0, 4.0, f32::INFINITY),
+        BrushStroke::seed(3.0, 4.0, f32::NAN),
+        BrushStroke::seed(3.0, 4.0, -1.0),
+        BrushStroke::seed(f32::NAN, 4.0, 2.0),
+        BrushStroke {
+            strength: f32::INFINITY,
+            ..BrushStroke::erase(3.0, 4.0, 2.0)
+        },
+    ] {
+        assert!(system.apply_brush(&stroke).is_err(), "{:?}", stroke);
+    }
+    assert!(system.uvs().iter().all(|uv| uv.v == 0.0));
+}
@@ -0,0 +1,27 @@
+//! Helpers shared by the tests that run on the GPU.
+
+use gray_scott_reaction_diffusion::ReactionDiffusionSystem;
+
+/// A GPU system with Mitosis's rates, or `None` if there is no adapter to
+/// run it on, in which case the test should return early. Software adapters
+/// such as llvmpipe are enough.
+pub fn gpu_system(width: usize, height: usize) -> Option<ReactionDiffusionSystem> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    let has_adapter = instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .next()
+        .is_some();
+    // Dropping a GL instance tears down the EGL display that later instances
+    // share, so this one is kept alive
+    std::mem::forget(instance);
+    if !has_adapter {
+        eprintln!("No GPU adapter available, skipping");
+        return None;
+    }
+    Some(futures::executor::block_on(ReactionDiffusionSystem::new(
+        width, height, 0.0367, 0.0649, 0.2097, 0.105,
+    )))
+}
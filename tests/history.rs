@@ -0,0 +1,165 @@
+//! Checks that checkpoints taken on the GPU wind the grid back exactly, and
+//! that the ring of them honours its memory budget.
+
+mod common;
+
+use gray_scott_reaction_diffusion::{ReactionDiffusionSystem, UVPair};
+use std::collections::BTreeMap;
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 12;
+/// Bytes in one checkpoint of the grid.
+const CHECKPOINT_SIZE: u64 = (WIDTH * HEIGHT * std::mem::size_of::<UVPair>()) as u64;
+const INTERVAL: u64 = 5;
+
+/// A seeded system checkpointing every [`INTERVAL`] steps, with room for
+/// `capacity` checkpoints.
+fn system(capacity: u64) -> Option<ReactionDiffusionSystem> {
+    let mut system = common::gpu_system(WIDTH, HEIGHT)?;
+    let values: Vec<(f32, f32)> = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let (x, y) = (i % WIDTH, i / WIDTH);
+            if (5..10).contains(&x) && (4..8).contains(&y) {
+                (0.5, 0.25)
+            } else {
+                (1.0, 0.0)
+            }
+        })
+        .collect();
+    system.set_checkpoint_interval(INTERVAL);
+    system.set_history_budget(capacity * CHECKPOINT_SIZE);
+    // Seeding starts the history afresh, as it does in the app
+    system.set_all(&values);
+    system.set_seed(1);
+    Some(system)
+}
+
+/// Steps `system` up to `step_count`, keeping the grid at every checkpoint.
+fn run_to(
+    system: &mut ReactionDiffusionSystem,
+    step_count: u64,
+    grids: &mut BTreeMap<u64, Vec<UVPair>>,
+) {
+    while system.step_count() < step_count {
+        system.update();
+        if system.step_count().is_multiple_of(INTERVAL) {
+            grids.insert(system.step_count(), system.uvs().to_vec());
+        }
+    }
+}
+
+/// Rewinds and checks the grid against the one kept at `expected`.
+fn assert_rewinds_to(
+    system: &mut ReactionDiffusionSystem,
+    expected: u64,
+    grids: &BTreeMap<u64, Vec<UVPair>>,
+) {
+    assert!(system.rewind(), "Couldn't rewind to step {}", expected);
+    assert_eq!(system.step_count(), expected);
+    assert!(
+        system.uvs() == grids[&expected].as_slice(),
+        "The grid differs from step {}",
+        expected
+    );
+}
+
+#[test]
+fn rewind_restores_the_checkpointed_grid() {
+    let Some(mut system) = system(100) else {
+        return;
+    };
+    let mut grids = BTreeMap::from([(0, system.uvs().to_vec())]);
+    run_to(&mut system, 7, &mut grids);
+    assert_ne!(
+        system.uvs(),
+        grids[&5].as_slice(),
+        "The pattern isn't moving"
+    );
+
+    assert_rewinds_to(&mut system, 5, &grids);
+    // Stepping on from a rewound grid retraces the same path
+    run_to(&mut system, 6, &mut BTreeMap::new());
+    let replayed = system.uvs().to_vec();
+    assert_rewinds_to(&mut system, 5, &grids);
+    system.update();
+    assert!(system.uvs() == replayed.as_slice());
+}
+
+#[test]
+fn repeated_rewinds_walk_back_one_checkpoint_at_a_time() {
+    let Some(mut system) = system(100) else {
+        return;
+    };
+    let mut grids = BTreeMap::from([(0, system.uvs().to_vec())]);
+    run_to(&mut system, 17, &mut grids);
+    assert_eq!(system.history().len(), 4);
+
+    for expected in [15, 10, 5] {
+        assert_rewinds_to(&mut system, expected, &grids);
+    }
+    // The seeded grid is the oldest checkpoint, with nothing before it
+    assert_eq!(system.history().len(), 2);
+    assert_rewinds_to(&mut system, 0, &grids);
+    assert!(!system.rewind());
+    assert_eq!(system.step_count(), 0);
+    assert!(system.uvs() == grids[&0].as_slice());
+}
+
+#[test]
+fn capacity_follows_the_budget() {
+    let Some(mut system) = system(3) else {
+        return;
+    };
+    assert_eq!(system.history().capacity(), 3);
+    system.set_history_budget(3 * CHECKPOINT_SIZE - 1);
+    assert_eq!(system.history().capacity(), 2);
+    assert_eq!(
+        system.history().len(),
+        1,
+        "The current grid is checkpointed"
+    );
+
+    // Too small for a single checkpoint, so there is nothing to go back to
+    system.set_history_budget(CHECKPOINT_SIZE - 1);
+    assert_eq!(system.history().capacity(), 0);
+    run_to(&mut system, 12, &mut BTreeMap::new());
+    assert!(system.history().is_empty());
+    assert!(!system.rewind());
+    assert_eq!(system.step_count(), 12);
+}
+
+#[test]
+fn full_ring_reuses_the_oldest_checkpoints() {
+    let Some(mut system) = system(3) else {
+        return;
+    };
+    let mut grids = BTreeMap::new();
+    run_to(&mut system, 32, &mut grids);
+    assert_eq!(system.history().len(), 3);
+
+    // Checkpoints 20 to 30 took over the buffers of the earlier ones
+    for expected in [30, 25, 20] {
+        assert_rewinds_to(&mut system, expected, &grids);
+    }
+    assert!(!system.rewind());
+}
+
+#[test]
+fn recording_after_a_rewind_drops_the_newer_checkpoints() {
+    let Some(mut system) = system(100) else {
+        return;
+    };
+    let mut grids = BTreeMap::new();
+    run_to(&mut system, 17, &mut grids);
+    assert_rewinds_to(&mut system, 15, &grids);
+    assert_rewinds_to(&mut system, 10, &grids);
+
+    // A different path from step 10 replaces the checkpoint at 15
+    system.update_rates(0.03, 0.055);
+    let old_15 = grids.remove(&15).unwrap();
+    run_to(&mut system, 17, &mut grids);
+    assert_ne!(grids[&15], old_15);
+    assert_eq!(system.history().len(), 4);
+    assert_rewinds_to(&mut system, 15, &grids);
+    assert_rewinds_to(&mut system, 10, &grids);
+}
@@ -0,0 +1,128 @@
+//! Checks that the GPU evaluates nutrient graphs the same way as
+//! `NutrientGraph::factors` on the CPU.
+
+mod common;
+
+use gray_scott_reaction_diffusion::readback::{Readback, ReadbackHandle};
+use gray_scott_reaction_diffusion::{
+    NutrientField, NutrientGraph, NutrientMap, NutrientPattern, ReactionDiffusionSystem,
+};
+
+const WIDTH: usize = 96;
+const HEIGHT: usize = 64;
+/// Largest difference allowed between the two, which leaves room for the
+/// GPU's trigonometry but not for a pattern that is laid out differently.
+const TOLERANCE: f32 = 1e-4;
+
+fn assert_factors_match(graph: NutrientGraph, steps: u64) {
+    let Some(mut system) = common::gpu_system(WIDTH, HEIGHT) else {
+        return;
+    };
+    system.set_nutrient_graph(graph.clone()).unwrap();
+    for _ in 0..steps {
+        system.update();
+    }
+
+    let gpu = system.read_nutrient_field().factors;
+    let cpu = graph.factors(WIDTH, HEIGHT, system.simulated_time() as f32);
+    assert_eq!(gpu.len(), cpu.len());
+    for (i, (gpu, cpu)) in gpu.iter().zip(&cpu).enumerate() {
+        assert!(
+            (gpu - cpu).abs() <= TOLERANCE,
+            "{} at ({}, {}) is {} on the GPU but {} on the CPU",
+            graph,
+            i % WIDTH,
+            i / WIDTH,
+            gpu,
+            cpu
+        );
+    }
+}
+
+/// A small map, smaller than the grid in one direction and larger in the
+/// other, so that both enlarging and shrinking are exercised.
+fn gradient_map() -> NutrientMap {
+    let (width, height) = (12, 80);
+    let values = (0..width * height)
+        .map(|i| ((i % width) as f32 / width as f32 + (i / width) as f32 / height as f32) / 2.0)
+        .collect();
+    NutrientMap::from_values(width, height, values).unwrap()
+}
+
+#[test]
+fn every_pattern_matches() {
+    for pattern in NutrientPattern::all() {
+        assert_factors_match(NutrientGraph::pattern(pattern), 0);
+    }
+}
+
+#[test]
+fn blended_graph_with_an_image_matches() {
+    let graph = (NutrientGraph::image(gradient_map()) * NutrientPattern::Noise.into()).blend(
+        NutrientPattern::WaveFunction,
+        NutrientGraph::pattern(NutrientPattern::RadialGradient).max(NutrientPattern::Checkerboard),
+    );
+    assert_factors_match(graph, 0);
+}
+
+#[test]
+fn animated_graph_matches_after_stepping() {
+    let graph = NutrientGraph::pattern(NutrientPattern::ScrollingNoise)
+        + NutrientGraph::pattern(NutrientPattern::RotatingRadialGradient)
+            .min(NutrientPattern::PulsingRings);
+    assert_factors_match(graph, 25);
+}
+
+fn wait(
+    system: &mut ReactionDiffusionSystem,
+    mut handle: ReadbackHandle<NutrientField>,
+) -> NutrientField {
+    loop {
+        match system.poll_nutrient_field(handle) {
+            Readback::Ready(field) => return field,
+            Readback::Pending(pending) => handle = pending,
+            Readback::Failed(e) => panic!("Nutrient field readback failed: {}", e),
+        }
+    }
+}
+
+#[test]
+fn static_field_is_only_read_back_when_it_changes() {
+    let Some(mut system) = common::gpu_system(WIDTH, HEIGHT) else {
+        return;
+    };
+    let handle = system.request_nutrient_field().unwrap();
+    assert!(wait(&mut system, handle).factors.iter().all(|&f| f == 1.0));
+
+    system.update();
+    assert!(system.request_nutrient_field().is_none());
+
+    system
+        .set_nutrient_graph(NutrientGraph::pattern(NutrientPattern::RadialGradient))
+        .unwrap();
+    let handle = system.request_nutrient_field().unwrap();
+    let field = wait(&mut system, handle);
+    assert_eq!((field.width, field.height), (WIDTH, HEIGHT));
+    assert!(system.request_nutrient_field().is_none());
+}
+
+#[test]
+fn animated_field_is_read_back_after_each_step() {
+    let Some(mut system) = common::gpu_system(WIDTH, HEIGHT) else {
+        return;
+    };
+    system
+        .set_nutrient_graph(NutrientGraph::pattern(NutrientPattern::MovingStripes))
+        .unwrap();
+    let handle = system.request_nutrient_field().unwrap();
+    let before = wait(&mut system, handle);
+    assert!(system.request_nutrient_field().is_none());
+
+    for _ in 0..100 {
+        system.update();
+    }
+    let handle = system.request_nutrient_field().unwrap();
+    let after = wait(&mut system, handle);
+    assert_eq!(after.step_count, 100);
+    assert_ne!(before.factors, after.factors);
+}
@@ -0,0 +1,129 @@
+//! Runs each built-in preset on a small grid on the CPU and checks that it
+//! ends up doing what its description says.
+
+use gray_scott_reaction_diffusion::CpuReactionDiffusionSystem;
+use gray_scott_reaction_diffusion::convergence::ConvergenceMonitor;
+use gray_scott_reaction_diffusion::model_presets::{self, Preset, PresetRegistry};
+use gray_scott_reaction_diffusion::phase_diagram::{self, PhaseOutcome};
+use gray_scott_reaction_diffusion::seeders;
+
+/// Grid size for presets that don't need room to develop.
+const GRID_SIZE: usize = 64;
+const MAX_STEPS: u64 = 6000;
+const SEED: u64 = 1;
+/// Seeder used by presets that don't recommend one.
+const DEFAULT_SEEDER: &str = "Noise";
+/// Root mean square change per step above which a pattern is still moving
+/// when the run ends. Settled patterns stay below about 8e-4 and moving ones
+/// above about 1.2e-3.
+const WAVE_CHANGE: f32 = 1e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Dies,
+    /// Separate spots that have settled.
+    Spots,
+    /// Stripes, labyrinths or a sheet with holes through it that have settled.
+    Stripes,
+    /// A pattern that is still moving, whether travelling waves, spots that
+    /// keep dividing and dying or chaos.
+    Waves,
+    /// Survived without forming any pattern.
+    Uniform,
+}
+
+/// Runs `preset` on a `grid_size` square grid and classifies it the way the
+/// phase diagram does, telling apart patterns that are still moving.
+fn outcome(preset: &Preset, grid_size: usize) -> Outcome {
+    let seeders = seeders::builtin();
+    let seeder_name = preset.seeder.as_deref().unwrap_or(DEFAULT_SEEDER);
+    let seeder = seeders::position_by_name(&seeders, seeder_name)
+        .map(|index| &seeders[index])
+        .unwrap_or_else(|| panic!("{} has unknown seeder {}", preset.name, seeder_name));
+
+    let mut system = CpuReactionDiffusionSystem::new(
+        grid_size,
+        grid_size,
+        preset.feed_rate,
+        preset.kill_rate,
+        preset.delta_u,
+        preset.delta_v,
+    );
+    system.set_all(&seeder.generate(grid_size, grid_size, SEED));
+    let mut monitor = ConvergenceMonitor::new();
+    let event = system.run_until_converged(MAX_STEPS, &mut monitor);
+
+    let (outcome, _) = phase_diagram::classify(&system.field(), event, &monitor);
+    if outcome == PhaseOutcome::DiedOut {
+        return Outcome::Dies;
+    }
+    if system.change_sample().rms_change() > WAVE_CHANGE {
+        return Outcome::Waves;
+    }
+    match outcome {
+        PhaseOutcome::DiedOut => Outcome::Dies,
+        PhaseOutcome::Uniform => Outcome::Uniform,
+        PhaseOutcome::Spots => Outcome::Spots,
+        PhaseOutcome::Stripes | PhaseOutcome::Labyrinth => Outcome::Stripes,
+    }
+}
+
+macro_rules! preset_tests {
+    ($($test:ident: $preset:ident => $expected:ident $(on $grid_size:literal)?,)*) => {
+        $(
+            #[test]
+            fn $test() {
+                let grid_size = [$($grid_size,)? GRID_SIZE][0];
+                assert_eq!(
+                    outcome(&model_presets::$preset, grid_size),
+                    Outcome::$expected
+                );
+            }
+        )*
+
+        /// Every built-in preset other than Custom needs a test above.
+        #[test]
+        fn every_preset_is_tested() {
+            let tested = [$(model_presets::$preset.name),*];
+            for preset in &PresetRegistry::builtin() {
+                assert!(
+                    preset.name == model_presets::CUSTOM.name || tested.contains(&preset.name),
+                    "{} has no outcome test",
+                    preset.name
+                );
+            }
+        }
+    };
+}
+
+preset_tests! {
+    brain_coral: BRAIN_CORAL => Stripes,
+    fingerprint: FINGERPRINT => Stripes,
+    mitosis: MITOSIS => Spots,
+    ripples: RIPPLES => Waves,
+    soliton_collapse: SOLITON_COLLAPSE => Waves on 128,
+    u_skate_world: U_SKATE_WORLD => Stripes,
+    undulating: UNDULATING => Waves on 96,
+    worms: WORMS => Stripes on 96,
+    pearson_alpha: PEARSON_ALPHA => Waves on 128,
+    pearson_gamma: PEARSON_GAMMA => Waves on 96,
+    pearson_delta: PEARSON_DELTA => Stripes,
+    pearson_epsilon: PEARSON_EPSILON => Waves on 96,
+    pearson_zeta: PEARSON_ZETA => Spots on 128,
+    pearson_eta: PEARSON_ETA => Spots,
+    pearson_theta: PEARSON_THETA => Stripes,
+    pearson_iota: PEARSON_IOTA => Stripes,
+    pearson_kappa: PEARSON_KAPPA => Stripes,
+    pearson_lambda: PEARSON_LAMBDA => Spots,
+    pearson_mu: PEARSON_MU => Stripes,
+    pearson_nu: PEARSON_NU => Spots,
+    pearson_xi: PEARSON_XI => Waves on 192,
+    pearson_rho: PEARSON_RHO => Stripes,
+    pearson_sigma: PEARSON_SIGMA => Uniform,
+    solitons: SOLITONS => Spots,
+    pulsating_solitons: PULSATING_SOLITONS => Spots on 96,
+    mazes: MAZES => Stripes,
+    holes: HOLES => Stripes,
+    chaos_and_holes: CHAOS_AND_HOLES => Waves on 96,
+    moving_spots: MOVING_SPOTS => Waves,
+}
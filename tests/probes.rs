@@ -0,0 +1,231 @@
+//! Checks that probes and region readbacks see the same values as the whole
+//! grid, and the CSV written from probe series.
+
+mod common;
+
+use gray_scott_reaction_diffusion::probes::{self, Probe, ProbeSeries};
+use gray_scott_reaction_diffusion::{ReactionDiffusionSystem, Region, ResampleMode, UVPair};
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 12;
+
+/// A system seeded with a block of V, stepped a little so every cell differs.
+fn seeded_system() -> Option<ReactionDiffusionSystem> {
+    let mut system = common::gpu_system(WIDTH, HEIGHT)?;
+    let values: Vec<(f32, f32)> = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let (x, y) = (i % WIDTH, i / WIDTH);
+            let v = if (4..9).contains(&x) && (3..7).contains(&y) {
+                0.25
+            } else {
+                0.0
+            };
+            (1.0 - i as f32 / 1000.0, v)
+        })
+        .collect();
+    system.set_all(&values);
+    for _ in 0..3 {
+        system.update();
+    }
+    Some(system)
+}
+
+/// The cell at `(x, y)` of the current grid, read back whole.
+fn cell(system: &mut ReactionDiffusionSystem, x: usize, y: usize) -> UVPair {
+    system.read_field().get(x as isize, y as isize)
+}
+
+fn series(name: &str, u: [f32; 2], v: [f32; 2]) -> ProbeSeries {
+    ProbeSeries {
+        probe: Probe {
+            name: name.to_string(),
+            x: 0,
+            y: 0,
+        },
+        steps: vec![10, 11],
+        u: u.to_vec(),
+        v: v.to_vec(),
+    }
+}
+
+fn csv(series: &[ProbeSeries]) -> String {
+    let mut bytes = Vec::new();
+    probes::write_csv(series, &mut bytes).unwrap();
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn writes_a_row_per_step() {
+    let text = csv(&[
+        series("centre", [1.0, 0.5], [0.0, 0.25]),
+        series("edge", [0.75, 0.5], [0.125, 0.5]),
+    ]);
+    assert_eq!(
+        text,
+        "step,centre_u,centre_v,edge_u,edge_v\n10,1,0,0.75,0.125\n11,0.5,0.25,0.5,0.5\n"
+    );
+}
+
+#[test]
+fn quotes_names_with_commas_quotes_and_line_breaks() {
+    let text = csv(&[
+        series("a,b", [1.0, 1.0], [0.0, 0.0]),
+        series("say \"hi\"", [1.0, 1.0], [0.0, 0.0]),
+        series("two\nlines", [1.0, 1.0], [0.0, 0.0]),
+    ]);
+    let header = text.split("\n10,").next().unwrap();
+    assert_eq!(
+        header,
+        "step,\"a,b_u\",\"a,b_v\",\"say \"\"hi\"\"_u\",\"say \"\"hi\"\"_v\",\
+         \"two\nlines_u\",\"two\nlines_v\""
+    );
+    assert!(
+        text.ends_with("\n10,1,0,1,0,1,0\n11,1,0,1,0,1,0\n"),
+        "{}",
+        text
+    );
+}
+
+#[test]
+fn writes_only_the_header_without_series() {
+    assert_eq!(csv(&[]), "step\n");
+}
+
+#[test]
+fn read_region_matches_the_whole_grid() {
+    let Some(mut system) = seeded_system() else {
+        return;
+    };
+    let field = system.read_field();
+    for region in [
+        Region {
+            x: 0,
+            y: 0,
+            width: WIDTH,
+            height: HEIGHT,
+        },
+        Region {
+            x: 3,
+            y: 2,
+            width: 7,
+            height: 5,
+        },
+        Region {
+            x: WIDTH - 1,
+            y: HEIGHT - 1,
+            width: 1,
+            height: 1,
+        },
+        Region {
+            x: 0,
+            y: 5,
+            width: WIDTH,
+            height: 2,
+        },
+        Region {
+            x: 9,
+            y: 0,
+            width: 4,
+            height: HEIGHT,
+        },
+    ] {
+        let read = system.read_region(region);
+        assert_eq!(read, field.crop(region), "{:?}", region);
+        assert_eq!(read.step_count, system.step_count());
+    }
+    assert_eq!(field.as_slice(), system.uvs());
+}
+
+#[test]
+fn probes_sample_every_step() {
+    let Some(mut system) = seeded_system() else {
+        return;
+    };
+    let cells = [(5, 4), (0, 0), (WIDTH - 1, HEIGHT - 1)];
+    for (i, &(x, y)) in cells.iter().enumerate() {
+        system.add_probe(format!("probe {}", i), x, y);
+    }
+
+    let mut expected = vec![Vec::new(); cells.len()];
+    for _ in 0..6 {
+        system.update();
+        for (values, &(x, y)) in expected.iter_mut().zip(&cells) {
+            values.push(cell(&mut system, x, y));
+        }
+    }
+
+    let series = system.take_probe_series();
+    assert_eq!(series.len(), cells.len());
+    for ((s, values), &(x, y)) in series.iter().zip(&expected).zip(&cells) {
+        assert_eq!((s.probe.x, s.probe.y), (x, y));
+        assert_eq!(s.steps, (4..=9).collect::<Vec<u64>>());
+        assert_eq!(s.u, values.iter().map(|uv| uv.u).collect::<Vec<_>>());
+        assert_eq!(s.v, values.iter().map(|uv| uv.v).collect::<Vec<_>>());
+    }
+    assert!(
+        system.take_probe_series().is_empty(),
+        "Samples were taken twice"
+    );
+}
+
+#[test]
+fn full_ring_keeps_the_newest_samples() {
+    let Some(mut system) = seeded_system() else {
+        return;
+    };
+    system.set_probe_capacity(4);
+    system.add_probe("centre", 6, 5);
+
+    let mut expected = Vec::new();
+    for _ in 0..10 {
+        system.update();
+        expected.push(cell(&mut system, 6, 5));
+    }
+    let series = system.take_probe_series();
+    assert_eq!(series[0].steps, vec![10, 11, 12, 13]);
+    assert_eq!(
+        series[0].v,
+        expected[6..].iter().map(|uv| uv.v).collect::<Vec<_>>()
+    );
+
+    // The ring starts over after being read
+    for _ in 0..2 {
+        system.update();
+        expected.push(cell(&mut system, 6, 5));
+    }
+    let series = system.take_probe_series();
+    assert_eq!(series[0].steps, vec![14, 15]);
+    assert_eq!(
+        series[0].u,
+        expected[10..].iter().map(|uv| uv.u).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn resize_moves_probes_to_the_same_relative_cell() {
+    let Some(mut system) = seeded_system() else {
+        return;
+    };
+    system.add_probe("centre", 6, 5);
+    system.add_probe("corner", WIDTH - 1, 0);
+    system.update();
+
+    system.resize(2 * WIDTH, 2 * HEIGHT, ResampleMode::Bilinear);
+    let moved: Vec<(usize, usize)> = system.probes().iter().map(|p| (p.x, p.y)).collect();
+    assert_eq!(moved, [(12, 10), (2 * WIDTH - 2, 0)]);
+    assert!(
+        system.take_probe_series().is_empty(),
+        "Samples from before the resize were kept"
+    );
+
+    system.update();
+    let expected: Vec<UVPair> = moved
+        .iter()
+        .map(|&(x, y)| cell(&mut system, x, y))
+        .collect();
+    let series = system.take_probe_series();
+    for (s, uv) in series.iter().zip(&expected) {
+        assert_eq!(s.steps, vec![system.step_count()]);
+        assert_eq!((s.u[0], s.v[0]), (uv.u, uv.v), "{}", s.probe.name);
+    }
+}
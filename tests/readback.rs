@@ -0,0 +1,151 @@
+//! Checks the non-blocking readbacks: that they agree with the blocking ones,
+//! how many may be in flight, that their staging buffers are reused and that
+//! a handle is only accepted by the readback it was issued for.
+
+mod common;
+
+use gray_scott_reaction_diffusion::readback::{MAX_PENDING_READBACKS, Readback, ReadbackHandle};
+use gray_scott_reaction_diffusion::{Field, ReactionDiffusionSystem, ResampleMode};
+use std::mem::ManuallyDrop;
+use std::panic::{self, AssertUnwindSafe};
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 12;
+
+/// A system seeded with a block of V, stepped a little so every cell differs.
+fn seeded_system() -> Option<ReactionDiffusionSystem> {
+    let mut system = common::gpu_system(WIDTH, HEIGHT)?;
+    let values: Vec<(f32, f32)> = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let (x, y) = (i % WIDTH, i / WIDTH);
+            let v = if (4..9).contains(&x) && (3..7).contains(&y) {
+                0.25
+            } else {
+                0.0
+            };
+            (1.0 - i as f32 / 1000.0, v)
+        })
+        .collect();
+    system.set_all(&values);
+    for _ in 0..3 {
+        system.update();
+    }
+    Some(system)
+}
+
+fn wait(system: &mut ReactionDiffusionSystem, mut handle: ReadbackHandle) -> Field {
+    loop {
+        match system.poll_readback(handle) {
+            Readback::Ready(field) => return field,
+            Readback::Pending(pending) => handle = pending,
+            Readback::Failed(e) => panic!("Readback failed: {}", e),
+        }
+    }
+}
+
+#[test]
+fn polled_field_matches_the_blocking_readback() {
+    let Some(mut system) = seeded_system() else {
+        return;
+    };
+    let handle = system.request_readback().unwrap();
+    let field = wait(&mut system, handle);
+    assert_eq!((field.width, field.height), (WIDTH, HEIGHT));
+    assert_eq!(field.step_count, system.step_count());
+    assert_eq!(field.as_slice(), system.uvs());
+    assert_eq!(field, system.read_field());
+}
+
+#[test]
+fn readback_keeps_the_step_it_was_requested_at() {
+    let Some(mut system) = seeded_system() else {
+        return;
+    };
+    let expected = system.read_field();
+    let handle = system.request_readback().unwrap();
+    for _ in 0..5 {
+        system.update();
+    }
+    let field = wait(&mut system, handle);
+    assert_eq!(field, expected);
+    assert_ne!(field.as_slice(), system.uvs());
+}
+
+#[test]
+fn only_max_pending_readbacks_are_in_flight() {
+    let Some(mut system) = seeded_system() else {
+        return;
+    };
+    let mut handles: Vec<ReadbackHandle> = (0..MAX_PENDING_READBACKS)
+        .map(|_| system.request_readback().expect("Ring filled early"))
+        .collect();
+    assert!(system.request_readback().is_none());
+
+    // The blocking readbacks don't count against the limit
+    let expected = system.read_field();
+    assert!(system.request_readback().is_none());
+
+    // Completing one frees a place for another
+    let first = handles.remove(0);
+    assert_eq!(wait(&mut system, first), expected);
+    handles.push(system.request_readback().unwrap());
+    assert!(system.request_readback().is_none());
+    for handle in handles {
+        assert_eq!(wait(&mut system, handle), expected);
+    }
+}
+
+#[test]
+fn staging_buffers_are_reused() {
+    let Some(mut system) = seeded_system() else {
+        return;
+    };
+    // Many more readbacks than the ring holds, each seeing its own step
+    for _ in 0..MAX_PENDING_READBACKS * 4 {
+        let handles: Vec<ReadbackHandle> = (0..MAX_PENDING_READBACKS)
+            .map(|_| system.request_readback().unwrap())
+            .collect();
+        let expected = system.read_field();
+        for handle in handles {
+            assert_eq!(wait(&mut system, handle), expected);
+        }
+        system.update();
+    }
+
+    // A readback started before a resize keeps the old size, and the buffer
+    // it used is replaced for the new one
+    let before = system.read_field();
+    let pending = system.request_readback().unwrap();
+    system.resize(WIDTH * 2, HEIGHT + 3, ResampleMode::Nearest);
+    assert_eq!(wait(&mut system, pending), before);
+    for _ in 0..MAX_PENDING_READBACKS {
+        let handle = system.request_readback().unwrap();
+        let field = wait(&mut system, handle);
+        assert_eq!((field.width, field.height), (WIDTH * 2, HEIGHT + 3));
+        assert_eq!(field.as_slice(), system.uvs());
+    }
+}
+
+#[test]
+fn stale_handle_is_rejected() {
+    // Neither system is dropped, since tearing down one GL device breaks the
+    // other's display
+    let Some(system) = seeded_system() else {
+        return;
+    };
+    let mut system = ManuallyDrop::new(system);
+    let mut other = ManuallyDrop::new(common::gpu_system(WIDTH, HEIGHT).unwrap());
+
+    // The other system reuses its first staging buffer for a later readback,
+    // so the first handle from this one names a slot whose generation has
+    // moved on
+    let first = other.request_readback().unwrap();
+    wait(&mut other, first);
+    let second = other.request_readback().unwrap();
+    let stale = system.request_readback().unwrap();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| other.poll_readback(stale)));
+    assert!(result.is_err(), "A stale handle was accepted");
+
+    // The rejection leaves the readback that does own the slot intact
+    assert_eq!(wait(&mut other, second), other.read_field());
+}
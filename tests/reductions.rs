@@ -0,0 +1,98 @@
+//! Checks that the GPU reductions agree with `FieldStatistics::from_field` and
+//! `ChangeSample::between` on the CPU.
+
+mod common;
+
+use gray_scott_reaction_diffusion::ReactionDiffusionSystem;
+use gray_scott_reaction_diffusion::convergence::ChangeSample;
+use gray_scott_reaction_diffusion::statistics::{FieldStatistics, SpeciesStatistics};
+
+/// Larger than the reductions' 64 workgroups of 256 threads, so that threads
+/// visit several cells.
+const WIDTH: usize = 160;
+const HEIGHT: usize = 120;
+
+/// A system growing a pattern from a few offset discs.
+fn growing_system() -> Option<ReactionDiffusionSystem> {
+    let mut system = common::gpu_system(WIDTH, HEIGHT)?;
+    let values: Vec<(f32, f32)> = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let (x, y) = ((i % WIDTH) as f32, (i / WIDTH) as f32);
+            let seeded = [(40.0, 30.0), (100.0, 80.0), (130.0, 20.0)]
+                .iter()
+                .any(|&(cx, cy)| (x - cx).hypot(y - cy) < 8.0);
+            if seeded { (0.5, 0.25) } else { (1.0, 0.0) }
+        })
+        .collect();
+    system.set_all(&values);
+    for _ in 0..25 {
+        system.update();
+    }
+    Some(system)
+}
+
+fn assert_close(name: &str, gpu: f32, cpu: f32, tolerance: f32) {
+    assert!(
+        (gpu - cpu).abs() <= tolerance * cpu.abs().max(1.0),
+        "{} is {} on the GPU but {} on the CPU",
+        name,
+        gpu,
+        cpu
+    );
+}
+
+fn assert_species_match(name: &str, gpu: &SpeciesStatistics, cpu: &SpeciesStatistics) {
+    assert_close(&format!("{} mean", name), gpu.mean, cpu.mean, 1e-5);
+    assert_close(
+        &format!("{} variance", name),
+        gpu.variance,
+        cpu.variance,
+        1e-4,
+    );
+    assert_close(
+        &format!("{} total mass", name),
+        gpu.total_mass,
+        cpu.total_mass,
+        1e-5,
+    );
+    assert_eq!(gpu.min, cpu.min, "{} min", name);
+    assert_eq!(gpu.max, cpu.max, "{} max", name);
+    assert_eq!(gpu.histogram, cpu.histogram, "{} histogram", name);
+}
+
+#[test]
+fn statistics_match_the_cpu() {
+    let Some(mut system) = growing_system() else {
+        return;
+    };
+    let gpu = system.compute_statistics().unwrap();
+    let cpu = FieldStatistics::from_field(&system.read_field());
+
+    assert_eq!(gpu.step_count, cpu.step_count);
+    assert_eq!(gpu.cell_count, cpu.cell_count);
+    assert_species_match("U", &gpu.u, &cpu.u);
+    assert_species_match("V", &gpu.v, &cpu.v);
+}
+
+#[test]
+fn change_sample_matches_the_cpu() {
+    let Some(mut system) = growing_system() else {
+        return;
+    };
+    system.set_convergence_interval(Some(1));
+    let before = system.read_field();
+    system.update();
+    let after = system.read_field();
+
+    let gpu = (0..1000)
+        .find_map(|_| system.take_change_sample())
+        .expect("The change sample never arrived");
+    let cpu = ChangeSample::between(before.as_slice(), after.as_slice(), after.step_count);
+
+    assert_eq!(gpu.step_count, cpu.step_count);
+    assert_eq!(gpu.cell_count, cpu.cell_count);
+    assert!(cpu.l2_norm > 0.0, "The pattern isn't changing");
+    assert_close("L2 norm", gpu.l2_norm, cpu.l2_norm, 1e-4);
+    assert_close("Mean V", gpu.mean_v, cpu.mean_v, 1e-5);
+    assert_eq!(gpu.max_v, cpu.max_v);
+}
@@ -0,0 +1,137 @@
+//! Checks nearest and bilinear resampling against small fields worked out by
+//! hand, and that a resized GPU system carries on from the resampled grid.
+
+mod common;
+
+use gray_scott_reaction_diffusion::{
+    CpuReactionDiffusionSystem, Field, NutrientGraph, NutrientPattern, ResampleMode, UVPair,
+};
+
+/// A field whose U is `u(x, y)` and whose V is a quarter of it.
+fn field(width: usize, height: usize, u: impl Fn(usize, usize) -> f32) -> Field {
+    let data = (0..width * height)
+        .map(|i| {
+            let u = u(i % width, i / width);
+            UVPair { u, v: u / 4.0 }
+        })
+        .collect();
+    Field::new(width, height, 42, data)
+}
+
+fn us(field: &Field) -> Vec<f32> {
+    field.u_values().collect()
+}
+
+/// A single row counting up from 0.
+fn ramp() -> Field {
+    field(4, 1, |x, _| x as f32)
+}
+
+#[test]
+fn nearest_repeats_and_skips_cells() {
+    let up = ramp().resample(8, 1, ResampleMode::Nearest);
+    assert_eq!(us(&up), [0.0, 0.0, 1.0, 1.0, 2.0, 2.0, 3.0, 3.0]);
+    assert_eq!((up.width, up.height, up.step_count), (8, 1, 42));
+
+    // Each output cell sits between two input cells, and rounds away from 0
+    let down = ramp().resample(2, 1, ResampleMode::Nearest);
+    assert_eq!(us(&down), [1.0, 3.0]);
+    let tall = ramp().resample(4, 3, ResampleMode::Nearest);
+    assert_eq!(us(&tall), [0.0, 1.0, 2.0, 3.0].repeat(3));
+}
+
+#[test]
+fn bilinear_blends_neighbours_and_wraps_at_the_edges() {
+    // The outer cells blend with the opposite edge of the ramp
+    let up = ramp().resample(8, 1, ResampleMode::Bilinear);
+    assert_eq!(us(&up), [0.75, 0.25, 0.75, 1.25, 1.75, 2.25, 2.75, 2.25]);
+    assert!(up.as_slice().iter().all(|uv| uv.v == uv.u / 4.0));
+
+    let down = ramp().resample(2, 1, ResampleMode::Bilinear);
+    assert_eq!(us(&down), [0.5, 2.5]);
+}
+
+#[test]
+fn bilinear_works_along_both_axes() {
+    let small = field(2, 2, |x, y| (x + 2 * y) as f32);
+    let up = small.resample(4, 4, ResampleMode::Bilinear);
+    // Each axis wraps a 0, 1 ramp into 0.25, 0.25, 0.75, 0.75
+    let along = [0.25, 0.25, 0.75, 0.75];
+    for y in 0..4 {
+        for x in 0..4 {
+            let expected = along[x] + 2.0 * along[y];
+            assert_eq!(up.get(x as isize, y as isize).u, expected, "({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn same_size_and_uniform_fields_are_unchanged() {
+    let pattern = field(5, 3, |x, y| (x * 7 + y * 3) as f32 / 10.0);
+    for mode in [ResampleMode::Nearest, ResampleMode::Bilinear] {
+        assert_eq!(pattern.resample(5, 3, mode), pattern);
+        let uniform = field(5, 3, |_, _| 0.5);
+        for (width, height) in [(1, 1), (2, 7), (11, 4)] {
+            let resampled = uniform.resample(width, height, mode);
+            assert_eq!(resampled, field(width, height, |_, _| 0.5), "{:?}", mode);
+        }
+    }
+}
+
+#[test]
+fn resized_system_steps_on_from_the_resampled_grid() {
+    const WIDTH: usize = 16;
+    const HEIGHT: usize = 12;
+    let Some(mut system) = common::gpu_system(WIDTH, HEIGHT) else {
+        return;
+    };
+    let graph = NutrientGraph::pattern(NutrientPattern::RadialGradient);
+    system.set_nutrient_graph(graph.clone()).unwrap();
+    let values: Vec<(f32, f32)> = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let (x, y) = (i % WIDTH, i / WIDTH);
+            if (3..8).contains(&x) && (2..6).contains(&y) {
+                (0.5, 0.25)
+            } else {
+                (1.0, 0.0)
+            }
+        })
+        .collect();
+    system.set_all(&values);
+    for _ in 0..10 {
+        system.update();
+    }
+    let before = system.read_field();
+
+    // Grow along one axis and shrink along the other
+    let (width, height) = (27, 9);
+    system.resize(width, height, ResampleMode::Bilinear);
+    let resampled = before.resample(width, height, ResampleMode::Bilinear);
+    assert_eq!(system.read_field(), resampled);
+
+    let mut cpu = CpuReactionDiffusionSystem::new(width, height, 0.0367, 0.0649, 0.2097, 0.105);
+    cpu.set_nutrient_graph(graph).unwrap();
+    let resampled_values: Vec<(f32, f32)> =
+        resampled.as_slice().iter().map(|uv| (uv.u, uv.v)).collect();
+    cpu.set_all(&resampled_values);
+    system.update();
+    cpu.update();
+
+    let gpu = system.read_field();
+    assert_eq!(gpu.step_count, 11);
+    assert_ne!(
+        gpu.as_slice(),
+        resampled.as_slice(),
+        "The step changed nothing"
+    );
+    for (i, (gpu, cpu)) in gpu.as_slice().iter().zip(cpu.uvs()).enumerate() {
+        assert!(
+            (gpu.u - cpu.u).abs() < 1e-5 && (gpu.v - cpu.v).abs() < 1e-5,
+            "({}, {}) is {:?} on the GPU but {:?} on the CPU",
+            i % width,
+            i / width,
+            gpu,
+            cpu
+        );
+    }
+}
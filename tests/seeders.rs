@@ -0,0 +1,81 @@
+//! Checks that every seeder copes with empty grids and that seeder specs set
+//! their parameters.
+
+use gray_scott_reaction_diffusion::seeders::{self, SeederSpec};
+
+#[test]
+fn seeders_handle_empty_grids() {
+    for seeder in seeders::builtin() {
+        for (width, height) in [(0, 0), (0, 12), (12, 0), (1, 1)] {
+            let values = seeder.generate(width, height, 3);
+            assert_eq!(values.len(), width * height, "{}", seeder.name());
+        }
+    }
+}
+
+#[test]
+fn every_parameter_can_be_set() {
+    let mut seeders = seeders::builtin();
+    for seeder in &mut seeders {
+        for &parameter in seeder.parameters() {
+            let value = if parameter == "count" { "3" } else { "0.5" };
+            seeder
+                .set_parameter(parameter, value)
+                .unwrap_or_else(|e| panic!("{}: {}", seeder.name(), e));
+        }
+        assert_eq!(seeder.generate(16, 16, 3).len(), 16 * 16);
+    }
+}
+
+#[test]
+fn spec_sets_parameters_on_the_named_seeder() {
+    let spec = SeederSpec::parse("random-circles: count = 1, max_radius=3,MinRadius=3").unwrap();
+    assert_eq!(spec.name, "random-circles");
+    assert_eq!(spec.parameters.len(), 3);
+
+    let mut seeders = seeders::builtin();
+    let index = spec.apply(&mut seeders).unwrap();
+    assert_eq!(seeders[index].name(), "Random Circles");
+    // One circle of radius 3 covers the 29 cells within 3 of its centre, give
+    // or take the cells its off-grid centre brings in or leaves out
+    let seeded = seeders[index]
+        .generate(32, 32, 5)
+        .iter()
+        .filter(|&&value| value == seeders::SEEDED)
+        .count();
+    assert!((20..=36).contains(&seeded), "{} cells seeded", seeded);
+
+    let plain = SeederSpec::parse("Center Disc").unwrap();
+    assert!(plain.parameters.is_empty());
+    let index = plain.apply(&mut seeders).unwrap();
+    assert_eq!(seeders[index].name(), "Center Disc");
+}
+
+#[test]
+fn rejects_bad_specs() {
+    for (spec, expected) in [
+        (":count=1", "seeder name is empty"),
+        ("lines:count", "`count` is not name=value"),
+    ] {
+        assert_eq!(SeederSpec::parse(spec).unwrap_err(), expected);
+    }
+
+    let mut seeders = seeders::builtin();
+    for (spec, expected) in [
+        ("sprinkles", "unknown seeder `sprinkles`"),
+        (
+            "lines:colour=red",
+            "Lines has no parameter `colour`, expected count, angle, thickness",
+        ),
+        ("noise:density=2", "`2` is not between 0 and 1"),
+        ("dot-grid:radius=-1", "`-1` is negative"),
+        ("perlin-noise:threshold=NaN", "`NaN` is not a number"),
+        ("random-circles:count=1.5", "`1.5` is not a whole number"),
+    ] {
+        let error = SeederSpec::parse(spec)
+            .unwrap()
+            .apply(&mut seeders)
+            .unwrap_err();
+        assert_eq!(error, expected, "{}", spec);
+    }
+}
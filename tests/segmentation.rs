@@ -0,0 +1,142 @@
+//! Checks segmentation against synthetic fields whose components are known.
+
+use gray_scott_reaction_diffusion::analysis::{
+    ComponentShape, PatternClass, Segmentation, SegmentationOptions,
+};
+use gray_scott_reaction_diffusion::{Field, UVPair};
+
+/// A `width` by `height` field with `v(x, y)` in every cell.
+fn field(width: usize, height: usize, v: impl Fn(f32, f32) -> f32) -> Field {
+    let data = (0..width * height)
+        .map(|i| UVPair {
+            u: 1.0,
+            v: v((i % width) as f32, (i / width) as f32),
+        })
+        .collect();
+    Field::new(width, height, 0, data)
+}
+
+/// Whether `(x, y)` is within `radius` of `(cx, cy)` on a periodic grid.
+fn in_disc(size: f32, (x, y): (f32, f32), (cx, cy): (f32, f32), radius: f32) -> bool {
+    let wrap = |d: f32| d.abs().min(size - d.abs());
+    wrap(x - cx).hypot(wrap(y - cy)) <= radius
+}
+
+fn segment(field: &Field) -> Segmentation {
+    Segmentation::of_v(field, SegmentationOptions::default())
+}
+
+#[test]
+fn discs_are_spots() {
+    const SIZE: usize = 64;
+    // The last disc straddles the left and right edges
+    let centres = [
+        (12.0, 12.0),
+        (40.0, 16.0),
+        (20.0, 44.0),
+        (48.0, 50.0),
+        (1.0, 30.0),
+    ];
+    let discs = field(SIZE, SIZE, |x, y| {
+        let inside = centres
+            .iter()
+            .any(|&centre| in_disc(SIZE as f32, (x, y), centre, 4.5));
+        if inside { 0.3 } else { 0.0 }
+    });
+
+    let segmentation = segment(&discs);
+    assert_eq!(segmentation.count(), centres.len());
+    assert_eq!(segmentation.classify(), PatternClass::Spots);
+    for component in &segmentation.components {
+        assert_eq!(component.shape, ComponentShape::Spot, "{:?}", component);
+        assert!(!component.wraps);
+        assert!(component.elongation < 1.1, "{:?}", component);
+        let (x, y) = component.centroid;
+        assert!(
+            centres
+                .iter()
+                .any(|&centre| in_disc(SIZE as f32, (x, y), centre, 0.01)),
+            "No disc is centred on {:?}",
+            component.centroid
+        );
+    }
+    let areas = segmentation.area_statistics().unwrap();
+    assert_eq!((areas.min, areas.max), (69, 69));
+
+    // Cut out of a larger grid, the disc on the edge is two halves
+    let cut = Segmentation::of_v(
+        &discs,
+        SegmentationOptions {
+            periodic: false,
+            ..Default::default()
+        },
+    );
+    assert_eq!(cut.count(), centres.len() + 1);
+}
+
+#[test]
+fn stripe_around_the_grid_is_one_component() {
+    const WIDTH: usize = 48;
+    const HEIGHT: usize = 32;
+    // A band four cells wide, split by the top and bottom edges
+    let stripe = field(WIDTH, HEIGHT, |_, y| {
+        if !(2.0..30.0).contains(&y) { 0.5 } else { 0.0 }
+    });
+
+    let segmentation = segment(&stripe);
+    assert_eq!(segmentation.count(), 1);
+    let component = &segmentation.components[0];
+    assert!(component.wraps);
+    assert_eq!(component.elongation, f32::INFINITY);
+    assert_eq!(component.shape, ComponentShape::Stripe);
+    assert_eq!(component.area, 4 * WIDTH);
+    assert_eq!(component.perimeter, 2 * WIDTH);
+    assert!(
+        (component.centroid.1 - 0.5).abs() < 0.01 || (component.centroid.1 - 31.5).abs() < 0.01,
+        "{:?}",
+        component.centroid
+    );
+    assert_eq!(segmentation.classify(), PatternClass::Stripes);
+}
+
+#[test]
+fn crossing_stripes_are_a_labyrinth() {
+    const SIZE: usize = 32;
+    let cross = field(SIZE, SIZE, |x, y| {
+        if (8.0..11.0).contains(&x) || (20.0..23.0).contains(&y) {
+            0.5
+        } else {
+            0.0
+        }
+    });
+
+    let segmentation = segment(&cross);
+    assert_eq!(segmentation.count(), 1);
+    assert!(segmentation.components[0].wraps);
+    assert_eq!(segmentation.components[0].shape, ComponentShape::Network);
+    assert_eq!(segmentation.classify(), PatternClass::Labyrinth);
+}
+
+#[test]
+fn classifies_by_the_shape_covering_the_most_area() {
+    const SIZE: usize = 64;
+    // Specks outnumber the bars but cover less of the grid
+    let mixed = field(SIZE, SIZE, |x, y| {
+        let speck = x % 8.0 == 0.0 && y % 8.0 == 0.0 && y < 32.0;
+        let bar = (36.0..39.0).contains(&y) && (4.0..40.0).contains(&x)
+            || (50.0..53.0).contains(&y) && (10.0..50.0).contains(&x);
+        if speck || bar { 0.5 } else { 0.0 }
+    });
+    let segmentation = segment(&mixed);
+    assert_eq!(segmentation.count(), 34);
+    assert_eq!(segmentation.classify(), PatternClass::Stripes);
+
+    assert_eq!(
+        segment(&field(8, 8, |_, _| 0.1)).classify(),
+        PatternClass::Empty
+    );
+    assert_eq!(
+        segment(&field(8, 8, |_, _| 0.3)).classify(),
+        PatternClass::Uniform
+    );
+}
@@ -0,0 +1,304 @@
+//! Checks that state files survive a round trip and that corrupt ones are
+//! turned away, and that NumPy exports are well-formed archives.
+
+mod common;
+
+use gray_scott_reaction_diffusion::snapshot::{MAGIC, Snapshot, VERSION};
+use gray_scott_reaction_diffusion::{
+    NutrientGraph, NutrientMap, NutrientMapping, NutrientParams, NutrientPattern,
+};
+use std::io;
+
+const WIDTH: usize = 7;
+const HEIGHT: usize = 5;
+
+fn snapshot() -> Snapshot {
+    let map = NutrientMap::from_values(3, 2, vec![0.0, 0.25, 0.5, 0.75, 1.0, 0.5]).unwrap();
+    let params = NutrientParams {
+        scale: 2.0,
+        orientation: 0.5,
+        is_reversed: true,
+        speed: -0.5,
+        ..NutrientPattern::ScrollingNoise.default_params()
+    };
+    let nutrient = (NutrientGraph::image(map) * NutrientPattern::Checkerboard.into()).blend(
+        NutrientGraph::with_params(NutrientPattern::ScrollingNoise, params),
+        NutrientPattern::RadialGradient,
+    );
+    Snapshot {
+        width: WIDTH,
+        height: HEIGHT,
+        feed_rate: 0.0367,
+        kill_rate: 0.0649,
+        delta_u: 0.2097,
+        delta_v: 0.105,
+        nutrient,
+        nutrient_mapping: NutrientMapping::parse("feed=1,kill=0.1,dv=0.5").unwrap(),
+        step_count: 12345,
+        seed: 67890,
+        uvs: (0..WIDTH * HEIGHT)
+            .map(|i| (i as f32 / 35.0, 1.0 - i as f32 / 35.0))
+            .collect(),
+    }
+}
+
+/// The start of a state file declaring a `width` by `height` grid.
+fn header(version: u32, width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    for value in [version, width, height] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+fn read_error(bytes: &[u8]) -> io::Error {
+    Snapshot::read_from(bytes).expect_err("A corrupt state file was read")
+}
+
+#[test]
+fn round_trips_through_bytes() {
+    let snapshot = snapshot();
+    let mut bytes = Vec::new();
+    snapshot.write_to(&mut bytes).unwrap();
+    assert_eq!(Snapshot::read_from(bytes.as_slice()).unwrap(), snapshot);
+}
+
+#[test]
+fn round_trips_through_a_file() {
+    let snapshot = snapshot();
+    let path = std::env::temp_dir().join(format!("snapshot-test-{}.gsrd", std::process::id()));
+    snapshot.save(&path).unwrap();
+    let loaded = Snapshot::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap(), snapshot);
+}
+
+#[test]
+fn loads_into_a_system_of_another_size_and_saves_back() {
+    let Some(mut system) = common::gpu_system(3, 4) else {
+        return;
+    };
+    let snapshot = snapshot();
+    let path = std::env::temp_dir().join(format!("snapshot-gpu-{}.gsrd", std::process::id()));
+    snapshot.save(&path).unwrap();
+    let loaded = system.load_state(&path);
+    let saved = loaded.and_then(|()| system.save_state(&path));
+    let reloaded = Snapshot::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    saved.unwrap();
+
+    assert_eq!((system.width, system.height), (WIDTH, HEIGHT));
+    assert_eq!(reloaded.unwrap(), snapshot);
+}
+
+/// A nutrient graph with more nodes than the GPU can evaluate.
+fn oversized_graph() -> NutrientGraph {
+    (0..20)
+        .map(|_| NutrientGraph::from(NutrientPattern::Checkerboard))
+        .reduce(|a, b| a + b)
+        .unwrap()
+}
+
+#[test]
+fn refuses_to_write_snapshots_it_couldnt_read() {
+    let short = Snapshot {
+        uvs: vec![(1.0, 0.0); WIDTH * HEIGHT - 1],
+        ..snapshot()
+    };
+    let empty = Snapshot {
+        width: 0,
+        height: 0,
+        uvs: Vec::new(),
+        ..snapshot()
+    };
+    let oversized = Snapshot {
+        nutrient: oversized_graph(),
+        ..snapshot()
+    };
+    let infinite = Snapshot {
+        nutrient_mapping: NutrientMapping {
+            feed: f32::INFINITY,
+            ..NutrientMapping::FEED
+        },
+        ..snapshot()
+    };
+    for snapshot in [short, empty, oversized, infinite] {
+        let mut bytes = Vec::new();
+        let error = snapshot.write_to(&mut bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{}", error);
+        assert!(bytes.is_empty());
+    }
+}
+
+#[test]
+fn rejected_snapshot_leaves_the_system_alone() {
+    let Some(mut system) = common::gpu_system(3, 4) else {
+        return;
+    };
+    let values: Vec<(f32, f32)> = (0..12).map(|i| (1.0 - i as f32 / 24.0, 0.25)).collect();
+    system.set_all(&values);
+    system.update_params(0.03, 0.06, 0.9, 0.45);
+    let before = system.snapshot();
+
+    for snapshot in [
+        Snapshot {
+            nutrient: oversized_graph(),
+            ..snapshot()
+        },
+        Snapshot {
+            nutrient_mapping: NutrientMapping {
+                delta_v: f32::NAN,
+                ..NutrientMapping::FEED
+            },
+            ..snapshot()
+        },
+        Snapshot {
+            uvs: Vec::new(),
+            ..snapshot()
+        },
+    ] {
+        let error = system.restore(&snapshot).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", error);
+        assert_eq!(system.snapshot(), before);
+    }
+}
+
+#[test]
+fn rejects_other_versions() {
+    let mut bytes = Vec::new();
+    snapshot().write_to(&mut bytes).unwrap();
+    bytes[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+    assert_eq!(read_error(&bytes).kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn rejects_huge_and_empty_grids_from_the_header() {
+    for (width, height) in [(65535, 65535), (u32::MAX, 2), (0, 10)] {
+        let error = read_error(&header(VERSION, width, height));
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", error);
+    }
+}
+
+#[test]
+fn rejects_truncated_files() {
+    let mut bytes = Vec::new();
+    snapshot().write_to(&mut bytes).unwrap();
+    for len in [bytes.len() - 1, bytes.len() / 2, 10] {
+        assert!(Snapshot::read_from(&bytes[..len]).is_err());
+    }
+    // A plausible header followed by nothing
+    let error = read_error(&header(VERSION, 2048, 2048));
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+/// Reads a `.npy` array of little-endian `f32`s, returning its shape and
+/// values.
+fn parse_npy(bytes: &[u8]) -> (String, Vec<f32>) {
+    assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+    let header_len = u16_at(bytes, 8) as usize;
+    assert_eq!((10 + header_len) % 64, 0, "The header isn't padded");
+    let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+    assert!(header.ends_with('\n'));
+    assert!(header.contains("'descr': '<f4'"));
+    assert!(header.contains("'fortran_order': False"));
+    let shape = header
+        .split("'shape': (")
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+        .unwrap()
+        .to_string();
+    let values = bytes[10 + header_len..]
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    (shape, values)
+}
+
+/// Reads an uncompressed zip archive through its central directory,
+/// checking each entry against its local header.
+fn parse_stored_zip(archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let end = archive.len() - 22;
+    assert_eq!(u32_at(archive, end), 0x0605_4b50);
+    let count = u16_at(archive, end + 10) as usize;
+    let directory_size = u32_at(archive, end + 12) as usize;
+    let directory_offset = u32_at(archive, end + 16) as usize;
+    assert_eq!(directory_offset + directory_size, end);
+
+    let mut files = Vec::new();
+    let mut entry = directory_offset;
+    for _ in 0..count {
+        assert_eq!(u32_at(archive, entry), 0x0201_4b50);
+        let crc = u32_at(archive, entry + 16);
+        let size = u32_at(archive, entry + 20) as usize;
+        let name_len = u16_at(archive, entry + 28) as usize;
+        let local = u32_at(archive, entry + 42) as usize;
+        let name = std::str::from_utf8(&archive[entry + 46..entry + 46 + name_len]).unwrap();
+
+        assert_eq!(u32_at(archive, local), 0x0403_4b50);
+        assert_eq!(u16_at(archive, local + 8), 0, "{} is compressed", name);
+        assert_eq!(u32_at(archive, local + 14), crc);
+        assert_eq!(u16_at(archive, local + 26) as usize, name_len);
+        let data_start = local + 30 + name_len + u16_at(archive, local + 28) as usize;
+        let data = &archive[data_start..data_start + size];
+        assert_eq!(crc32(data), crc, "{} has the wrong checksum", name);
+
+        files.push((name.to_string(), data.to_vec()));
+        entry += 46 + name_len;
+    }
+    assert_eq!(entry, end);
+    files
+}
+
+#[test]
+fn npz_holds_u_and_v_arrays() {
+    let snapshot = snapshot();
+    let mut archive = Vec::new();
+    snapshot.write_npz(&mut archive).unwrap();
+
+    let files = parse_stored_zip(&archive);
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["u.npy", "v.npy"]);
+    for ((_, data), component) in files.iter().zip([0, 1]) {
+        let (shape, values) = parse_npy(data);
+        assert_eq!(shape, format!("{}, {}", HEIGHT, WIDTH));
+        let expected: Vec<f32> = snapshot
+            .uvs
+            .iter()
+            .map(|&(u, v)| [u, v][component])
+            .collect();
+        assert_eq!(values, expected);
+    }
+}
+
+#[test]
+fn npy_interleaves_u_and_v() {
+    let snapshot = snapshot();
+    let mut bytes = Vec::new();
+    snapshot.write_npy(&mut bytes).unwrap();
+    let (shape, values) = parse_npy(&bytes);
+    assert_eq!(shape, format!("{}, {}, 2", HEIGHT, WIDTH));
+    let expected: Vec<f32> = snapshot.uvs.iter().flat_map(|&(u, v)| [u, v]).collect();
+    assert_eq!(values, expected);
+}
+
+#[test]
+fn crc_matches_the_standard_check_value() {
+    // Guards the checksum the archive test relies on
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+}
@@ -0,0 +1,89 @@
+//! Checks spectral analysis against synthetic fields whose wavelengths are
+//! known.
+
+use gray_scott_reaction_diffusion::analysis::{
+    PowerSpectrum, RadialProfile, SpectralAnalysis, SpectrumWindow,
+};
+use gray_scott_reaction_diffusion::{Field, UVPair};
+use std::f32::consts::TAU;
+
+/// A `width` by `height` field with `v(x, y)` in every cell.
+fn field(width: usize, height: usize, v: impl Fn(f32, f32) -> f32) -> Field {
+    let data = (0..width * height)
+        .map(|i| UVPair {
+            u: 1.0,
+            v: v((i % width) as f32, (i / width) as f32),
+        })
+        .collect();
+    Field::new(width, height, 0, data)
+}
+
+#[test]
+fn sinusoid_wavelength_is_within_a_bin() {
+    const SIZE: usize = 128;
+    // 5 and 7 cycles across the grid, a wavenumber between two bins
+    let (cycles_x, cycles_y): (f32, f32) = (5.0, 7.0);
+    let waves = field(SIZE, SIZE, |x, y| {
+        0.25 + 0.1 * (TAU * (cycles_x * x + cycles_y * y) / SIZE as f32).sin()
+    });
+
+    let analysis = SpectralAnalysis::of_v(&waves);
+    let expected = f32::hypot(cycles_x, cycles_y) / SIZE as f32;
+    let bin_width = analysis.radial_profile.bin_width;
+    assert_eq!(bin_width, 1.0 / SIZE as f32);
+    let wavenumber = analysis.dominant_wavenumber.unwrap();
+    assert!(
+        (wavenumber - expected).abs() <= bin_width,
+        "Found {} cycles per cell, expected {}",
+        wavenumber,
+        expected
+    );
+    assert!((analysis.dominant_wavelength.unwrap() - 1.0 / wavenumber).abs() < 1e-4);
+    assert!(analysis.anisotropy.ratio > 0.9, "{:?}", analysis.anisotropy);
+    let orientation = cycles_y.atan2(cycles_x);
+    assert!(
+        (analysis.anisotropy.orientation - orientation).abs() < 0.05,
+        "{:?}",
+        analysis.anisotropy
+    );
+}
+
+#[test]
+fn windowed_sinusoid_cut_from_a_grid_keeps_its_wavelength() {
+    const SIZE: usize = 96;
+    // 10 cells doesn't divide the grid, so the edges don't meet up
+    let period = 10.0;
+    let values: Vec<f32> = (0..SIZE * SIZE)
+        .map(|i| (TAU * (i % SIZE) as f32 / period).cos())
+        .collect();
+    let spectrum = PowerSpectrum::from_values(SIZE, SIZE, &values, SpectrumWindow::Hann);
+    let wavenumber = SpectralAnalysis::of_spectrum(&spectrum)
+        .dominant_wavenumber
+        .unwrap();
+    assert!(
+        (wavenumber - 1.0 / period).abs() <= 1.0 / SIZE as f32,
+        "Found {} cycles per cell",
+        wavenumber
+    );
+}
+
+#[test]
+fn peak_is_refined_between_bins() {
+    let profile = |power: Vec<f32>| RadialProfile {
+        bin_width: 0.1,
+        power,
+    };
+    // A symmetric peak stays on its bin, and a lopsided one leans to the
+    // heavier side
+    let symmetric = profile(vec![9.0, 1.0, 3.0, 5.0, 3.0, 1.0]);
+    assert!((symmetric.peak_wavenumber().unwrap() - 0.3).abs() < 1e-6);
+    let lopsided = profile(vec![9.0, 1.0, 2.0, 5.0, 4.0, 1.0]);
+    let peak = lopsided.peak_wavenumber().unwrap();
+    assert!((peak - 0.325).abs() < 1e-6, "{}", peak);
+
+    // Peaks in the first and last bins can't be refined
+    assert_eq!(profile(vec![0.0, 5.0, 1.0]).peak_wavenumber(), Some(0.1));
+    assert_eq!(profile(vec![0.0, 1.0, 5.0]).peak_wavenumber(), Some(0.2));
+    assert_eq!(profile(vec![9.0, 0.0, 0.0]).peak_wavenumber(), None);
+    assert_eq!(profile(vec![9.0]).peak_wavenumber(), None);
+}
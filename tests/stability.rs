@@ -0,0 +1,203 @@
+//! Checks the steady states, Jacobians and dispersion relations of the
+//! stability analysis, and that a Turing unstable state grows the pattern it
+//! predicts.
+
+use gray_scott_reaction_diffusion::CpuReactionDiffusionSystem;
+use gray_scott_reaction_diffusion::analysis::SpectralAnalysis;
+use gray_scott_reaction_diffusion::model_presets::{DEFAULT_DELTA_U, DEFAULT_DELTA_V};
+use gray_scott_reaction_diffusion::stability::{
+    StabilityAnalysis, SteadyState, SteadyStateStability, laplacian_symbol,
+};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+/// Rates in the narrow band where the upper steady state is Turing unstable
+/// at the default diffusion rates.
+const TURING_RATES: (f32, f32) = (0.073, 0.062);
+
+fn analysis((feed_rate, kill_rate): (f32, f32)) -> StabilityAnalysis {
+    StabilityAnalysis::new(feed_rate, kill_rate, DEFAULT_DELTA_U, DEFAULT_DELTA_V)
+}
+
+fn turing_unstable_state(analysis: &StabilityAnalysis) -> &SteadyStateStability {
+    analysis
+        .states
+        .iter()
+        .find(|s| s.is_turing_unstable())
+        .expect("No state is Turing unstable")
+}
+
+/// The reaction terms `(u', v')` of the model.
+fn reaction(u: f64, v: f64, feed_rate: f64, kill_rate: f64) -> (f64, f64) {
+    let uvv = u * v * v;
+    (
+        -uvv + feed_rate * (1.0 - u),
+        uvv - (feed_rate + kill_rate) * v,
+    )
+}
+
+fn assert_is_steady_state(state: &SteadyState, feed_rate: f64, kill_rate: f64) {
+    let (du, dv) = reaction(state.u, state.v, feed_rate, kill_rate);
+    assert!(
+        du.abs() < 1e-12 && dv.abs() < 1e-12,
+        "{:?} isn't steady",
+        state
+    );
+}
+
+/// Compares the Jacobian with central differences of the reaction terms.
+fn assert_jacobian_matches(state: &SteadyState, feed_rate: f64, kill_rate: f64) {
+    let h = 1e-6;
+    let at = |u, v| reaction(u, v, feed_rate, kill_rate);
+    let (u, v) = (state.u, state.v);
+    let (plus_u, minus_u) = (at(u + h, v), at(u - h, v));
+    let (plus_v, minus_v) = (at(u, v + h), at(u, v - h));
+    let expected = [
+        [
+            (plus_u.0 - minus_u.0) / (2.0 * h),
+            (plus_v.0 - minus_v.0) / (2.0 * h),
+        ],
+        [
+            (plus_u.1 - minus_u.1) / (2.0 * h),
+            (plus_v.1 - minus_v.1) / (2.0 * h),
+        ],
+    ];
+    for (row, expected_row) in state.jacobian.iter().zip(&expected) {
+        for (value, expected) in row.iter().zip(expected_row) {
+            assert!(
+                (value - expected).abs() < 1e-8,
+                "Jacobian {:?} differs from {:?}",
+                state.jacobian,
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn turing_unstable_state_has_a_consistent_analysis() {
+    let analysis = analysis(TURING_RATES);
+    let (f, k) = (analysis.feed_rate, analysis.kill_rate);
+    assert_eq!(analysis.states.len(), 3);
+    assert!(analysis.states[0].state.is_trivial());
+    for stability in &analysis.states {
+        assert_is_steady_state(&stability.state, f, k);
+        assert_jacobian_matches(&stability.state, f, k);
+    }
+    assert!(analysis.is_turing_unstable());
+
+    // The refined peak should land closer to the true maximum than the
+    // spacing of the sampled curve
+    let stability = turing_unstable_state(&analysis);
+    let mode = stability.most_unstable_mode.unwrap();
+    let growth_rate = |wavenumber: f64| {
+        stability.state.eigenvalues(
+            laplacian_symbol(wavenumber, 0.0),
+            analysis.delta_u,
+            analysis.delta_v,
+        )[0]
+        .re
+    };
+    let peak = (0..=500_000)
+        .map(|i| 0.5 * i as f64 / 500_000.0)
+        .max_by(|&a, &b| growth_rate(a).total_cmp(&growth_rate(b)))
+        .unwrap();
+    let sample_spacing = stability.dispersion.wavenumbers[1];
+    assert!(
+        (mode.wavenumber - peak).abs() < sample_spacing / 4.0,
+        "Refined peak {} is far from {}",
+        mode.wavenumber,
+        peak
+    );
+    assert!((mode.wavelength - 1.0 / peak).abs() < 0.1);
+    assert!(mode.growth_rate > 0.0);
+    assert_eq!(analysis.most_unstable_mode(), Some(mode));
+}
+
+#[test]
+fn turing_unstable_state_grows_its_predicted_wavelength() {
+    const SIZE: usize = 128;
+    let analysis = analysis(TURING_RATES);
+    let stability = turing_unstable_state(&analysis);
+    let mode = stability.most_unstable_mode.unwrap();
+
+    // Small noise around the uniform state, grown through the linear regime
+    let mut rng = SmallRng::seed_from_u64(1);
+    let (u, v) = (stability.state.u as f32, stability.state.v as f32);
+    let values: Vec<(f32, f32)> = (0..SIZE * SIZE)
+        .map(|_| {
+            (
+                u + rng.gen_range(-1e-4..1e-4),
+                v + rng.gen_range(-1e-4..1e-4),
+            )
+        })
+        .collect();
+    let (feed_rate, kill_rate) = TURING_RATES;
+    let mut system = CpuReactionDiffusionSystem::new(
+        SIZE,
+        SIZE,
+        feed_rate,
+        kill_rate,
+        DEFAULT_DELTA_U,
+        DEFAULT_DELTA_V,
+    );
+    system.set_all(&values);
+    system.run(600);
+
+    let spectrum = SpectralAnalysis::of_v(&system.field());
+    let grown = spectrum.dominant_wavenumber.unwrap() as f64;
+    let bin_width = spectrum.radial_profile.bin_width as f64;
+    assert!(
+        (grown - mode.wavenumber).abs() <= bin_width,
+        "Grew {} cycles per cell but {} was predicted",
+        grown,
+        mode.wavenumber
+    );
+}
+
+#[test]
+fn low_feed_high_kill_is_stable() {
+    let analysis = analysis((0.01, 0.08));
+    assert_eq!(analysis.states.len(), 1, "Only the trivial state exists");
+    let trivial = &analysis.states[0];
+    assert!(trivial.state.is_trivial());
+    assert!(trivial.is_homogeneously_stable());
+    assert!(!trivial.is_oscillatory());
+    assert_eq!(
+        trivial.homogeneous_eigenvalues.map(|e| e.re),
+        [-0.01f32 as f64, -(0.01f32 as f64 + 0.08f32 as f64)]
+    );
+    assert!(trivial.most_unstable_mode.is_none());
+    assert!(
+        trivial
+            .dispersion
+            .growth_rates
+            .iter()
+            .all(|&rate| rate < 0.0)
+    );
+    assert!(!analysis.is_turing_unstable());
+    assert!(analysis.to_string().ends_with("Not Turing unstable"));
+}
+
+#[test]
+fn saddle_node_has_one_nontrivial_state() {
+    // f = 4(f + k)², where the two nontrivial states meet. Both rates are
+    // exact in binary, so the discriminant is exactly zero
+    let analysis = analysis((0.0625, 0.0625));
+    assert_eq!(analysis.states.len(), 2);
+    let state = &analysis.states[1].state;
+    assert_eq!((state.u, state.v), (0.5, 0.25));
+    assert_is_steady_state(state, analysis.feed_rate, analysis.kill_rate);
+    assert_jacobian_matches(state, analysis.feed_rate, analysis.kill_rate);
+
+    // The states merge where the Jacobian becomes singular
+    let [[a, b], [c, d]] = state.jacobian;
+    assert_eq!(a * d - b * c, 0.0);
+    assert_eq!(analysis.states[1].homogeneous_eigenvalues[0].re, 0.0);
+}
+
+#[test]
+fn laplacian_symbol_spans_the_stencil_range() {
+    assert!(laplacian_symbol(0.0, 0.0).abs() < 1e-12);
+    assert!((laplacian_symbol(0.5, 0.5) + 1.6).abs() < 1e-12);
+}
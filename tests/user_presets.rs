@@ -0,0 +1,276 @@
+//! Checks that preset files are validated with errors naming what is wrong,
+//! and that saved presets load back.
+
+use gray_scott_reaction_diffusion::NutrientPattern;
+use gray_scott_reaction_diffusion::model_presets::{
+    self, MAX_DIFFUSION_RATE, Preset, PresetRegistry,
+};
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// A path in the temporary directory unique to this process and `name`.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("presets-test-{}-{}", std::process::id(), name))
+}
+
+/// Loads `text` as a preset file named `file_name`.
+fn load(file_name: &str, text: &str) -> io::Result<Vec<Preset>> {
+    let path = temp_path(file_name);
+    fs::write(&path, text).unwrap();
+    let presets = model_presets::load_presets(&path);
+    fs::remove_file(&path).unwrap();
+    presets
+}
+
+/// Rates that pass validation.
+const RATES: &str = "feed_rate = 0.03\nkill_rate = 0.06";
+
+/// Loads a TOML file holding one preset with `fields`, expecting an error
+/// mentioning each of `expected`.
+fn assert_rejected(file_name: &str, fields: &str, expected: &[&str]) {
+    let text = format!("[[preset]]\nname = \"Broken\"\n{}\n", fields);
+    let error = load(file_name, &text).expect_err("An invalid preset was loaded");
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    let message = error.to_string();
+    for expected in [file_name, "preset 1 \"Broken\""].iter().chain(expected) {
+        assert!(
+            message.contains(expected),
+            "{:?} lacks {:?}",
+            message,
+            expected
+        );
+    }
+}
+
+fn pulsing_spots() -> Preset {
+    Preset {
+        name: Cow::Borrowed("Pulsing Spots"),
+        description: Cow::Borrowed("Spots that swell and shrink."),
+        feed_rate: 0.025,
+        kill_rate: 0.06,
+        delta_u: 0.9,
+        delta_v: 0.45,
+        seeder: Some(Cow::Borrowed("Random Circles")),
+        nutrient_pattern: Some(NutrientPattern::RadialGradient),
+        is_nutrient_pattern_reversed: true,
+        lut: Some(Cow::Borrowed("MATPLOTLIB_bone_r")),
+    }
+}
+
+#[test]
+fn loads_the_documented_format() {
+    let toml = r#"
+        [[preset]]
+        name = "Pulsing Spots"
+        description = "Spots that swell and shrink."
+        feed_rate = 0.025
+        kill_rate = 0.06
+        delta_u = 0.9
+        delta_v = 0.45
+        seeder = "random-circles"
+        nutrient_pattern = "radial-gradient"
+        nutrient_pattern_reversed = true
+        lut = "MATPLOTLIB_bone_r"
+
+        [[preset]]
+        name = "Plain"
+        feed_rate = 0.03
+        kill_rate = 0.06
+    "#;
+    let presets = load("documented.toml", toml).unwrap();
+    assert_eq!(presets.len(), 2);
+    assert_eq!(
+        presets[0],
+        Preset {
+            seeder: Some(Cow::Borrowed("random-circles")),
+            ..pulsing_spots()
+        }
+    );
+    let plain = &presets[1];
+    assert_eq!(plain.description, "");
+    assert_eq!(
+        (plain.delta_u, plain.delta_v),
+        (
+            model_presets::DEFAULT_DELTA_U,
+            model_presets::DEFAULT_DELTA_V
+        )
+    );
+    assert_eq!((plain.seeder.as_ref(), plain.lut.as_ref()), (None, None));
+    assert_eq!(plain.nutrient_pattern, None);
+
+    let json = r#"{"preset": [{"name": "Plain", "feed_rate": 0.03, "kill_rate": 0.06}]}"#;
+    assert_eq!(load("documented.json", json).unwrap(), presets[1..]);
+}
+
+#[test]
+fn rejects_rates_out_of_range() {
+    assert_rejected(
+        "negative-feed.toml",
+        "feed_rate = -0.01\nkill_rate = 0.06",
+        &["feed_rate is -0.01", "between 0 and 0.12"],
+    );
+    assert_rejected(
+        "fast-kill.toml",
+        "feed_rate = 0.03\nkill_rate = 0.5",
+        &["kill_rate is 0.5", "between 0 and 0.12"],
+    );
+}
+
+#[test]
+fn rejects_diffusion_rates_the_simulation_is_unstable_at() {
+    let too_fast = MAX_DIFFUSION_RATE + 0.01;
+    assert_rejected(
+        "fast-diffusion.toml",
+        &format!("{}\ndelta_u = {}", RATES, too_fast),
+        &[&format!("delta_u is {}", too_fast), "at most 1.25"],
+    );
+    assert_rejected(
+        "no-diffusion.toml",
+        &format!("{}\ndelta_v = 0.0", RATES),
+        &["delta_v is 0", "above 0"],
+    );
+    assert!(
+        load(
+            "fastest-diffusion.toml",
+            &format!(
+                "[[preset]]\nname = \"Fast\"\nfeed_rate = 0.03\nkill_rate = 0.06\ndelta_u = {}\n",
+                MAX_DIFFUSION_RATE
+            ),
+        )
+        .is_ok()
+    );
+}
+
+#[test]
+fn rejects_unknown_names() {
+    assert_rejected(
+        "unknown-seeder.toml",
+        &format!("{}\nseeder = \"sprinkles\"", RATES),
+        &["unknown seeder \"sprinkles\"", "Random Circles"],
+    );
+    assert_rejected(
+        "unknown-lut.toml",
+        &format!("{}\nlut = \"rainbow\"", RATES),
+        &["unknown LUT \"rainbow\""],
+    );
+    assert_rejected(
+        "unknown-pattern.toml",
+        &format!("{}\nnutrient_pattern = \"tartan\"", RATES),
+        &["unknown nutrient pattern \"tartan\"", "Checkerboard"],
+    );
+}
+
+#[test]
+fn rejects_duplicate_names_within_a_file() {
+    let toml = r#"
+        [[preset]]
+        name = "Pulsing Spots"
+        feed_rate = 0.025
+        kill_rate = 0.06
+
+        [[preset]]
+        name = "pulsing-spots"
+        feed_rate = 0.03
+        kill_rate = 0.06
+    "#;
+    let error = load("duplicate.toml", toml).expect_err("Duplicate names were loaded");
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(
+        error
+            .to_string()
+            .contains("preset 2 \"pulsing-spots\" has the same name as preset 1 \"Pulsing Spots\""),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn rejects_malformed_files() {
+    for (file_name, text) in [
+        ("unclosed.toml", "[[preset]\nname = \"Broken\""),
+        (
+            "missing-rate.toml",
+            "[[preset]]\nname = \"Broken\"\nfeed_rate = 0.03",
+        ),
+        (
+            "unknown-field.toml",
+            "[[preset]]\nname = \"Broken\"\nfeed_rate = 0.03\nkill_rate = 0.06\ncolour = 1",
+        ),
+        ("truncated.json", "{\"preset\": [{\"name\": \"Broken\""),
+        (
+            "string-rate.json",
+            r#"{"preset": [{"name": "Broken", "feed_rate": "fast", "kill_rate": 0.06}]}"#,
+        ),
+    ] {
+        let error = load(file_name, text).expect_err("A malformed file was loaded");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", file_name);
+        assert!(error.to_string().contains(file_name), "{}", error);
+    }
+}
+
+#[test]
+fn registry_skips_invalid_files_and_replaces_by_name() {
+    let dir = temp_path("registry");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("a.toml"),
+        "[[preset]]\nname = \"mitosis\"\nfeed_rate = 0.03\nkill_rate = 0.06\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("b.json"),
+        r#"{"preset": [{"name": "Good", "feed_rate": 0.03, "kill_rate": 0.06},
+                       {"name": "Bad", "feed_rate": 0.3, "kill_rate": 0.06}]}"#,
+    )
+    .unwrap();
+    fs::write(dir.join("notes.txt"), "Not a preset file").unwrap();
+
+    let mut registry = PresetRegistry::builtin();
+    let builtin_count = registry.len();
+    let errors = registry.load(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].to_string().contains("b.json"), "{}", errors[0]);
+    assert_eq!(
+        registry.len(),
+        builtin_count,
+        "The whole of b.json is skipped"
+    );
+    assert_eq!(registry.by_name("Mitosis").unwrap().feed_rate, 0.03);
+    assert!(registry.by_name("Good").is_none());
+}
+
+#[test]
+fn saved_presets_load_back() {
+    for file_name in ["saved.toml", "saved.json"] {
+        let path = temp_path("save").join(file_name);
+        let first = pulsing_spots();
+        let second = Preset::new("Plain", "", (0.03, 0.06));
+        model_presets::save_preset(&path, &first).unwrap();
+        model_presets::save_preset(&path, &second).unwrap();
+
+        // Saving under a matching name replaces the preset in place
+        let renamed = Preset {
+            name: Cow::Borrowed("pulsing spots"),
+            feed_rate: 0.02,
+            ..first.clone()
+        };
+        model_presets::save_preset(&path, &renamed).unwrap();
+        let loaded = model_presets::load_presets(&path);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(loaded.unwrap(), [renamed, second], "{}", file_name);
+    }
+}
+
+#[test]
+fn refuses_to_save_an_invalid_preset() {
+    let path = temp_path("invalid.toml");
+    let preset = Preset::new("Too Fast", "", (0.5, 0.06));
+    let error = model_presets::save_preset(&path, &preset).expect_err("Saved an invalid preset");
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    assert!(error.to_string().contains("feed_rate is 0.5"), "{}", error);
+    assert!(!path.exists());
+}